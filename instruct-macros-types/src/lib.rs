@@ -1,21 +1,124 @@
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
+use std::cell::RefCell;
+
+pub mod validators;
+
+/// Re-exported so that the partial types the derive generates don't depend on the
+/// `serde` crate being in scope.
+pub use serde;
 
 pub trait InstructMacro {
+    /// The type that a response model which is still being generated is read into.
+    ///
+    /// The derive generates a struct with every field wrapped in an `Option` for
+    /// structs with named fields, whose nested response models are partials themselves.
+    /// Enums and tuple structs only show up once they are complete, so they are their
+    /// own partial. Hand-written impls can use `Self` as well.
+    ///
+    /// Streaming needs the partial to implement `Deserialize`, which the derived ones
+    /// do whenever the types of their fields do.
+    type Partial;
+
     fn get_info() -> InstructMacroResult;
     fn validate(&self) -> Result<(), String>;
+
+    /// The name the type is described by, without building its description.
+    ///
+    /// Defaults to the name of the type without its path or type arguments. The derive
+    /// names generic types after their type arguments as well, so `Page<User>` is
+    /// `Page_User`.
+    fn schema_name() -> String {
+        let name = std::any::type_name::<Self>();
+        let name = name.split('<').next().unwrap_or(name);
+        name.rsplit("::").next().unwrap_or(name).to_string()
+    }
+}
+
+/// A boxed value is described and validated like the value itself, which is how
+/// recursive types hold themselves.
+impl<T: InstructMacro> InstructMacro for Box<T> {
+    type Partial = T::Partial;
+
+    fn get_info() -> InstructMacroResult {
+        T::get_info()
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        T::validate(self)
+    }
+
+    fn schema_name() -> String {
+        T::schema_name()
+    }
+}
+
+/// A set of response models the model can answer with at the same time, each one
+/// offered as its own tool.
+///
+/// Derive it on an enum whose variants each wrap a single `InstructMacro` struct. Each
+/// variant becomes a tool named after the variant.
+pub trait ParallelTools: Sized {
+    /// The response model of every tool, named after the tool.
+    fn get_tools() -> Vec<Parameter>;
+    /// Builds the value for a call to the tool `name` from its JSON arguments.
+    fn from_tool_call(name: &str, arguments: &str) -> Result<Self, String>;
+    fn validate(&self) -> Result<(), String>;
+}
+
+/// Deserializes the JSON arguments of a tool call. Used by the `ParallelTools` derive.
+pub fn from_tool_arguments<T: DeserializeOwned>(arguments: &str) -> Result<T, String> {
+    serde_json::from_str(arguments).map_err(|e| e.to_string())
+}
+
+/// Reads a field of a generated partial, which stays `None` until the JSON that has
+/// arrived so far can be read as a `T`. Used by the `InstructMacro` derive.
+pub fn deserialize_partial_field<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned,
+{
+    let value = serde_json::Value::deserialize(deserializer)?;
+    Ok(serde_json::from_value(value).ok())
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum InstructMacroResult {
     Struct(StructInfo),
     Enum(EnumInfo),
+    Union(UnionInfo),
+    /// A type described by a single parameter rather than by fields or variants of its
+    /// own, like a newtype around a primitive or a tuple struct.
+    Parameter(Parameter),
+}
+
+impl From<Parameter> for InstructMacroResult {
+    fn from(parameter: Parameter) -> Self {
+        match parameter {
+            Parameter::Struct(struct_info) => InstructMacroResult::Struct(struct_info),
+            Parameter::Enum(enum_info) => InstructMacroResult::Enum(enum_info),
+            Parameter::Union(union_info) => InstructMacroResult::Union(union_info),
+            parameter => InstructMacroResult::Parameter(parameter),
+        }
+    }
 }
 
 impl InstructMacroResult {
+    /// The name of the described type, or of the field it was wrapped into.
+    pub fn name(&self) -> &str {
+        match self {
+            InstructMacroResult::Struct(struct_info) => &struct_info.name,
+            InstructMacroResult::Enum(enum_info) => &enum_info.title,
+            InstructMacroResult::Union(union_info) => &union_info.name,
+            InstructMacroResult::Parameter(parameter) => parameter.name(),
+        }
+    }
+
     pub fn wrap_info(self, new_name: String) -> Parameter {
         match self {
             InstructMacroResult::Struct(struct_info) => struct_info.wrap_info(new_name),
             InstructMacroResult::Enum(enum_info) => enum_info.wrap_info(new_name),
+            InstructMacroResult::Union(union_info) => union_info.wrap_info(new_name),
+            InstructMacroResult::Parameter(parameter) => parameter.wrap_info(new_name),
         }
     }
 
@@ -27,6 +130,12 @@ impl InstructMacroResult {
             InstructMacroResult::Enum(enum_info) => {
                 InstructMacroResult::Enum(enum_info.override_description(new_description))
             }
+            InstructMacroResult::Union(union_info) => {
+                InstructMacroResult::Union(union_info.override_description(new_description))
+            }
+            InstructMacroResult::Parameter(parameter) => {
+                InstructMacroResult::Parameter(parameter.override_description(new_description))
+            }
         }
     }
 
@@ -38,6 +147,12 @@ impl InstructMacroResult {
             InstructMacroResult::Enum(enum_info) => {
                 InstructMacroResult::Enum(enum_info.set_optional(is_optional))
             }
+            InstructMacroResult::Union(union_info) => {
+                InstructMacroResult::Union(union_info.set_optional(is_optional))
+            }
+            InstructMacroResult::Parameter(parameter) => {
+                InstructMacroResult::Parameter(parameter.set_optional(is_optional))
+            }
         }
     }
 
@@ -49,6 +164,12 @@ impl InstructMacroResult {
             InstructMacroResult::Enum(enum_info) => {
                 InstructMacroResult::Enum(enum_info.set_list(is_list))
             }
+            InstructMacroResult::Union(union_info) => {
+                InstructMacroResult::Union(union_info.set_list(is_list))
+            }
+            InstructMacroResult::Parameter(parameter) => {
+                InstructMacroResult::Parameter(parameter.set_list(is_list))
+            }
         }
     }
 }
@@ -56,8 +177,14 @@ impl InstructMacroResult {
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct StructInfo {
     pub name: String,
+    /// The name of the struct type, which stays the same when the struct is wrapped into
+    /// a field. Empty for the fields of an enum variant, which aren't a type of their own.
+    pub r#type: String,
     pub description: String,
     pub parameters: Vec<Parameter>,
+    /// Whether deserialization rejects properties the struct doesn't have, from
+    /// `#[serde(deny_unknown_fields)]`.
+    pub deny_unknown_fields: bool,
     pub is_optional: bool,
     pub is_list: bool,
 }
@@ -69,7 +196,7 @@ impl StructInfo {
     }
 
     pub fn override_description(mut self, new_description: String) -> StructInfo {
-        if new_description.len() > 0 {
+        if !new_description.is_empty() {
             self.description = new_description;
         }
         self
@@ -91,6 +218,170 @@ pub enum Parameter {
     Struct(StructInfo),
     Field(ParameterInfo),
     Enum(EnumInfo),
+    Union(UnionInfo),
+    List(ListInfo),
+    Map(MapInfo),
+    Tuple(TupleInfo),
+    Ref(RefInfo),
+}
+
+impl Parameter {
+    pub fn name(&self) -> &str {
+        match self {
+            Parameter::Struct(struct_info) => &struct_info.name,
+            Parameter::Field(field_info) => &field_info.name,
+            Parameter::Enum(enum_info) => &enum_info.title,
+            Parameter::Union(union_info) => &union_info.name,
+            Parameter::List(list_info) => &list_info.name,
+            Parameter::Map(map_info) => &map_info.name,
+            Parameter::Tuple(tuple_info) => &tuple_info.name,
+            Parameter::Ref(ref_info) => &ref_info.name,
+        }
+    }
+
+    pub fn description(&self) -> &str {
+        match self {
+            Parameter::Struct(struct_info) => &struct_info.description,
+            Parameter::Field(field_info) => &field_info.comment,
+            Parameter::Enum(enum_info) => &enum_info.description,
+            Parameter::Union(union_info) => &union_info.description,
+            Parameter::List(list_info) => &list_info.description,
+            Parameter::Map(map_info) => &map_info.description,
+            Parameter::Tuple(tuple_info) => &tuple_info.description,
+            Parameter::Ref(ref_info) => &ref_info.description,
+        }
+    }
+
+    pub fn is_optional(&self) -> bool {
+        match self {
+            Parameter::Struct(struct_info) => struct_info.is_optional,
+            Parameter::Field(field_info) => field_info.is_optional,
+            Parameter::Enum(enum_info) => enum_info.is_optional,
+            Parameter::Union(union_info) => union_info.is_optional,
+            Parameter::List(list_info) => list_info.is_optional,
+            Parameter::Map(map_info) => map_info.is_optional,
+            Parameter::Tuple(tuple_info) => tuple_info.is_optional,
+            Parameter::Ref(ref_info) => ref_info.is_optional,
+        }
+    }
+
+    pub fn wrap_info(mut self, new_name: String) -> Parameter {
+        match &mut self {
+            Parameter::Struct(struct_info) => struct_info.name = new_name,
+            Parameter::Field(field_info) => field_info.name = new_name,
+            Parameter::Enum(enum_info) => enum_info.title = new_name,
+            Parameter::Union(union_info) => union_info.name = new_name,
+            Parameter::List(list_info) => list_info.name = new_name,
+            Parameter::Map(map_info) => map_info.name = new_name,
+            Parameter::Tuple(tuple_info) => tuple_info.name = new_name,
+            Parameter::Ref(ref_info) => ref_info.name = new_name,
+        }
+        self
+    }
+
+    pub fn override_description(mut self, new_description: String) -> Parameter {
+        if new_description.is_empty() {
+            return self;
+        }
+        match &mut self {
+            Parameter::Struct(struct_info) => struct_info.description = new_description,
+            Parameter::Field(field_info) => field_info.comment = new_description,
+            Parameter::Enum(enum_info) => enum_info.description = new_description,
+            Parameter::Union(union_info) => union_info.description = new_description,
+            Parameter::List(list_info) => list_info.description = new_description,
+            Parameter::Map(map_info) => map_info.description = new_description,
+            Parameter::Tuple(tuple_info) => tuple_info.description = new_description,
+            Parameter::Ref(ref_info) => ref_info.description = new_description,
+        }
+        self
+    }
+
+    pub fn set_optional(mut self, is_optional: bool) -> Parameter {
+        match &mut self {
+            Parameter::Struct(struct_info) => struct_info.is_optional = is_optional,
+            Parameter::Field(field_info) => field_info.is_optional = is_optional,
+            Parameter::Enum(enum_info) => enum_info.is_optional = is_optional,
+            Parameter::Union(union_info) => union_info.is_optional = is_optional,
+            Parameter::List(list_info) => list_info.is_optional = is_optional,
+            Parameter::Map(map_info) => map_info.is_optional = is_optional,
+            Parameter::Tuple(tuple_info) => tuple_info.is_optional = is_optional,
+            Parameter::Ref(ref_info) => ref_info.is_optional = is_optional,
+        }
+        self
+    }
+
+    /// Makes the parameter a list of itself. Parameters that are already lists, or that
+    /// can't be marked as one, are wrapped in a `Parameter::List`.
+    pub fn set_list(self, is_list: bool) -> Parameter {
+        if !is_list {
+            return self;
+        }
+        match self {
+            Parameter::Struct(struct_info) if !struct_info.is_list => {
+                Parameter::Struct(struct_info.set_list(true))
+            }
+            Parameter::Field(mut field_info) if !field_info.is_list => {
+                field_info.is_list = true;
+                Parameter::Field(field_info)
+            }
+            Parameter::Enum(enum_info) if !enum_info.is_list => {
+                Parameter::Enum(enum_info.set_list(true))
+            }
+            Parameter::Union(union_info) if !union_info.is_list => {
+                Parameter::Union(union_info.set_list(true))
+            }
+            Parameter::Ref(mut ref_info) if !ref_info.is_list => {
+                ref_info.is_list = true;
+                Parameter::Ref(ref_info)
+            }
+            parameter => parameter.into_list(Vec::new()),
+        }
+    }
+
+    /// Adds the constraints of built-in validators. Lists of structs, enums, unions and
+    /// references become a `Parameter::List`, so the constraints can describe the list.
+    /// Other structs, enums, maps and tuples can't carry constraints and are left as
+    /// they are.
+    pub fn add_constraints(self, constraints: Vec<Constraint>) -> Parameter {
+        if constraints.is_empty() {
+            return self;
+        }
+        match self {
+            Parameter::Field(mut field_info) => {
+                field_info.constraints.extend(constraints);
+                Parameter::Field(field_info)
+            }
+            Parameter::List(mut list_info) => {
+                list_info.constraints.extend(constraints);
+                Parameter::List(list_info)
+            }
+            Parameter::Struct(struct_info) if struct_info.is_list => {
+                Parameter::Struct(struct_info.set_list(false)).into_list(constraints)
+            }
+            Parameter::Enum(enum_info) if enum_info.is_list => {
+                Parameter::Enum(enum_info.set_list(false)).into_list(constraints)
+            }
+            Parameter::Union(union_info) if union_info.is_list => {
+                Parameter::Union(union_info.set_list(false)).into_list(constraints)
+            }
+            Parameter::Ref(mut ref_info) if ref_info.is_list => {
+                ref_info.is_list = false;
+                Parameter::Ref(ref_info).into_list(constraints)
+            }
+            parameter => parameter,
+        }
+    }
+
+    fn into_list(self, constraints: Vec<Constraint>) -> Parameter {
+        Parameter::List(ListInfo {
+            name: self.name().to_string(),
+            description: self.description().to_string(),
+            is_optional: self.is_optional(),
+            length: None,
+            constraints,
+            items: Box::new(self.set_optional(false)),
+        })
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
@@ -98,16 +389,48 @@ pub struct ParameterInfo {
     pub name: String,
     pub r#type: String,
     pub comment: String,
+    /// The constraints of the field's built-in validators. For a list, they apply to
+    /// every item, except for the length of the list itself.
+    pub constraints: Vec<Constraint>,
     pub is_optional: bool,
     pub is_list: bool,
 }
 
+/// What a built-in `#[validate(...)]` validator requires of a field. It is checked by
+/// `validate()` and described in the schema, so the model knows about it up front.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum Constraint {
+    /// `length(min = .., max = ..)`: the number of characters of a string, or of items
+    /// of a list.
+    Length {
+        min: Option<usize>,
+        max: Option<usize>,
+    },
+    /// `non_empty`: a string or list with at least one character or item.
+    NonEmpty,
+    /// `range(min = .., max = ..)`: the bounds of a number, both inclusive.
+    Range { min: Option<f64>, max: Option<f64> },
+    /// `regex = "..."`: a pattern the string has to match somewhere.
+    Pattern(String),
+    /// `email`: an email address.
+    Email,
+    /// `url`: a URL with a scheme, like `https://example.com`.
+    Url,
+    /// `contains = "..."`: a substring the string has to contain.
+    Contains(String),
+    /// `one_of(..)`: the only values the field can take, strings or numbers.
+    OneOf(Vec<serde_json::Value>),
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct EnumInfo {
     pub title: String,
     pub r#enum: Vec<String>,
     pub r#type: String,
     pub description: String,
+    /// Every variant of `r#enum`, by the name it is serialized with, with its own
+    /// description.
+    pub variants: Vec<VariantInfo>,
     pub is_optional: bool,
     pub is_list: bool,
 }
@@ -119,7 +442,7 @@ impl EnumInfo {
     }
 
     pub fn override_description(mut self, new_description: String) -> EnumInfo {
-        if new_description.len() > 0 {
+        if !new_description.is_empty() {
             self.description = new_description;
         }
         self
@@ -136,6 +459,145 @@ impl EnumInfo {
     }
 }
 
+/// A list around a parameter that can't be marked with `is_list`: lists of lists like
+/// `Vec<Vec<T>>`, lists of maps or tuples, lists of `Option`s and fixed-size arrays
+/// like `[T; N]`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ListInfo {
+    pub name: String,
+    pub description: String,
+    /// The items, which are optional when they may be `null`, as in `Vec<Option<T>>`.
+    pub items: Box<Parameter>,
+    /// The number of items of a fixed-size array.
+    pub length: Option<usize>,
+    /// The constraints of the field's built-in validators, like its length.
+    pub constraints: Vec<Constraint>,
+    pub is_optional: bool,
+}
+
+/// A tuple like `(f64, f64)` or a tuple struct, with one parameter per element.
+/// Elements that may be `null` are optional.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TupleInfo {
+    pub name: String,
+    pub description: String,
+    pub items: Vec<Parameter>,
+    pub is_optional: bool,
+}
+
+/// A map with string keys, like `HashMap<String, T>`. `values` describes every value,
+/// and is optional when the values may be `null`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MapInfo {
+    pub name: String,
+    pub description: String,
+    pub values: Box<Parameter>,
+    pub is_optional: bool,
+}
+
+/// An enum with variants that carry data, described as one alternative per variant.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UnionInfo {
+    pub name: String,
+    /// The name of the enum type, which stays the same when the union is wrapped into a
+    /// field.
+    pub r#type: String,
+    pub description: String,
+    pub variants: Vec<VariantInfo>,
+    pub tagging: UnionTagging,
+    pub is_optional: bool,
+    pub is_list: bool,
+}
+
+impl UnionInfo {
+    pub fn wrap_info(mut self, new_name: String) -> Parameter {
+        self.name = new_name;
+        Parameter::Union(self)
+    }
+
+    pub fn override_description(mut self, new_description: String) -> UnionInfo {
+        if !new_description.is_empty() {
+            self.description = new_description;
+        }
+        self
+    }
+
+    pub fn set_optional(mut self, is_optional: bool) -> UnionInfo {
+        self.is_optional = is_optional;
+        self
+    }
+
+    pub fn set_list(mut self, is_list: bool) -> UnionInfo {
+        self.is_list = is_list;
+        self
+    }
+}
+
+/// A reference to a struct or enum that is already being described further up, which is
+/// how recursive types like `struct Comment { replies: Vec<Comment> }` are described.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RefInfo {
+    pub name: String,
+    pub description: String,
+    /// The name of the referenced type.
+    pub r#type: String,
+    pub is_optional: bool,
+    pub is_list: bool,
+}
+
+thread_local! {
+    static DESCRIBING: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Describes the type named `r#type` with `describe`. If the type is already being
+/// described further up, it refers to that description instead, so recursive types
+/// don't describe themselves forever. Used by the `InstructMacro` derive.
+pub fn describe_type(
+    r#type: String,
+    describe: impl FnOnce() -> InstructMacroResult,
+) -> InstructMacroResult {
+    if DESCRIBING.with(|describing| describing.borrow().contains(&r#type)) {
+        return InstructMacroResult::Parameter(Parameter::Ref(RefInfo {
+            name: r#type.clone(),
+            description: "".to_string(),
+            r#type,
+            is_optional: false,
+            is_list: false,
+        }));
+    }
+
+    DESCRIBING.with(|describing| describing.borrow_mut().push(r#type));
+    let info = describe();
+    DESCRIBING.with(|describing| describing.borrow_mut().pop());
+    info
+}
+
+/// A single variant of a [`UnionInfo`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct VariantInfo {
+    /// The name the variant is serialized with.
+    pub name: String,
+    pub description: String,
+    /// What the variant carries: a struct for named fields, the wrapped type for a
+    /// newtype variant and `None` for a unit variant.
+    pub content: Option<Parameter>,
+}
+
+/// How the variants of a union are told apart in JSON, following serde's enum
+/// representations.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub enum UnionTagging {
+    /// `{"Variant": {...}}`, serde's default.
+    #[default]
+    External,
+    /// `{"tag": "Variant", ...}`, from `#[serde(tag = "...")]`.
+    Internal { tag: String },
+    /// `{"tag": "Variant", "content": {...}}`, from `#[serde(tag = "...", content = "...")]`.
+    Adjacent { tag: String, content: String },
+    /// The variant's content alone, from `#[serde(untagged)]`.
+    Untagged,
+}
+
 pub struct FieldInfo {
     pub name: String,
     pub description: String,
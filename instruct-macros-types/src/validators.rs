@@ -0,0 +1,487 @@
+//! The built-in validators of `#[validate(...)]`, called by the `validate()` of the
+//! `InstructMacro` derive for every field that uses them.
+//!
+//! Validators check every item of a list and skip an `Option` that is `None`, except
+//! for `length` and `non_empty`, which look at the list itself.
+
+use regex::Regex;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+/// A value with a length: the characters of a string or the items of a collection.
+pub trait Length {
+    /// The length, or `None` when there is no value, like an `Option` that is `None`.
+    fn length(&self) -> Option<usize>;
+}
+
+/// The strings the text validators check: the value itself, or every item of a list.
+pub trait Texts {
+    fn texts(&self) -> Vec<&str>;
+}
+
+/// The numbers `range` and `one_of` check: the value itself, or every item of a list.
+pub trait Numbers {
+    fn numbers(&self) -> Vec<f64>;
+}
+
+impl Length for str {
+    fn length(&self) -> Option<usize> {
+        Some(self.chars().count())
+    }
+}
+
+impl Length for String {
+    fn length(&self) -> Option<usize> {
+        self.as_str().length()
+    }
+}
+
+impl<T> Length for [T] {
+    fn length(&self) -> Option<usize> {
+        Some(self.len())
+    }
+}
+
+impl<T> Length for Vec<T> {
+    fn length(&self) -> Option<usize> {
+        Some(self.len())
+    }
+}
+
+impl<T, const N: usize> Length for [T; N] {
+    fn length(&self) -> Option<usize> {
+        Some(N)
+    }
+}
+
+impl<K, V, S> Length for HashMap<K, V, S> {
+    fn length(&self) -> Option<usize> {
+        Some(self.len())
+    }
+}
+
+impl<K, V> Length for BTreeMap<K, V> {
+    fn length(&self) -> Option<usize> {
+        Some(self.len())
+    }
+}
+
+impl<T, S> Length for HashSet<T, S> {
+    fn length(&self) -> Option<usize> {
+        Some(self.len())
+    }
+}
+
+impl<T> Length for BTreeSet<T> {
+    fn length(&self) -> Option<usize> {
+        Some(self.len())
+    }
+}
+
+impl<T: Length + ?Sized> Length for &T {
+    fn length(&self) -> Option<usize> {
+        (**self).length()
+    }
+}
+
+impl<T: Length + ?Sized> Length for Box<T> {
+    fn length(&self) -> Option<usize> {
+        (**self).length()
+    }
+}
+
+impl<T: Length> Length for Option<T> {
+    fn length(&self) -> Option<usize> {
+        self.as_ref().and_then(Length::length)
+    }
+}
+
+impl Texts for str {
+    fn texts(&self) -> Vec<&str> {
+        vec![self]
+    }
+}
+
+impl Texts for String {
+    fn texts(&self) -> Vec<&str> {
+        vec![self.as_str()]
+    }
+}
+
+impl<T: Texts> Texts for [T] {
+    fn texts(&self) -> Vec<&str> {
+        self.iter().flat_map(Texts::texts).collect()
+    }
+}
+
+impl<T: Texts> Texts for Vec<T> {
+    fn texts(&self) -> Vec<&str> {
+        self.as_slice().texts()
+    }
+}
+
+impl<T: Texts, const N: usize> Texts for [T; N] {
+    fn texts(&self) -> Vec<&str> {
+        self.as_slice().texts()
+    }
+}
+
+impl<T: Texts + ?Sized> Texts for &T {
+    fn texts(&self) -> Vec<&str> {
+        (**self).texts()
+    }
+}
+
+impl<T: Texts + ?Sized> Texts for Box<T> {
+    fn texts(&self) -> Vec<&str> {
+        (**self).texts()
+    }
+}
+
+impl<T: Texts> Texts for Option<T> {
+    fn texts(&self) -> Vec<&str> {
+        self.as_ref().map_or_else(Vec::new, Texts::texts)
+    }
+}
+
+macro_rules! impl_numbers {
+    ($($number:ty),*) => {
+        $(
+            impl Numbers for $number {
+                fn numbers(&self) -> Vec<f64> {
+                    vec![*self as f64]
+                }
+            }
+        )*
+    };
+}
+
+impl_numbers!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+
+impl<T: Numbers> Numbers for [T] {
+    fn numbers(&self) -> Vec<f64> {
+        self.iter().flat_map(Numbers::numbers).collect()
+    }
+}
+
+impl<T: Numbers> Numbers for Vec<T> {
+    fn numbers(&self) -> Vec<f64> {
+        self.as_slice().numbers()
+    }
+}
+
+impl<T: Numbers, const N: usize> Numbers for [T; N] {
+    fn numbers(&self) -> Vec<f64> {
+        self.as_slice().numbers()
+    }
+}
+
+impl<T: Numbers + ?Sized> Numbers for &T {
+    fn numbers(&self) -> Vec<f64> {
+        (**self).numbers()
+    }
+}
+
+impl<T: Numbers + ?Sized> Numbers for Box<T> {
+    fn numbers(&self) -> Vec<f64> {
+        (**self).numbers()
+    }
+}
+
+impl<T: Numbers> Numbers for Option<T> {
+    fn numbers(&self) -> Vec<f64> {
+        self.as_ref().map_or_else(Vec::new, Numbers::numbers)
+    }
+}
+
+pub fn length<T: Length + ?Sized>(
+    value: &T,
+    min: Option<usize>,
+    max: Option<usize>,
+) -> Result<(), String> {
+    let Some(length) = value.length() else {
+        return Ok(());
+    };
+    if let Some(min) = min.filter(|min| length < *min) {
+        return Err(format!("length must be at least {}, found {}", min, length));
+    }
+    if let Some(max) = max.filter(|max| length > *max) {
+        return Err(format!("length must be at most {}, found {}", max, length));
+    }
+    Ok(())
+}
+
+pub fn non_empty<T: Length + ?Sized>(value: &T) -> Result<(), String> {
+    match value.length() {
+        Some(0) => Err("must not be empty".to_string()),
+        _ => Ok(()),
+    }
+}
+
+pub fn range<T: Numbers + ?Sized>(
+    value: &T,
+    min: Option<f64>,
+    max: Option<f64>,
+) -> Result<(), String> {
+    for number in value.numbers() {
+        if let Some(min) = min.filter(|min| number < *min) {
+            return Err(format!("{} is less than the minimum of {}", number, min));
+        }
+        if let Some(max) = max.filter(|max| number > *max) {
+            return Err(format!("{} is greater than the maximum of {}", number, max));
+        }
+    }
+    Ok(())
+}
+
+thread_local! {
+    static PATTERNS: RefCell<HashMap<String, Regex>> = RefCell::new(HashMap::new());
+}
+
+/// Checks that `pattern` is a valid regex that means the same in the ECMA-262 dialect
+/// of a schema's `pattern`. Used by the derive to reject patterns at compile time.
+///
+/// Syntax only the `regex` crate understands is rejected: inline flags like `(?i)`,
+/// `(?P<name>...)` groups, the `\A` and `\z` anchors, Unicode classes like `\p{L}`,
+/// braced `\x{..}` and `\u{..}` escapes, `\U` escapes, POSIX classes like
+/// `[[:alpha:]]`, nested classes, class set operations (`&&`, `--`, `~~`) and a
+/// `]` right at the start of a class. `\d`, `\w`, `\s` and `\b` are still allowed,
+/// although they match Unicode in Rust and only ASCII in ECMA-262.
+pub fn check_pattern(pattern: &str) -> Result<(), String> {
+    Regex::new(pattern).map_err(|e| e.to_string())?;
+
+    let chars: Vec<char> = pattern.chars().collect();
+    let unsupported = |syntax: &str| Err(format!("{} is not supported in schema patterns", syntax));
+    let mut in_class = false;
+    let mut i = 0;
+    while i < chars.len() {
+        let next = chars.get(i + 1).copied();
+        match chars[i] {
+            '\\' => {
+                match (next, chars.get(i + 2)) {
+                    (Some(escape @ ('A' | 'z' | 'p' | 'P' | 'U')), _)
+                    | (Some(escape @ ('x' | 'u')), Some('{')) => {
+                        return unsupported(&format!("`\\{}`", escape))
+                    }
+                    _ => {}
+                }
+                i += 2;
+                continue;
+            }
+            '[' if in_class && next == Some(':') => return unsupported("A POSIX class"),
+            '[' if in_class => return unsupported("A nested class"),
+            '[' => {
+                in_class = true;
+                if next == Some('^') {
+                    i += 1;
+                }
+                if chars.get(i + 1) == Some(&']') {
+                    return unsupported("A `]` at the start of a class");
+                }
+            }
+            ']' if in_class => in_class = false,
+            operator @ ('&' | '-' | '~') if in_class && next == Some(operator) => {
+                return unsupported(&format!("The class operation `{}{}`", operator, operator))
+            }
+            '(' if !in_class && next == Some('?') => match chars.get(i + 2) {
+                Some(':' | '=' | '!') => {}
+                Some('<') if chars.get(i + 3) != Some(&'=') && chars.get(i + 3) != Some(&'!') => {}
+                Some('P') => return unsupported("A `(?P<name>...)` group"),
+                _ => return unsupported("An inline flag"),
+            },
+            _ => {}
+        }
+        i += 1;
+    }
+    Ok(())
+}
+
+pub fn regex<T: Texts + ?Sized>(value: &T, pattern: &str) -> Result<(), String> {
+    let texts = value.texts();
+    if texts.is_empty() {
+        return Ok(());
+    }
+
+    // Patterns are compiled once per thread rather than on every call
+    PATTERNS.with(|patterns| {
+        let mut patterns = patterns.borrow_mut();
+        if !patterns.contains_key(pattern) {
+            let compiled = Regex::new(pattern).map_err(|e| e.to_string())?;
+            patterns.insert(pattern.to_string(), compiled);
+        }
+        let compiled = &patterns[pattern];
+        match texts.iter().find(|text| !compiled.is_match(text)) {
+            Some(text) => Err(format!(
+                "'{}' does not match the pattern '{}'",
+                text, pattern
+            )),
+            None => Ok(()),
+        }
+    })
+}
+
+fn is_email(text: &str) -> bool {
+    let Some((local, domain)) = text.split_once('@') else {
+        return false;
+    };
+    !local.is_empty()
+        && !domain.contains('@')
+        && !text.chars().any(char::is_whitespace)
+        && domain.contains('.')
+        && domain.split('.').all(|label| !label.is_empty())
+}
+
+pub fn email<T: Texts + ?Sized>(value: &T) -> Result<(), String> {
+    match value.texts().into_iter().find(|text| !is_email(text)) {
+        Some(text) => Err(format!("'{}' is not a valid email address", text)),
+        None => Ok(()),
+    }
+}
+
+fn is_url(text: &str) -> bool {
+    let Some((scheme, rest)) = text.split_once("://") else {
+        return false;
+    };
+    let host = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        && !host.is_empty()
+        && !text.chars().any(char::is_whitespace)
+}
+
+pub fn url<T: Texts + ?Sized>(value: &T) -> Result<(), String> {
+    match value.texts().into_iter().find(|text| !is_url(text)) {
+        Some(text) => Err(format!("'{}' is not a valid URL", text)),
+        None => Ok(()),
+    }
+}
+
+pub fn contains<T: Texts + ?Sized>(value: &T, needle: &str) -> Result<(), String> {
+    match value
+        .texts()
+        .into_iter()
+        .find(|text| !text.contains(needle))
+    {
+        Some(text) => Err(format!("'{}' does not contain '{}'", text, needle)),
+        None => Ok(()),
+    }
+}
+
+pub fn one_of_texts<T: Texts + ?Sized>(value: &T, options: &[&str]) -> Result<(), String> {
+    match value
+        .texts()
+        .into_iter()
+        .find(|text| !options.contains(text))
+    {
+        Some(text) => Err(format!("'{}' must be one of: {}", text, options.join(", "))),
+        None => Ok(()),
+    }
+}
+
+pub fn one_of_numbers<T: Numbers + ?Sized>(value: &T, options: &[f64]) -> Result<(), String> {
+    match value
+        .numbers()
+        .into_iter()
+        .find(|number| !options.contains(number))
+    {
+        Some(number) => Err(format!(
+            "{} must be one of: {}",
+            number,
+            options
+                .iter()
+                .map(f64::to_string)
+                .collect::<Vec<String>>()
+                .join(", ")
+        )),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_length_and_range() {
+        assert!(length("héllo", Some(5), Some(5)).is_ok());
+        assert_eq!(
+            length(&vec![1, 2, 3], None, Some(2)).unwrap_err(),
+            "length must be at most 2, found 3"
+        );
+        assert!(length(&None::<String>, Some(1), None).is_ok());
+        assert_eq!(non_empty("").unwrap_err(), "must not be empty");
+
+        assert!(range(&Some(5u8), Some(0.0), Some(10.0)).is_ok());
+        assert_eq!(
+            range(&vec![1.5, -2.0], Some(0.0), None).unwrap_err(),
+            "-2 is less than the minimum of 0"
+        );
+    }
+
+    #[test]
+    fn test_text_validators() {
+        assert!(email("ann@example.com").is_ok());
+        for invalid in [
+            "ann",
+            "ann@example",
+            "@example.com",
+            "a b@example.com",
+            "a@@b.c",
+        ] {
+            assert!(email(invalid).is_err(), "{}", invalid);
+        }
+
+        assert!(url("https://example.com/path?q=1").is_ok());
+        for invalid in [
+            "example.com",
+            "https://",
+            "1http://example.com",
+            "http://a b",
+        ] {
+            assert!(url(invalid).is_err(), "{}", invalid);
+        }
+
+        assert!(regex(&vec!["ab12".to_string()], "^[a-z]+[0-9]+$").is_ok());
+        assert_eq!(
+            regex("12ab", "^[a-z]+").unwrap_err(),
+            "'12ab' does not match the pattern '^[a-z]+'"
+        );
+        assert!(check_pattern("[a-z").is_err());
+        for portable in [
+            r"^[a-z0-9_-]+$",
+            r"^(?:\d{3})-(?<rest>\w+)\.$",
+            r"[^\]a]\[\\p",
+            r"^\x41\u0042$",
+        ] {
+            assert!(check_pattern(portable).is_ok(), "{}", portable);
+        }
+        for (pattern, error) in [
+            ("(?i)abc", "An inline flag"),
+            ("(?x: a )", "An inline flag"),
+            ("(?P<name>a)", "A `(?P<name>...)` group"),
+            (r"\Aabc\z", "`\\A`"),
+            (r"\p{L}+", "`\\p`"),
+            (r"\x{41}", "`\\x`"),
+            ("[[:alpha:]]", "A POSIX class"),
+            ("[a[bc]]", "A nested class"),
+            ("[a-z&&b]", "The class operation `&&`"),
+            ("[]a]", "A `]` at the start of a class"),
+        ] {
+            assert_eq!(
+                check_pattern(pattern).unwrap_err(),
+                format!("{} is not supported in schema patterns", error),
+                "{}",
+                pattern
+            );
+        }
+
+        assert!(contains("rust-lang", "rust").is_ok());
+        assert!(one_of_texts(&Some("b".to_string()), &["a", "b"]).is_ok());
+        assert_eq!(
+            one_of_numbers(&3u32, &[1.0, 2.0]).unwrap_err(),
+            "3 must be one of: 1, 2"
+        );
+    }
+}
@@ -1,2 +1,6 @@
 pub mod parameters;
+pub mod serde_attributes;
+pub mod validators;
 pub use parameters::*;
+pub use serde_attributes::*;
+pub use validators::*;
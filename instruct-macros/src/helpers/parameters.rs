@@ -1,192 +1,639 @@
-use instruct_macros_types::FieldInfo;
 use quote::quote;
-use syn::{Expr, Ident, Lit};
+use syn::{Expr, GenericArgument, Lit, PathArguments, Type};
 
-fn extract_field_description(field: &syn::Field) -> String {
-    field
-        .attrs
+use super::serde_attributes::{RenameRule, SerdeField};
+use super::validators::extract_constraints;
+
+/// The description of a type, field or variant: its `#[description("...")]`, or its
+/// `///` doc comment when it has none. Doc comment lines are joined into paragraphs.
+pub fn extract_description(attrs: &[syn::Attribute]) -> String {
+    let description = attrs
         .iter()
-        .filter_map(|attr| {
-            if attr.path().is_ident("description") {
-                let meta = attr.parse_args().expect("Unable to parse attribute");
-                if let Expr::Lit(expr_lit) = &meta {
-                    if let Lit::Str(lit) = &expr_lit.lit {
-                        return Some(lit.value());
-                    }
-                }
-            }
-            None
-        })
+        .filter(|attr| attr.path().is_ident("description"))
+        .filter_map(|attr| attr.parse_args::<syn::LitStr>().ok())
+        .map(|lit| lit.value())
         .collect::<Vec<String>>()
-        .join(" ")
-}
+        .join(" ");
+    if !description.is_empty() {
+        return description;
+    }
 
-pub fn extract_parameter_information(fields: &syn::FieldsNamed) -> Vec<FieldInfo> {
-    fields
-        .named
+    let mut paragraphs = vec![Vec::new()];
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("doc")) {
+        let doc = match &attr.meta {
+            syn::Meta::NameValue(syn::MetaNameValue {
+                value:
+                    Expr::Lit(syn::ExprLit {
+                        lit: Lit::Str(lit), ..
+                    }),
+                ..
+            }) => lit.value(),
+            _ => continue,
+        };
+        // An empty `///` line has no lines at all, but still ends a paragraph
+        for line in doc.split('\n').map(str::trim) {
+            match line.is_empty() {
+                true => paragraphs.push(Vec::new()),
+                false => paragraphs.last_mut().unwrap().push(line.to_string()),
+            }
+        }
+    }
+    paragraphs
         .iter()
-        .map(|field| {
-            let field_name = &field.ident;
-            let field_comment = extract_field_description(field);
+        .filter(|lines| !lines.is_empty())
+        .map(|lines| lines.join(" "))
+        .collect::<Vec<String>>()
+        .join("\n\n")
+}
 
-            let field_type = &field.ty;
-            let serialized_field_type = quote!(#field_type).to_string();
+const SIMPLE_TYPES: [&str; 18] = [
+    "bool", "char", "f32", "f64", "i8", "i16", "i32", "i64", "i128", "isize", "str", "String",
+    "u8", "u16", "u32", "u64", "u128", "usize",
+];
 
-            let serialized_field_type = if serialized_field_type.contains("Option <")
-                || serialized_field_type.contains("Vec <")
-            {
-                serialized_field_type.replace(" ", "")
-            } else {
-                serialized_field_type
-            };
+/// The shape of a field's type once the wrappers serde sees through are peeled off.
+struct TypeShape<'a> {
+    /// Whether the outermost type is an `Option`.
+    is_optional: bool,
+    /// The lists nested around the item, outermost first.
+    lists: Vec<ListShape<'a>>,
+    item: Item<'a>,
+}
 
-            FieldInfo {
-                name: field_name
-                    .as_ref()
-                    .map_or_else(|| "Unnamed".to_string(), |ident| ident.to_string()),
-                description: field_comment,
-                r#type: serialized_field_type.clone(),
-                is_complex: is_complex_type(serialized_field_type.clone()),
-                is_optional: is_option_type(&serialized_field_type),
-                is_list: is_list_type(serialized_field_type.clone()),
-            }
-        })
-        .collect()
+/// One of the lists around an item.
+struct ListShape<'a> {
+    /// The length of a fixed-size array. `Vec<[T; 2]>` has lengths `[None, Some(2)]`.
+    length: Option<&'a Expr>,
+    /// Whether the items are `Option`s, which may be `null`.
+    nullable_items: bool,
 }
 
-fn is_list_type(field_type: String) -> bool {
-    field_type.starts_with("Vec<") && field_type.ends_with(">")
+impl<'a> ListShape<'a> {
+    fn new(length: Option<&'a Expr>) -> Self {
+        Self {
+            length,
+            nullable_items: false,
+        }
+    }
 }
 
-pub fn is_complex_type(field_type: String) -> bool {
-    let simple_types = vec![
-        "bool", "char", "f32", "f64", "i8", "i16", "i32", "i64", "i128", "isize", "str", "String",
-        "u8", "u16", "u32", "u64", "u128", "usize",
-    ];
+enum Item<'a> {
+    /// A primitive, by its unqualified name.
+    Simple(String),
+    /// Any other type, which has to implement `InstructMacro` itself.
+    Model(&'a Type),
+    /// A map with string keys, by the shape of its values.
+    Map(Box<TypeShape<'a>>),
+    /// A tuple, by the shape of each element.
+    Tuple(Vec<TypeShape<'a>>),
+}
 
-    let option_types: Vec<String> = simple_types
-        .iter()
-        .map(|&t| format!("Option<{}>", t))
-        .collect();
+/// The type arguments of `segment`, as in `HashMap<K, V>`.
+fn type_arguments(segment: &syn::PathSegment) -> Vec<&Type> {
+    match &segment.arguments {
+        PathArguments::AngleBracketed(arguments) => arguments
+            .args
+            .iter()
+            .filter_map(|argument| match argument {
+                GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
 
-    let vec_types: Vec<String> = simple_types
-        .iter()
-        .map(|&t| format!("Vec<{}>", t))
-        .collect();
+/// The single type argument of `segment`, as in `Option<T>`.
+fn single_type_argument(segment: &syn::PathSegment) -> Option<&Type> {
+    match type_arguments(segment)[..] {
+        [ty] => Some(ty),
+        _ => None,
+    }
+}
 
-    if simple_types.contains(&field_type.as_str()) {
-        return false;
+/// The value type of a `HashMap` or `BTreeMap`. JSON object keys are strings, so the
+/// keys have to be strings too.
+fn map_value_type(segment: &syn::PathSegment) -> Option<&Type> {
+    let name = segment.ident.to_string();
+    if name != "HashMap" && name != "BTreeMap" {
+        return None;
     }
 
-    if option_types.contains(&field_type) {
-        return false;
+    match type_arguments(segment)[..] {
+        [key, value, ..] => {
+            let key_shape = analyze_type(key);
+            match key_shape.item {
+                Item::Simple(key_type)
+                    if key_shape.lists.is_empty()
+                        && matches!(key_type.as_str(), "String" | "str" | "char") => {}
+                _ => panic!("Map keys must be strings, found {}", quote!(#key)),
+            }
+            Some(value)
+        }
+        _ => None,
     }
+}
 
-    if vec_types.contains(&field_type) {
-        return false;
+/// Walks a type, following qualified paths and any nesting of `Option`, `Vec`, arrays
+/// and `Box`. An `Option` inside a list makes the items of that list nullable.
+fn analyze_type(ty: &Type) -> TypeShape<'_> {
+    let mut shape = TypeShape {
+        is_optional: false,
+        lists: Vec::new(),
+        item: Item::Model(ty),
+    };
+    let mut current = ty;
+
+    loop {
+        let segment = match current {
+            Type::Path(type_path) if type_path.qself.is_none() => {
+                type_path.path.segments.last().expect("Empty type path")
+            }
+            Type::Reference(reference) => {
+                current = &reference.elem;
+                continue;
+            }
+            Type::Paren(paren) => {
+                current = &paren.elem;
+                continue;
+            }
+            Type::Group(group) => {
+                current = &group.elem;
+                continue;
+            }
+            Type::Array(array) => {
+                shape.lists.push(ListShape::new(Some(&array.len)));
+                current = &array.elem;
+                continue;
+            }
+            Type::Slice(slice) => {
+                shape.lists.push(ListShape::new(None));
+                current = &slice.elem;
+                continue;
+            }
+            Type::Tuple(tuple) if tuple.elems.is_empty() => {
+                shape.item = Item::Simple("()".to_string());
+                return shape;
+            }
+            Type::Tuple(tuple) => {
+                shape.item = Item::Tuple(tuple.elems.iter().map(analyze_type).collect());
+                return shape;
+            }
+            _ => panic!("Unsupported type: {}", quote!(#current)),
+        };
+        if let Some(value) = map_value_type(segment) {
+            shape.item = Item::Map(Box::new(analyze_type(value)));
+            return shape;
+        }
+        let name = segment.ident.to_string();
+
+        match (name.as_str(), single_type_argument(segment)) {
+            ("Option", Some(inner)) => {
+                match shape.lists.last_mut() {
+                    Some(list) => list.nullable_items = true,
+                    None => shape.is_optional = true,
+                }
+                current = inner;
+            }
+            ("Vec", Some(inner)) => {
+                shape.lists.push(ListShape::new(None));
+                current = inner;
+            }
+            ("Box", Some(inner)) => current = inner,
+            _ => {
+                shape.item =
+                    if segment.arguments.is_empty() && SIMPLE_TYPES.contains(&name.as_str()) {
+                        Item::Simple(name)
+                    } else {
+                        Item::Model(current)
+                    };
+                return shape;
+            }
+        }
     }
+}
 
-    true
+/// The type a field of type `ty` has in a generated partial. Fields that aren't an
+/// `Option` already are wrapped in one, since every field may still be missing.
+pub fn partial_field_type(ty: &Type) -> proc_macro2::TokenStream {
+    let partial = partial_type(unwrap_option(ty));
+    quote! { Option<#partial> }
 }
 
-fn is_option_type(field_type: &str) -> bool {
-    field_type.starts_with("Option<") && field_type.ends_with(">")
+/// What a field of type `ty` holds once it has arrived: the type inside the field's
+/// `Option`, with borrowed strings and arrays owned and response models left as they
+/// are. A partial can be read when these can.
+pub fn owned_field_type(ty: &Type) -> proc_macro2::TokenStream {
+    owned_type(unwrap_option(ty))
 }
 
-fn extract_nested_type(field_type: &str) -> String {
-    if is_option_type(field_type) {
-        field_type[7..field_type.len() - 1].to_string()
-    } else {
-        field_type.to_string()
+/// The partial a field of type `ty` holds once it has arrived.
+pub fn partial_value_type(ty: &Type) -> proc_macro2::TokenStream {
+    partial_type(unwrap_option(ty))
+}
+
+/// The type inside `ty` when it is an `Option`, or `ty` itself.
+fn unwrap_option(ty: &Type) -> &Type {
+    match ty {
+        Type::Path(type_path) if type_path.qself.is_none() => type_path
+            .path
+            .segments
+            .last()
+            .filter(|segment| segment.ident == "Option")
+            .and_then(single_type_argument)
+            .unwrap_or(ty),
+        _ => ty,
     }
 }
 
-pub fn extract_parameters(fields: &syn::FieldsNamed) -> Vec<proc_macro2::TokenStream> {
-    extract_parameter_information(fields)
-        .iter()
-        .map(|field| {
-            let field_name = &field.name;
-            let field_type = &field.r#type;
-            let field_comment = &field.description;
-            let is_option = is_option_type(field_type);
-            let is_list = field.is_list;
+/// The type a value of type `ty` is read into while it is still being generated.
+///
+/// Response models are read into their own partial, and the lists, maps, tuples and
+/// wrappers around them keep their shape. Borrowed strings and arrays, which can't be
+/// read from a value that is still growing, become `String` and `Vec`.
+fn partial_type(ty: &Type) -> proc_macro2::TokenStream {
+    owned_shape(ty, &|model| {
+        quote! { <#model as instruct_macros_types::InstructMacro>::Partial }
+    })
+}
 
-            
+/// `ty` with borrowed strings and arrays turned into `String` and `Vec`, the way its
+/// partial reads them, but with response models left as they are.
+fn owned_type(ty: &Type) -> proc_macro2::TokenStream {
+    owned_shape(ty, &|model| quote! { #model })
+}
 
-            if !field.is_complex {
+/// Walks the shape of `ty` the way partials read it, mapping every response model in
+/// it with `model`.
+fn owned_shape(
+    ty: &Type,
+    model: &dyn Fn(&Type) -> proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let type_path = match ty {
+        Type::Path(type_path) if type_path.qself.is_none() => type_path,
+        Type::Reference(reference) => return owned_shape(&reference.elem, model),
+        Type::Paren(paren) => return owned_shape(&paren.elem, model),
+        Type::Group(group) => return owned_shape(&group.elem, model),
+        Type::Array(array) => {
+            let item = owned_shape(&array.elem, model);
+            return quote! { Vec<#item> };
+        }
+        Type::Slice(slice) => {
+            let item = owned_shape(&slice.elem, model);
+            return quote! { Vec<#item> };
+        }
+        Type::Tuple(tuple) => {
+            let elements = tuple
+                .elems
+                .iter()
+                .map(|element| owned_shape(element, model));
+            return quote! { (#(#elements,)*) };
+        }
+        _ => panic!("Unsupported type: {}", quote!(#ty)),
+    };
+    let segment = type_path.path.segments.last().expect("Empty type path");
 
-                let field_type = if is_list {
-                    extract_nested_type(&field_type[4..field_type.len() - 1])
-                } else {
-                    field_type.to_string()
-                };
+    if let Some(value) = map_value_type(segment) {
+        let key = owned_shape(type_arguments(segment)[0], model);
+        let value = owned_shape(value, model);
+        let mut path = type_path.path.clone();
+        let last = path.segments.last_mut().expect("Empty type path");
+        last.arguments = PathArguments::None;
+        return quote! { #path<#key, #value> };
+    }
+    let name = segment.ident.to_string();
 
-                quote! {
-                    parameters.push(Parameter::Field(ParameterInfo {
-                        name: #field_name.to_string(),
-                        r#type: #field_type.to_string(),
-                        comment: #field_comment.to_string(),
-                        is_optional: #is_option,
-                        is_list: #is_list,
-                    }));
-                }
-            } else if is_option_type(field_type) {
-                let field_type = extract_nested_type(field_type);
-                let field_type = Ident::new(&field_type, proc_macro2::Span::call_site()); // Convert string to an identifier
+    match (name.as_str(), single_type_argument(segment)) {
+        ("Option", Some(inner)) => {
+            let inner = owned_shape(inner, model);
+            quote! { Option<#inner> }
+        }
+        ("Vec", Some(inner)) => {
+            let inner = owned_shape(inner, model);
+            quote! { Vec<#inner> }
+        }
+        ("Box", Some(inner)) => {
+            let inner = owned_shape(inner, model);
+            quote! { Box<#inner> }
+        }
+        ("str", _) => quote! { String },
+        _ if segment.arguments.is_empty() && SIMPLE_TYPES.contains(&name.as_str()) => {
+            quote! { #ty }
+        }
+        _ => model(ty),
+    }
+}
 
+/// The parameters of a struct or struct variant, named the way serde deserializes them.
+/// Fields serde skips are left out, and fields with a default aren't required.
+pub fn extract_parameters(
+    fields: &syn::FieldsNamed,
+    rename_all: Option<RenameRule>,
+    default: bool,
+) -> Vec<proc_macro2::TokenStream> {
+    fields
+        .named
+        .iter()
+        .filter_map(|field| {
+            let serde = SerdeField::from_attributes(&field.attrs);
+            if serde.skip {
+                return None;
+            }
+            if serde.flatten {
+                return Some(flatten_expression(field));
+            }
+
+            let ident = field.ident.as_ref().expect("Named fields have an ident");
+            let field_name = serde.name(ident, rename_all, false);
+            let parameter = parameter_expression(&field_name, field);
+            Some(if default || serde.default {
                 quote! {
-                    parameters.push(#field_type::get_info().override_description(#field_comment.to_string()).set_optional(#is_option).wrap_info(#field_name.to_string()));
+                    parameters.push(#parameter.set_optional(true));
                 }
             } else {
-                let is_list = field.is_list;
-                
-
-                let field_type = if is_list {
-                    extract_nested_type(&field_type[4..field_type.len() - 1])
-                } else {
-                    field_type.to_string()
-                };
-
-                let field_type = Ident::new(&field_type, proc_macro2::Span::call_site()); // Convert string to an identifier
-                
-                
                 quote! {
-                    parameters.push(#field_type::get_info().override_description(#field_comment.to_string()).set_optional(#is_option).set_list(#is_list).wrap_info(#field_name.to_string()));
+                    parameters.push(#parameter);
                 }
-            }
+            })
         })
         .collect()
 }
 
+/// Adds the parameters of a `#[serde(flatten)]` struct field to the parameters of the
+/// struct holding it.
+fn flatten_expression(field: &syn::Field) -> proc_macro2::TokenStream {
+    let shape = analyze_type(&field.ty);
+    let field_type = match shape.item {
+        Item::Model(field_type) if shape.lists.is_empty() => field_type,
+        _ => {
+            let field_type = &field.ty;
+            panic!(
+                "Only structs can be flattened, found {}",
+                quote!(#field_type)
+            )
+        }
+    };
+    let is_option = shape.is_optional;
+
+    quote! {
+        match <#field_type as instruct_macros_types::InstructMacro>::get_info() {
+            instruct_macros_types::InstructMacroResult::Struct(info) => {
+                parameters.extend(info.parameters.into_iter().map(|parameter| {
+                    let is_optional = parameter.is_optional() || #is_option;
+                    parameter.set_optional(is_optional)
+                }));
+            }
+            _ => panic!("Only structs can be flattened, found {}", stringify!(#field_type)),
+        }
+    }
+}
+
+/// The `Parameter` describing the single field of a newtype variant, named after the
+/// variant.
+pub fn extract_newtype_parameter(
+    variant_name: &str,
+    field: &syn::Field,
+) -> proc_macro2::TokenStream {
+    parameter_expression(variant_name, field)
+}
+
+/// The `Parameter::Tuple` describing the fields of a tuple struct, named after the
+/// struct.
+pub fn extract_tuple_parameter(
+    struct_name: &str,
+    fields: &syn::FieldsUnnamed,
+) -> proc_macro2::TokenStream {
+    let items = fields
+        .unnamed
+        .iter()
+        .map(|field| parameter_expression(struct_name, field));
+    quote! {
+        Parameter::Tuple(instruct_macros_types::TupleInfo {
+            name: #struct_name.to_string(),
+            description: "".to_string(),
+            items: vec![#(#items),*],
+            is_optional: false,
+        })
+    }
+}
+
+/// Whether the value of a newtype variant is a response model with its own validation.
+pub fn is_validated_newtype(field: &syn::Field) -> bool {
+    let shape = analyze_type(&field.ty);
+    !shape.is_optional && shape.lists.is_empty() && matches!(shape.item, Item::Model(_))
+}
+
+fn parameter_expression(field_name: &str, field: &syn::Field) -> proc_macro2::TokenStream {
+    let field_comment = extract_description(&field.attrs);
+    let shape = analyze_type(&field.ty);
+    let parameter = shape_expression(
+        field_name,
+        &field_comment,
+        shape.is_optional,
+        &shape.lists,
+        &shape.item,
+    );
+    let constraints = extract_constraints(&field.attrs);
+    if constraints.is_empty() {
+        return parameter;
+    }
+    quote! {
+        #parameter.add_constraints(vec![#(#constraints),*])
+    }
+}
+
+/// Builds the `Parameter` for an item nested in `lists`. A single `Vec` around a
+/// primitive or a model is marked with `is_list`, and every other list is a
+/// `Parameter::List`, which also carries the length of fixed-size arrays. Items that
+/// may be `null` are marked optional.
+fn shape_expression(
+    field_name: &str,
+    field_comment: &str,
+    is_option: bool,
+    lists: &[ListShape],
+    item: &Item,
+) -> proc_macro2::TokenStream {
+    let is_list = match (lists, item) {
+        ([], _) => false,
+        (
+            [ListShape {
+                length: None,
+                nullable_items: false,
+            }],
+            Item::Simple(_) | Item::Model(_),
+        ) => true,
+        _ => {
+            let length = match lists[0].length {
+                Some(length) => quote!(Some(#length)),
+                None => quote!(None),
+            };
+            let items =
+                shape_expression(field_name, "", lists[0].nullable_items, &lists[1..], item);
+            return quote! {
+                Parameter::List(instruct_macros_types::ListInfo {
+                    name: #field_name.to_string(),
+                    description: #field_comment.to_string(),
+                    items: Box::new(#items),
+                    length: #length,
+                    constraints: Vec::new(),
+                    is_optional: #is_option,
+                })
+            };
+        }
+    };
+    match item {
+        Item::Simple(field_type) => {
+            let field_type = if is_option {
+                format!("Option<{}>", field_type)
+            } else {
+                field_type.to_string()
+            };
+
+            quote! {
+                Parameter::Field(ParameterInfo {
+                    name: #field_name.to_string(),
+                    r#type: #field_type.to_string(),
+                    comment: #field_comment.to_string(),
+                    constraints: Vec::new(),
+                    is_optional: #is_option,
+                    is_list: #is_list,
+                })
+            }
+        }
+        Item::Model(field_type) => quote! {
+            <#field_type>::get_info().override_description(#field_comment.to_string()).set_optional(#is_option).set_list(#is_list).wrap_info(#field_name.to_string())
+        },
+        Item::Map(values) => {
+            let values = shape_expression(
+                field_name,
+                "",
+                values.is_optional,
+                &values.lists,
+                &values.item,
+            );
+            quote! {
+                Parameter::Map(instruct_macros_types::MapInfo {
+                    name: #field_name.to_string(),
+                    description: #field_comment.to_string(),
+                    values: Box::new(#values),
+                    is_optional: #is_option,
+                })
+            }
+        }
+        Item::Tuple(elements) => {
+            let items = elements.iter().map(|element| {
+                shape_expression(
+                    field_name,
+                    "",
+                    element.is_optional,
+                    &element.lists,
+                    &element.item,
+                )
+            });
+            quote! {
+                Parameter::Tuple(instruct_macros_types::TupleInfo {
+                    name: #field_name.to_string(),
+                    description: #field_comment.to_string(),
+                    items: vec![#(#items),*],
+                    is_optional: #is_option,
+                })
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// Whether the type is optional, whether the items of each list are nullable, and
+    /// the item.
+    fn shape_of(ty: &Type) -> (bool, Vec<bool>, String) {
+        let shape = analyze_type(ty);
+        let item = match shape.item {
+            Item::Simple(name) => name,
+            Item::Model(ty) => quote!(#ty).to_string(),
+            Item::Map(_) => "map".to_string(),
+            Item::Tuple(elements) => format!("tuple of {}", elements.len()),
+        };
+        let lists = shape.lists.iter().map(|list| list.nullable_items).collect();
+        (shape.is_optional, lists, item)
+    }
+
     #[test]
-    fn test_is_complex_type() {
-        // Simple types
-        let simple_types = vec![
-            "bool", "char", "f32", "f64", "i8", "i16", "i32", "i64", "i128", "isize", "str",
-            "String", "u8", "u16", "u32", "u64", "u128", "usize",
-        ];
+    fn test_simple_types() {
+        for simple_type in SIMPLE_TYPES.iter() {
+            let ty: Type = syn::parse_str(simple_type).unwrap();
+            assert_eq!(shape_of(&ty), (false, vec![], simple_type.to_string()));
 
-        for simple_type in &simple_types {
-            assert_eq!(is_complex_type(simple_type.to_string()), false);
-            assert_eq!(is_complex_type(format!("Option<{}>", simple_type)), false);
+            let ty: Type = syn::parse_str(&format!("Option<{}>", simple_type)).unwrap();
+            assert_eq!(shape_of(&ty), (true, vec![], simple_type.to_string()));
         }
 
-        // Complex types
+        let ty: Type = syn::parse_str("std::string::String").unwrap();
+        assert_eq!(shape_of(&ty), (false, vec![], "String".to_string()));
+        let ty: Type = syn::parse_str("&'static str").unwrap();
+        assert_eq!(shape_of(&ty), (false, vec![], "str".to_string()));
+    }
 
-        assert_eq!(is_complex_type("Option<User>".to_string()), true);
+    #[test]
+    #[should_panic(expected = "Map keys must be strings")]
+    fn test_map_keys_must_be_strings() {
+        let ty: Type = syn::parse_str("HashMap<User, i32>").unwrap();
+        analyze_type(&ty);
     }
 
     #[test]
-    fn test_extract_nested_type() {
-        // Test cases for extract_nested_type function
-        let test_cases = vec![("Option<User>", "User")];
+    fn test_nested_wrappers() {
+        let cases = [
+            ("Option<User>", (true, vec![], "User")),
+            ("Option<Vec<User>>", (true, vec![false], "User")),
+            ("Vec<Option<i32>>", (false, vec![true], "i32")),
+            (
+                "Option<Vec<Option<Vec<i32>>>>",
+                (true, vec![true, false], "i32"),
+            ),
+            ("Vec<Vec<f64>>", (false, vec![false, false], "f64")),
+            ("Box<User>", (false, vec![], "User")),
+            ("std::vec::Vec<Box<User>>", (false, vec![false], "User")),
+            (
+                "crate::models::User",
+                (false, vec![], "crate :: models :: User"),
+            ),
+            ("Option<HashMap<String, User>>", (true, vec![], "map")),
+            (
+                "Vec<std::collections::BTreeMap<&str, i32>>",
+                (false, vec![false], "map"),
+            ),
+            ("[f64; 3]", (false, vec![false], "f64")),
+            ("Vec<[u8; 4]>", (false, vec![false, false], "u8")),
+            ("(f64, f64)", (false, vec![], "tuple of 2")),
+            ("Option<(String, Vec<i32>)>", (true, vec![], "tuple of 2")),
+            ("()", (false, vec![], "()")),
+        ];
 
-        for (input, expected) in test_cases {
-            assert_eq!(extract_nested_type(input), expected);
+        for (input, (is_optional, lists, item)) in cases.iter() {
+            let ty: Type = syn::parse_str(input).unwrap();
+            assert_eq!(
+                shape_of(&ty),
+                (*is_optional, lists.clone(), item.to_string()),
+                "{}",
+                input
+            );
         }
     }
+
+    #[test]
+    fn test_array_lengths() {
+        let ty: Type = syn::parse_str("Vec<[[i32; 2]; LEN]>").unwrap();
+        let lengths: Vec<String> = analyze_type(&ty)
+            .lists
+            .iter()
+            .map(|list| match list.length {
+                Some(length) => quote!(#length).to_string(),
+                None => "none".to_string(),
+            })
+            .collect();
+        assert_eq!(lengths, vec!["none", "LEN", "2"]);
+    }
 }
@@ -0,0 +1,269 @@
+use syn::punctuated::Punctuated;
+use syn::{Attribute, Expr, ExprLit, Lit, Meta, Token};
+
+/// A `rename_all` rule, applied the way serde applies it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RenameRule {
+    Lower,
+    Upper,
+    Pascal,
+    Camel,
+    Snake,
+    ScreamingSnake,
+    Kebab,
+    ScreamingKebab,
+}
+
+impl RenameRule {
+    fn parse(rule: &str) -> RenameRule {
+        match rule {
+            "lowercase" => RenameRule::Lower,
+            "UPPERCASE" => RenameRule::Upper,
+            "PascalCase" => RenameRule::Pascal,
+            "camelCase" => RenameRule::Camel,
+            "snake_case" => RenameRule::Snake,
+            "SCREAMING_SNAKE_CASE" => RenameRule::ScreamingSnake,
+            "kebab-case" => RenameRule::Kebab,
+            "SCREAMING-KEBAB-CASE" => RenameRule::ScreamingKebab,
+            _ => panic!("Unknown serde rename_all rule: {}", rule),
+        }
+    }
+
+    /// Renames a field, which is written in snake_case.
+    pub fn apply_to_field(self, field: &str) -> String {
+        match self {
+            RenameRule::Lower | RenameRule::Snake => field.to_string(),
+            RenameRule::Upper | RenameRule::ScreamingSnake => field.to_ascii_uppercase(),
+            RenameRule::Pascal | RenameRule::Camel => {
+                let mut renamed = String::new();
+                let mut capitalize = self == RenameRule::Pascal;
+                for c in field.chars() {
+                    if c == '_' {
+                        capitalize = true;
+                    } else if capitalize {
+                        renamed.push(c.to_ascii_uppercase());
+                        capitalize = false;
+                    } else {
+                        renamed.push(c);
+                    }
+                }
+                renamed
+            }
+            RenameRule::Kebab => field.replace('_', "-"),
+            RenameRule::ScreamingKebab => field.replace('_', "-").to_ascii_uppercase(),
+        }
+    }
+
+    /// Renames a variant, which is written in PascalCase.
+    pub fn apply_to_variant(self, variant: &str) -> String {
+        match self {
+            RenameRule::Pascal => variant.to_string(),
+            RenameRule::Lower => variant.to_ascii_lowercase(),
+            RenameRule::Upper => variant.to_ascii_uppercase(),
+            RenameRule::Camel => variant[..1].to_ascii_lowercase() + &variant[1..],
+            RenameRule::Snake => {
+                let mut snake = String::new();
+                for (i, c) in variant.char_indices() {
+                    if i > 0 && c.is_uppercase() {
+                        snake.push('_');
+                    }
+                    snake.push(c.to_ascii_lowercase());
+                }
+                snake
+            }
+            RenameRule::ScreamingSnake => RenameRule::Snake
+                .apply_to_variant(variant)
+                .to_ascii_uppercase(),
+            RenameRule::Kebab => RenameRule::Snake
+                .apply_to_variant(variant)
+                .replace('_', "-"),
+            RenameRule::ScreamingKebab => RenameRule::ScreamingSnake
+                .apply_to_variant(variant)
+                .replace('_', "-"),
+        }
+    }
+}
+
+/// The `#[serde(...)]` attributes of a struct or enum that change what it deserializes
+/// from.
+#[derive(Default)]
+pub struct SerdeContainer {
+    pub rename_all: Option<RenameRule>,
+    /// `rename_all_fields`, which renames the fields of every variant of an enum.
+    pub rename_all_fields: Option<RenameRule>,
+    pub default: bool,
+    pub deny_unknown_fields: bool,
+    pub tag: Option<String>,
+    pub content: Option<String>,
+    pub untagged: bool,
+}
+
+/// The `#[serde(...)]` attributes of a field or variant that change what it
+/// deserializes from. `alias` only adds names, so the schema keeps the primary one.
+#[derive(Default)]
+pub struct SerdeField {
+    pub rename: Option<String>,
+    /// `rename_all` on a variant, which renames the variant's fields.
+    pub rename_all: Option<RenameRule>,
+    pub skip: bool,
+    pub default: bool,
+    pub flatten: bool,
+}
+
+impl SerdeContainer {
+    pub fn from_attributes(attrs: &[Attribute]) -> SerdeContainer {
+        let mut container = SerdeContainer::default();
+        for meta in serde_metas(attrs) {
+            let name = meta_name(&meta);
+            match name.as_str() {
+                "rename_all" => {
+                    container.rename_all =
+                        deserialize_value(&meta).map(|rule| RenameRule::parse(&rule))
+                }
+                "rename_all_fields" => {
+                    container.rename_all_fields =
+                        deserialize_value(&meta).map(|rule| RenameRule::parse(&rule))
+                }
+                "default" => container.default = true,
+                "deny_unknown_fields" => container.deny_unknown_fields = true,
+                "tag" => container.tag = deserialize_value(&meta),
+                "content" => container.content = deserialize_value(&meta),
+                "untagged" => container.untagged = true,
+                _ => {}
+            }
+        }
+        container
+    }
+}
+
+impl SerdeField {
+    pub fn from_attributes(attrs: &[Attribute]) -> SerdeField {
+        let mut field = SerdeField::default();
+        for meta in serde_metas(attrs) {
+            let name = meta_name(&meta);
+            match name.as_str() {
+                "rename" => field.rename = deserialize_value(&meta),
+                "rename_all" => {
+                    field.rename_all = deserialize_value(&meta).map(|rule| RenameRule::parse(&rule))
+                }
+                "skip" | "skip_deserializing" | "other" => field.skip = true,
+                "default" => field.default = true,
+                "flatten" => field.flatten = true,
+                _ => {}
+            }
+        }
+        field
+    }
+
+    /// The name the field or variant `ident` is deserialized from.
+    pub fn name(
+        &self,
+        ident: &syn::Ident,
+        rename_all: Option<RenameRule>,
+        is_variant: bool,
+    ) -> String {
+        if let Some(rename) = &self.rename {
+            return rename.clone();
+        }
+
+        let ident = ident.to_string();
+        let ident = ident.strip_prefix("r#").unwrap_or(&ident);
+        match rename_all {
+            Some(rule) if is_variant => rule.apply_to_variant(ident),
+            Some(rule) => rule.apply_to_field(ident),
+            None => ident.to_string(),
+        }
+    }
+}
+
+fn serde_metas(attrs: &[Attribute]) -> Vec<Meta> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("serde"))
+        .flat_map(|attr| {
+            attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)
+                .expect("Unable to parse serde attribute")
+        })
+        .collect()
+}
+
+fn meta_name(meta: &Meta) -> String {
+    meta.path()
+        .get_ident()
+        .map_or_else(String::new, |ident| ident.to_string())
+}
+
+fn string_value(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Str(lit_str),
+            ..
+        }) => Some(lit_str.value()),
+        _ => None,
+    }
+}
+
+/// The value of `name = "..."`, or of its `deserialize` half in
+/// `name(serialize = "...", deserialize = "...")`.
+fn deserialize_value(meta: &Meta) -> Option<String> {
+    match meta {
+        Meta::NameValue(name_value) => string_value(&name_value.value),
+        Meta::List(list) => list
+            .parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)
+            .expect("Unable to parse serde attribute")
+            .iter()
+            .find_map(|meta| match meta {
+                Meta::NameValue(name_value) if name_value.path.is_ident("deserialize") => {
+                    string_value(&name_value.value)
+                }
+                _ => None,
+            }),
+        Meta::Path(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rename_rules() {
+        let cases = [
+            ("lowercase", "user_id", "user_id"),
+            ("UPPERCASE", "user_id", "USER_ID"),
+            ("PascalCase", "user_id", "UserId"),
+            ("camelCase", "user_id", "userId"),
+            ("snake_case", "user_id", "user_id"),
+            ("SCREAMING_SNAKE_CASE", "user_id", "USER_ID"),
+            ("kebab-case", "user_id", "user-id"),
+            ("SCREAMING-KEBAB-CASE", "user_id", "USER-ID"),
+        ];
+        for (rule, field, renamed) in cases.iter() {
+            assert_eq!(
+                RenameRule::parse(rule).apply_to_field(field),
+                *renamed,
+                "{}",
+                rule
+            );
+        }
+
+        let cases = [
+            ("lowercase", "UserId", "userid"),
+            ("UPPERCASE", "UserId", "USERID"),
+            ("PascalCase", "UserId", "UserId"),
+            ("camelCase", "UserId", "userId"),
+            ("snake_case", "UserId", "user_id"),
+            ("SCREAMING_SNAKE_CASE", "UserId", "USER_ID"),
+            ("kebab-case", "UserId", "user-id"),
+            ("SCREAMING-KEBAB-CASE", "UserId", "USER-ID"),
+        ];
+        for (rule, variant, renamed) in cases.iter() {
+            assert_eq!(
+                RenameRule::parse(rule).apply_to_variant(variant),
+                *renamed,
+                "{}",
+                rule
+            );
+        }
+    }
+}
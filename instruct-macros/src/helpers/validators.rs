@@ -0,0 +1,264 @@
+use instruct_macros_types::Constraint;
+use quote::quote;
+use syn::punctuated::Punctuated;
+use syn::{Attribute, Expr, ExprLit, ExprUnary, Lit, Meta, Token, UnOp};
+
+/// A validator from a field's `#[validate(...)]` attributes.
+enum Validator {
+    /// `custom = "fn_name"`, a function that takes a reference to the field and returns
+    /// a `Result<(), String>`.
+    Custom(syn::Ident),
+    /// One of the built-in validators, which also constrain the field's schema.
+    Builtin(Constraint),
+}
+
+/// The checks of every validator on a field, which return early with an error naming
+/// `label`. `value` is a reference to the field.
+pub fn extract_validations(
+    label: &str,
+    value: &proc_macro2::TokenStream,
+    attrs: &[Attribute],
+) -> Vec<proc_macro2::TokenStream> {
+    validators(attrs)
+        .iter()
+        .map(|validator| {
+            let check = match validator {
+                Validator::Custom(func) => quote! { #func(#value) },
+                Validator::Builtin(constraint) => builtin_check(value, constraint),
+            };
+            quote! {
+                if let Err(e) = #check {
+                    return Err(format!("Validation failed for field '{}': {}", #label, e));
+                }
+            }
+        })
+        .collect()
+}
+
+/// The `Constraint`s of the built-in validators on a field, for its schema.
+pub fn extract_constraints(attrs: &[Attribute]) -> Vec<proc_macro2::TokenStream> {
+    validators(attrs)
+        .iter()
+        .filter_map(|validator| match validator {
+            Validator::Custom(_) => None,
+            Validator::Builtin(constraint) => Some(constraint_expression(constraint)),
+        })
+        .collect()
+}
+
+fn validators(attrs: &[Attribute]) -> Vec<Validator> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("validate"))
+        .flat_map(|attr| {
+            attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)
+                .expect("Unable to parse attribute")
+        })
+        .map(|meta| parse_validator(&meta))
+        .collect()
+}
+
+fn parse_validator(meta: &Meta) -> Validator {
+    let name = meta
+        .path()
+        .get_ident()
+        .map_or_else(String::new, |ident| ident.to_string());
+    let constraint = match (name.as_str(), meta) {
+        ("custom", Meta::NameValue(name_value)) => {
+            let func = string_literal(&name_value.value)
+                .expect("Custom validator must be a string literal");
+            return Validator::Custom(syn::Ident::new(&func, proc_macro2::Span::call_site()));
+        }
+        ("length", Meta::List(_)) => {
+            let (min, max) = bounds(meta, |value| {
+                number_literal(value)
+                    .filter(|number| *number >= 0.0 && number.fract() == 0.0)
+                    .map(|number| number as usize)
+                    .expect("length bounds must be non-negative integers")
+            });
+            Constraint::Length { min, max }
+        }
+        ("range", Meta::List(_)) => {
+            let (min, max) = bounds(meta, |value| {
+                number_literal(value).expect("range bounds must be numbers")
+            });
+            Constraint::Range { min, max }
+        }
+        ("regex", Meta::NameValue(name_value)) => {
+            let pattern = string_literal(&name_value.value)
+                .expect("regex validator must be a string literal");
+            if let Err(e) = instruct_macros_types::validators::check_pattern(&pattern) {
+                panic!("Invalid regex `{}`: {}", pattern, e);
+            }
+            Constraint::Pattern(pattern)
+        }
+        ("contains", Meta::NameValue(name_value)) => Constraint::Contains(
+            string_literal(&name_value.value).expect("contains validator must be a string literal"),
+        ),
+        ("one_of", Meta::List(list)) => {
+            let values = list
+                .parse_args_with(Punctuated::<Expr, Token![,]>::parse_terminated)
+                .expect("Unable to parse one_of validator");
+            let values: Vec<serde_json::Value> = values
+                .iter()
+                .map(
+                    |value| match (string_literal(value), number_literal(value)) {
+                        (Some(text), _) => serde_json::Value::from(text),
+                        (None, Some(number)) if number.fract() == 0.0 => {
+                            serde_json::Value::from(number as i64)
+                        }
+                        (None, Some(number)) => serde_json::Value::from(number),
+                        (None, None) => panic!("one_of values must be string or number literals"),
+                    },
+                )
+                .collect();
+            if values.is_empty() {
+                panic!("one_of needs at least one value");
+            }
+            if !values.iter().all(|value| value.is_string())
+                && !values.iter().all(|value| value.is_number())
+            {
+                panic!("one_of values must be all strings or all numbers");
+            }
+            Constraint::OneOf(values)
+        }
+        ("email", Meta::Path(_)) => Constraint::Email,
+        ("url", Meta::Path(_)) => Constraint::Url,
+        ("non_empty", Meta::Path(_)) => Constraint::NonEmpty,
+        _ => panic!("Unsupported validation attribute"),
+    };
+    Validator::Builtin(constraint)
+}
+
+/// The `min` and `max` of `name(min = .., max = ..)`, at least one of which is set.
+fn bounds<T>(meta: &Meta, parse: impl Fn(&Expr) -> T) -> (Option<T>, Option<T>) {
+    let arguments = match meta {
+        Meta::List(list) => list
+            .parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)
+            .expect("Unable to parse validation attribute"),
+        _ => Punctuated::new(),
+    };
+    let (mut min, mut max) = (None, None);
+    for argument in arguments.iter() {
+        match argument {
+            Meta::NameValue(name_value) if name_value.path.is_ident("min") => {
+                min = Some(parse(&name_value.value))
+            }
+            Meta::NameValue(name_value) if name_value.path.is_ident("max") => {
+                max = Some(parse(&name_value.value))
+            }
+            _ => panic!("Validation bounds must be `min = ..` or `max = ..`"),
+        }
+    }
+    if min.is_none() && max.is_none() {
+        panic!("Validation bounds need a `min`, a `max` or both");
+    }
+    (min, max)
+}
+
+fn string_literal(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Str(lit_str),
+            ..
+        }) => Some(lit_str.value()),
+        _ => None,
+    }
+}
+
+/// An integer or float literal, which may be negated.
+fn number_literal(expr: &Expr) -> Option<f64> {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(lit_int),
+            ..
+        }) => lit_int.base10_parse().ok(),
+        Expr::Lit(ExprLit {
+            lit: Lit::Float(lit_float),
+            ..
+        }) => lit_float.base10_parse().ok(),
+        Expr::Unary(ExprUnary {
+            op: UnOp::Neg(_),
+            expr,
+            ..
+        }) => number_literal(expr).map(|number| -number),
+        _ => None,
+    }
+}
+
+fn option_tokens<T: quote::ToTokens>(value: &Option<T>) -> proc_macro2::TokenStream {
+    match value {
+        Some(value) => quote! { Some(#value) },
+        None => quote! { None },
+    }
+}
+
+fn builtin_check(
+    value: &proc_macro2::TokenStream,
+    constraint: &Constraint,
+) -> proc_macro2::TokenStream {
+    match constraint {
+        Constraint::Length { min, max } => {
+            let (min, max) = (option_tokens(min), option_tokens(max));
+            quote! { instruct_macros_types::validators::length(#value, #min, #max) }
+        }
+        Constraint::NonEmpty => quote! { instruct_macros_types::validators::non_empty(#value) },
+        Constraint::Range { min, max } => {
+            let (min, max) = (option_tokens(min), option_tokens(max));
+            quote! { instruct_macros_types::validators::range(#value, #min, #max) }
+        }
+        Constraint::Pattern(pattern) => {
+            quote! { instruct_macros_types::validators::regex(#value, #pattern) }
+        }
+        Constraint::Email => quote! { instruct_macros_types::validators::email(#value) },
+        Constraint::Url => quote! { instruct_macros_types::validators::url(#value) },
+        Constraint::Contains(needle) => {
+            quote! { instruct_macros_types::validators::contains(#value, #needle) }
+        }
+        Constraint::OneOf(options) => match options.iter().all(|option| option.is_string()) {
+            true => {
+                let options = options.iter().filter_map(|option| option.as_str());
+                quote! { instruct_macros_types::validators::one_of_texts(#value, &[#(#options),*]) }
+            }
+            false => {
+                let options = options.iter().filter_map(|option| option.as_f64());
+                quote! { instruct_macros_types::validators::one_of_numbers(#value, &[#(#options),*]) }
+            }
+        },
+    }
+}
+
+fn constraint_expression(constraint: &Constraint) -> proc_macro2::TokenStream {
+    match constraint {
+        Constraint::Length { min, max } => {
+            let (min, max) = (option_tokens(min), option_tokens(max));
+            quote! { instruct_macros_types::Constraint::Length { min: #min, max: #max } }
+        }
+        Constraint::NonEmpty => quote! { instruct_macros_types::Constraint::NonEmpty },
+        Constraint::Range { min, max } => {
+            let (min, max) = (option_tokens(min), option_tokens(max));
+            quote! { instruct_macros_types::Constraint::Range { min: #min, max: #max } }
+        }
+        Constraint::Pattern(pattern) => {
+            quote! { instruct_macros_types::Constraint::Pattern(#pattern.to_string()) }
+        }
+        Constraint::Email => quote! { instruct_macros_types::Constraint::Email },
+        Constraint::Url => quote! { instruct_macros_types::Constraint::Url },
+        Constraint::Contains(needle) => {
+            quote! { instruct_macros_types::Constraint::Contains(#needle.to_string()) }
+        }
+        Constraint::OneOf(options) => {
+            let options = options.iter().map(|option| match option {
+                serde_json::Value::String(text) => quote! { #text.into() },
+                number => match number.as_i64() {
+                    Some(integer) => quote! { #integer.into() },
+                    None => {
+                        let float = number.as_f64().unwrap_or_default();
+                        quote! { #float.into() }
+                    }
+                },
+            });
+            quote! { instruct_macros_types::Constraint::OneOf(vec![#(#options),*]) }
+        }
+    }
+}
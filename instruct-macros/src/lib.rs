@@ -4,7 +4,7 @@ mod helpers;
 
 use proc_macro::TokenStream;
 use quote::quote;
-use syn::{parse_macro_input, Attribute, Data, DeriveInput, Expr, ExprLit, Fields, Lit, Meta};
+use syn::{parse_macro_input, Data, DeriveInput, Fields};
 
 #[proc_macro_derive(InstructMacro, attributes(validate, description))]
 pub fn instruct_validate_derive(input: TokenStream) -> TokenStream {
@@ -21,6 +21,59 @@ pub fn instruct_validate_derive(input: TokenStream) -> TokenStream {
     TokenStream::from(expanded)
 }
 
+/// Derives `ParallelTools` for an enum whose variants each wrap one response model.
+///
+/// Every variant is offered to the model as a tool named after the variant, and each
+/// tool call is parsed into the variant it names.
+#[proc_macro_derive(ParallelTools)]
+pub fn parallel_tools_derive(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = &input.ident;
+
+    let variants = match &input.data {
+        Data::Enum(data) if !data.variants.is_empty() => &data.variants,
+        _ => panic!("ParallelTools can only be derived for enums with at least one variant"),
+    };
+
+    let (idents, types): (Vec<_>, Vec<_>) = variants
+        .iter()
+        .map(|variant| match &variant.fields {
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                (&variant.ident, &fields.unnamed[0].ty)
+            }
+            _ => panic!("Every ParallelTools variant must wrap exactly one response model"),
+        })
+        .unzip();
+    let tool_names: Vec<String> = idents.iter().map(|ident| ident.to_string()).collect();
+
+    let expanded = quote! {
+        impl instruct_macros_types::ParallelTools for #name {
+            fn get_tools() -> Vec<instruct_macros_types::Parameter> {
+                vec![#(
+                    <#types as instruct_macros_types::InstructMacro>::get_info()
+                        .wrap_info(#tool_names.to_string())
+                ),*]
+            }
+
+            fn from_tool_call(name: &str, arguments: &str) -> Result<Self, String> {
+                match name {
+                    #(#tool_names => instruct_macros_types::from_tool_arguments::<#types>(arguments)
+                        .map(#name::#idents),)*
+                    _ => Err(format!("Unknown tool {}", name)),
+                }
+            }
+
+            fn validate(&self) -> Result<(), String> {
+                match self {
+                    #(#name::#idents(value) => instruct_macros_types::InstructMacro::validate(value),)*
+                }
+            }
+        }
+    };
+
+    TokenStream::from(expanded)
+}
+
 fn generate_instruct_macro_enum(input: &DeriveInput) -> proc_macro2::TokenStream {
     let name = &input.ident;
 
@@ -29,28 +82,65 @@ fn generate_instruct_macro_enum(input: &DeriveInput) -> proc_macro2::TokenStream
         _ => panic!("Only enums are supported"),
     };
 
-    let enum_variants: Vec<String> = variants.iter().map(|v| v.ident.to_string()).collect();
+    // Only externally tagged unit variants are plain strings, so any other tagging is
+    // described as a union even when no variant carries data
+    let serde = helpers::SerdeContainer::from_attributes(&input.attrs);
+    if variants.iter().any(|v| !matches!(v.fields, Fields::Unit))
+        || serde.untagged
+        || serde.tag.is_some()
+    {
+        return generate_instruct_macro_union(input);
+    }
+
+    let (enum_variants, variant_descriptions): (Vec<String>, Vec<String>) = variants
+        .iter()
+        .filter_map(|variant| {
+            let variant_serde = helpers::SerdeField::from_attributes(&variant.attrs);
+            match variant_serde.skip {
+                true => None,
+                false => Some((
+                    variant_serde.name(&variant.ident, serde.rename_all, true),
+                    helpers::extract_description(&variant.attrs),
+                )),
+            }
+        })
+        .unzip();
 
     // Extract struct-level comment
-    let description = extract_attribute_value(&input.attrs, "description");
+    let description = helpers::extract_description(&input.attrs);
+
+    let generics = add_instruct_macro_bounds(&input.generics);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let schema_name = schema_name(input);
 
     let enum_info = quote! {
         instruct_macros_types::InstructMacroResult::Enum(instruct_macros_types::EnumInfo {
-            title: stringify!(#name).to_string(),
+            title: #schema_name,
             r#enum: vec![#(#enum_variants.to_string()),*],
-            r#type: stringify!(#name).to_string(),
+            r#type: #schema_name,
             description: #description.to_string(),
+            variants: vec![#(instruct_macros_types::VariantInfo {
+                name: #enum_variants.to_string(),
+                description: #variant_descriptions.to_string(),
+                content: None,
+            }),*],
             is_optional:false,
             is_list: false
         })
     };
 
     quote! {
-        impl instruct_macros_types::InstructMacro for #name {
+        impl #impl_generics instruct_macros_types::InstructMacro for #name #ty_generics #where_clause {
+            type Partial = Self;
+
             fn get_info() -> instruct_macros_types::InstructMacroResult {
                 #enum_info
             }
 
+            fn schema_name() -> String {
+                #schema_name
+            }
+
 
             fn validate(&self) -> Result<(), String> {
                 Ok(())
@@ -59,73 +149,479 @@ fn generate_instruct_macro_enum(input: &DeriveInput) -> proc_macro2::TokenStream
     }
 }
 
-fn extract_attribute_value(attrs: &[Attribute], attr_name: &str) -> String {
-    attrs
+/// Generates the `InstructMacro` impl for an enum with variants that carry data, or with
+/// a serde tagging other than the default, which is described as a union laid out
+/// according to that tagging.
+fn generate_instruct_macro_union(input: &DeriveInput) -> proc_macro2::TokenStream {
+    let name = &input.ident;
+
+    let variants = match &input.data {
+        Data::Enum(data) => &data.variants,
+        _ => panic!("Only enums are supported"),
+    };
+
+    let description = helpers::extract_description(&input.attrs);
+    let serde = helpers::SerdeContainer::from_attributes(&input.attrs);
+    let tagging = union_tagging(&serde);
+
+    let variant_infos: Vec<_> = variants
         .iter()
-        .filter_map(|attr| {
-            if attr.path().is_ident(attr_name) {
-                attr.parse_args::<syn::LitStr>().ok().map(|lit| lit.value())
-            } else {
-                None
+        .filter_map(|variant| {
+            let variant_serde = helpers::SerdeField::from_attributes(&variant.attrs);
+            if variant_serde.skip {
+                return None;
             }
+            let variant_name = variant_serde.name(&variant.ident, serde.rename_all, true);
+            let variant_description = helpers::extract_description(&variant.attrs);
+
+            let content = match &variant.fields {
+                Fields::Unit => quote! { None },
+                Fields::Named(fields) => {
+                    let rename_all = variant_serde.rename_all.or(serde.rename_all_fields);
+                    let parameters = helpers::extract_parameters(fields, rename_all, false);
+                    quote! {
+                        Some({
+                            let mut parameters = Vec::new();
+                            #(#parameters)*
+
+                            Parameter::Struct(StructInfo {
+                                name: #variant_name.to_string(),
+                                r#type: "".to_string(),
+                                description: #variant_description.to_string(),
+                                parameters,
+                                deny_unknown_fields: false,
+                                is_optional: false,
+                                is_list: false,
+                            })
+                        })
+                    }
+                }
+                Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                    let parameter =
+                        helpers::extract_newtype_parameter(&variant_name, &fields.unnamed[0]);
+                    quote! { Some(#parameter) }
+                }
+                Fields::Unnamed(_) => {
+                    panic!("Tuple variants with more than one field are not supported")
+                }
+            };
+
+            Some(quote! {
+                instruct_macros_types::VariantInfo {
+                    name: #variant_name.to_string(),
+                    description: #variant_description.to_string(),
+                    content: #content,
+                }
+            })
         })
-        .collect::<Vec<String>>()
-        .join(" ")
+        .collect();
+
+    let validations: Vec<_> = variants
+        .iter()
+        .filter_map(|variant| {
+            let ident = &variant.ident;
+            match &variant.fields {
+                Fields::Unnamed(fields)
+                    if fields.unnamed.len() == 1
+                        && helpers::is_validated_newtype(&fields.unnamed[0]) =>
+                {
+                    Some(quote! {
+                        #name::#ident(value) => instruct_macros_types::InstructMacro::validate(value),
+                    })
+                }
+                // The fields of a struct variant are bound by reference, so they are
+                // validated like the fields of a struct
+                Fields::Named(fields) => {
+                    let (bindings, checks): (Vec<_>, Vec<_>) = fields
+                        .named
+                        .iter()
+                        .map(|field| {
+                            let field_ident =
+                                field.ident.as_ref().expect("Named fields have an ident");
+                            let checks = helpers::extract_validations(
+                                &field_ident.to_string(),
+                                &quote!(#field_ident),
+                                &field.attrs,
+                            );
+                            (field_ident, checks)
+                        })
+                        .filter(|(_, checks)| !checks.is_empty())
+                        .unzip();
+                    if bindings.is_empty() {
+                        return None;
+                    }
+                    let checks = checks.into_iter().flatten();
+                    Some(quote! {
+                        #name::#ident { #(#bindings,)* .. } => {
+                            #(#checks)*
+                            Ok(())
+                        }
+                    })
+                }
+                _ => None,
+            }
+        })
+        .collect();
+    let other_variants = if validations.len() < variants.len() {
+        quote! { _ => Ok(()), }
+    } else {
+        quote! {}
+    };
+
+    let generics = add_instruct_macro_bounds(&input.generics);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let schema_name = schema_name(input);
+
+    quote! {
+        impl #impl_generics instruct_macros_types::InstructMacro for #name #ty_generics #where_clause {
+            type Partial = Self;
+
+            fn get_info() -> instruct_macros_types::InstructMacroResult {
+                let type_name = #schema_name;
+                instruct_macros_types::describe_type(type_name.clone(), move || {
+                    instruct_macros_types::InstructMacroResult::Union(instruct_macros_types::UnionInfo {
+                        name: type_name.clone(),
+                        r#type: type_name,
+                        description: #description.to_string(),
+                        variants: vec![#(#variant_infos),*],
+                        tagging: #tagging,
+                        is_optional: false,
+                        is_list: false,
+                    })
+                })
+            }
+
+            fn schema_name() -> String {
+                #schema_name
+            }
+
+            fn validate(&self) -> Result<(), String> {
+                match self {
+                    #(#validations)*
+                    #other_variants
+                }
+            }
+        }
+    }
+}
+
+/// The enum representation from `#[serde(tag = "...", content = "...")]` and
+/// `#[serde(untagged)]`, defaulting to serde's external tagging.
+fn union_tagging(serde: &helpers::SerdeContainer) -> proc_macro2::TokenStream {
+    match (serde.untagged, &serde.tag, &serde.content) {
+        (true, _, _) => quote! { instruct_macros_types::UnionTagging::Untagged },
+        (false, Some(tag), Some(content)) => quote! {
+            instruct_macros_types::UnionTagging::Adjacent {
+                tag: #tag.to_string(),
+                content: #content.to_string(),
+            }
+        },
+        (false, Some(tag), None) => quote! {
+            instruct_macros_types::UnionTagging::Internal { tag: #tag.to_string() }
+        },
+        (false, None, _) => quote! { instruct_macros_types::UnionTagging::External },
+    }
+}
+
+/// Adds an `InstructMacro` bound to every type parameter, since fields of those types
+/// are described through their own `get_info`.
+fn add_instruct_macro_bounds(generics: &syn::Generics) -> syn::Generics {
+    let mut generics = generics.clone();
+    for param in generics.type_params_mut() {
+        param
+            .bounds
+            .push(syn::parse_quote!(instruct_macros_types::InstructMacro));
+    }
+    generics
+}
+
+/// Generates the partial of a struct with named fields, which reads the same JSON as the
+/// struct with every field optional. Fields stay `None` until what has arrived of them
+/// can be read, and nested response models are read into their own partials.
+///
+/// The partial is named `__<Name>Partial` and hidden from the docs, since it is only
+/// meant to be reached through `InstructMacro::Partial`. Its `Deserialize` and `Debug`
+/// impls are bounded on every field, so a partial only implements them when its fields
+/// do, and the derive keeps working for types that are never streamed.
+fn generate_partial_struct(
+    input: &DeriveInput,
+    fields: &syn::FieldsNamed,
+    serde: &helpers::SerdeContainer,
+) -> proc_macro2::TokenStream {
+    let name = &input.ident;
+    let vis = &input.vis;
+    let partial_name = partial_name(name);
+    let doc = format!(
+        "A [`{}`] that is still being generated, with every field optional.",
+        name
+    );
+
+    let generics = add_instruct_macro_bounds(&input.generics);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let mut definitions = Vec::new();
+    let mut idents = Vec::new();
+    let mut bound_types = Vec::new();
+    for field in fields.named.iter() {
+        let field_serde = helpers::SerdeField::from_attributes(&field.attrs);
+        if field_serde.skip {
+            continue;
+        }
+        let ident = field.ident.as_ref().expect("Named fields have an ident");
+        let field_vis = &field.vis;
+        let field_type = helpers::partial_field_type(&field.ty);
+        // A flattened partial always reads, so it doesn't need to be read leniently
+        let attribute = match field_serde.flatten {
+            true => quote! { #[serde(flatten)] },
+            false => {
+                let field_name = field_serde.name(ident, serde.rename_all, false);
+                quote! {
+                    #[serde(
+                        rename = #field_name,
+                        deserialize_with = "instruct_macros_types::deserialize_partial_field"
+                    )]
+                }
+            }
+        };
+        definitions.push(quote! {
+            #attribute
+            #field_vis #ident: #field_type
+        });
+        // Fields of the struct's own type are read through the impls being generated
+        if !mentions_type(&field.ty, name) {
+            bound_types.push(match mentions_type_params(&field.ty, &input.generics) {
+                true => helpers::partial_value_type(&field.ty),
+                false => helpers::owned_field_type(&field.ty),
+            });
+        }
+        idents.push(ident);
+    }
+
+    // Generic partials only use their parameters through the partials of other types
+    let (marker, marker_default) = match input.generics.params.is_empty() {
+        true => (quote! {}, quote! {}),
+        false => (
+            quote! {
+                #[serde(skip)]
+                _marker: std::marker::PhantomData<fn() -> #name #ty_generics>,
+            },
+            quote! { _marker: std::marker::PhantomData, },
+        ),
+    };
+
+    // The partial implements `Deserialize` and `Debug` when the struct's field types do.
+    // Bounding the field types rather than their partials keeps recursive types from
+    // bounding themselves, and bounds on concrete types have to be higher-ranked, or the
+    // compiler rejects the ones that don't hold instead of leaving the impl out.
+    let deserialize_bound = bound_types
+        .iter()
+        .map(|ty| {
+            quote! { for<'__partial> #ty: instruct_macros_types::serde::Deserialize<'__partial> }
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    let mut debug_generics = generics.clone();
+    let debug_where_clause = debug_generics.make_where_clause();
+    for ty in bound_types.iter() {
+        debug_where_clause
+            .predicates
+            .push(syn::parse_quote!(for<'__partial> #ty: std::fmt::Debug));
+    }
+    let debug_where_clause = &debug_generics.where_clause;
+    let partial_label = format!("{}Partial", name);
+    let labels = idents.iter().map(|ident| ident.to_string());
+
+    quote! {
+        #[doc = #doc]
+        #[doc(hidden)]
+        #[derive(instruct_macros_types::serde::Deserialize)]
+        #[serde(
+            crate = "instruct_macros_types::serde",
+            default,
+            bound(deserialize = #deserialize_bound)
+        )]
+        #vis struct #partial_name #impl_generics #where_clause {
+            #(#definitions,)*
+            #marker
+        }
+
+        impl #impl_generics Default for #partial_name #ty_generics #where_clause {
+            fn default() -> Self {
+                Self {
+                    #(#idents: None,)*
+                    #marker_default
+                }
+            }
+        }
+
+        impl #impl_generics std::fmt::Debug for #partial_name #ty_generics #debug_where_clause {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.debug_struct(#partial_label)
+                    #(.field(#labels, &self.#idents))*
+                    .finish()
+            }
+        }
+    }
+}
+
+/// Whether `ty` mentions one of the type parameters of `generics`.
+fn mentions_type_params(ty: &syn::Type, generics: &syn::Generics) -> bool {
+    generics
+        .type_params()
+        .any(|param| mentions_type(ty, &param.ident))
+}
+
+/// Whether `ty` mentions the type `name`, or `Self`.
+fn mentions_type(ty: &syn::Type, name: &syn::Ident) -> bool {
+    fn mentions(tokens: proc_macro2::TokenStream, name: &syn::Ident) -> bool {
+        tokens.into_iter().any(|token| match token {
+            proc_macro2::TokenTree::Ident(ident) => ident == *name || ident == "Self",
+            proc_macro2::TokenTree::Group(group) => mentions(group.stream(), name),
+            _ => false,
+        })
+    }
+    mentions(quote!(#ty), name)
+}
+
+/// The name of the partial generated for the struct `name`.
+fn partial_name(name: &syn::Ident) -> syn::Ident {
+    quote::format_ident!("__{}Partial", name)
+}
+
+/// The name a derived type is described by. Generic types are named after their type
+/// arguments as well, so `Page<User>` is `Page_User`.
+fn schema_name(input: &DeriveInput) -> proc_macro2::TokenStream {
+    let name = &input.ident;
+    let params: Vec<_> = input
+        .generics
+        .type_params()
+        .map(|param| &param.ident)
+        .collect();
+    if params.is_empty() {
+        return quote! { stringify!(#name).to_string() };
+    }
+
+    quote! {
+        [
+            stringify!(#name).to_string(),
+            #(<#params as instruct_macros_types::InstructMacro>::schema_name()),*
+        ]
+        .join("_")
+    }
 }
 
 fn generate_instruct_macro_struct(input: &DeriveInput) -> proc_macro2::TokenStream {
     let name = &input.ident;
+    let struct_name = name.to_string();
 
-    let description = extract_attribute_value(&input.attrs, "description");
+    let description = helpers::extract_description(&input.attrs);
 
-    let fields = match &input.data {
-        Data::Struct(data) => match &data.fields {
-            Fields::Named(fields) => &fields.named,
-            _ => panic!("Only named fields are supported"),
-        },
+    let data = match &input.data {
+        Data::Struct(data) => data,
         _ => panic!("Only structs are supported"),
     };
+    let generics = add_instruct_macro_bounds(&input.generics);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let schema_name = schema_name(input);
+    let mut partial = quote! {};
+    let mut partial_type = quote! { Self };
 
-    let validation_fields: Vec<_> = fields
+    let mut validation_fields: Vec<_> = data
+        .fields
         .iter()
-        .filter_map(|f| {
-            let field_name = &f.ident;
-            f.attrs
-                .iter()
-                .find(|attr| attr.path().is_ident("validate"))
-                .map(|attr| {
-                    let meta = attr.parse_args().expect("Unable to parse attribute");
-                    parse_validation_attribute(field_name, &meta)
-                })
+        .enumerate()
+        .flat_map(|(index, f)| {
+            let member = match &f.ident {
+                Some(ident) => syn::Member::Named(ident.clone()),
+                None => syn::Member::Unnamed(syn::Index::from(index)),
+            };
+            helpers::extract_validations(
+                &quote!(#member).to_string(),
+                &quote!(&self.#member),
+                &f.attrs,
+            )
         })
         .collect();
 
-    // Process each field in the struct
-    let fields = if let Data::Struct(data) = &input.data {
-        if let Fields::Named(fields) = &data.fields {
-            fields
-        } else {
-            panic!("Unnamed fields are not supported");
+    // Named structs are objects, while newtypes take the shape of the type they wrap
+    // and other tuple structs are arrays with one item per field
+    let info = match &data.fields {
+        Fields::Named(fields) => {
+            let serde = helpers::SerdeContainer::from_attributes(&input.attrs);
+            let parameters = helpers::extract_parameters(fields, serde.rename_all, serde.default);
+            let deny_unknown_fields = serde.deny_unknown_fields;
+            partial = generate_partial_struct(input, fields, &serde);
+            let partial_name = partial_name(name);
+            partial_type = quote! { #partial_name #ty_generics };
+            quote! {
+                let type_name = #schema_name;
+                instruct_macros_types::describe_type(type_name.clone(), move || {
+                    let mut parameters = Vec::new();
+                    #(#parameters)*
+
+                    instruct_macros_types::InstructMacroResult::Struct(StructInfo {
+                        name: type_name.clone(),
+                        r#type: type_name,
+                        description: #description.to_string(),
+                        parameters,
+                        deny_unknown_fields: #deny_unknown_fields,
+                        is_optional:false,
+                        is_list: false,
+                    })
+                })
+            }
         }
-    } else {
-        panic!("Only structs are supported");
+        Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+            let field = &fields.unnamed[0];
+            if helpers::is_validated_newtype(field) {
+                validation_fields.push(quote! {
+                    instruct_macros_types::InstructMacro::validate(&self.0)?;
+                });
+            }
+            let parameter = helpers::extract_newtype_parameter(&struct_name, field);
+            quote! {
+                instruct_macros_types::InstructMacroResult::from(
+                    #parameter
+                        .override_description(#description.to_string())
+                        .wrap_info(#schema_name),
+                )
+            }
+        }
+        Fields::Unnamed(fields) => {
+            let parameter = helpers::extract_tuple_parameter(&struct_name, fields);
+            quote! {
+                instruct_macros_types::InstructMacroResult::from(
+                    #parameter
+                        .override_description(#description.to_string())
+                        .wrap_info(#schema_name),
+                )
+            }
+        }
+        Fields::Unit => quote! {
+            instruct_macros_types::InstructMacroResult::Parameter(Parameter::Field(ParameterInfo {
+                name: #schema_name,
+                r#type: "()".to_string(),
+                comment: #description.to_string(),
+                constraints: Vec::new(),
+                is_optional: false,
+                is_list: false,
+            }))
+        },
     };
 
-    let parameters = helpers::extract_parameters(fields);
-
     let expanded = quote! {
-        impl instruct_macros_types::InstructMacro for #name {
+        #partial
+
+        impl #impl_generics instruct_macros_types::InstructMacro for #name #ty_generics #where_clause {
+            type Partial = #partial_type;
+
             fn get_info() -> instruct_macros_types::InstructMacroResult {
-                let mut parameters = Vec::new();
-                #(#parameters)*
-
-                instruct_macros_types::InstructMacroResult::Struct(StructInfo {
-                    name: stringify!(#name).to_string(),
-                    description: #description.to_string(),
-                    parameters,
-                    is_optional:false,
-                    is_list: false,
-                })
+                #info
+            }
+
+            fn schema_name() -> String {
+                #schema_name
             }
 
             fn validate(&self) -> Result<(), String> {
@@ -138,41 +634,6 @@ fn generate_instruct_macro_struct(input: &DeriveInput) -> proc_macro2::TokenStre
     expanded
 }
 
-/// Parses the validation attribute and generates corresponding validation code.
-///
-/// This function processes custom validation attributes, expanding them into function calls
-/// that perform the specified validation. It supports custom validators that take a reference
-/// to the field type and return a Result with a string error type.
-fn parse_validation_attribute(
-    field_name: &Option<syn::Ident>,
-    meta: &Meta,
-) -> proc_macro2::TokenStream {
-    let mut output = proc_macro2::TokenStream::new();
-
-    match meta {
-        Meta::NameValue(name_value) if name_value.path.is_ident("custom") => {
-            if let Expr::Lit(ExprLit {
-                lit: Lit::Str(lit_str),
-                ..
-            }) = &name_value.value
-            {
-                let func = syn::Ident::new(&lit_str.value(), proc_macro2::Span::call_site());
-                let tokens = quote! {
-                    if let Err(e) = #func(&self.#field_name) {
-                        return Err(format!("Validation failed for field '{}': {}", stringify!(#field_name), e));
-                    }
-                };
-                output.extend(tokens);
-            } else {
-                panic!("Custom validator must be a string literal");
-            }
-        }
-        _ => panic!("Unsupported validation attribute"),
-    }
-
-    output
-}
-
 /// Custom attribute macro for field validation in structs.
 ///
 /// This procedural macro attribute is designed to be applied to structs,
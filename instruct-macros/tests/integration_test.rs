@@ -8,10 +8,21 @@ use instruct_macros_types::{
 #[cfg(test)]
 mod tests {
 
-    use instruct_macros_types::EnumInfo;
+    use instruct_macros_types::{EnumInfo, VariantInfo};
 
     use super::*;
 
+    fn unit_variants(names: &[&str]) -> Vec<VariantInfo> {
+        names
+            .iter()
+            .map(|name| VariantInfo {
+                name: name.to_string(),
+                description: "".to_string(),
+                content: None,
+            })
+            .collect()
+    }
+
     #[test]
     fn test_string_conversion() {
         #[derive(InstructMacro, Debug)]
@@ -30,12 +41,14 @@ mod tests {
         let info = TestStruct::get_info();
         let desired_struct = StructInfo {
             name: "TestStruct".to_string(),
+            r#type: "TestStruct".to_string(),
             description: "This is a struct".to_string(),
             parameters: vec![
                 Parameter::Field(ParameterInfo {
                     name: "field1".to_string(),
                     r#type: "String".to_string(),
                     comment: "This is a sample example that spans across three lines".to_string(),
+                    constraints: vec![],
                     is_optional: false,
                     is_list: false,
                 }),
@@ -43,10 +56,12 @@ mod tests {
                     name: "field2".to_string(),
                     r#type: "str".to_string(),
                     comment: "This is a test field".to_string(),
+                    constraints: vec![],
                     is_optional: false,
                     is_list: false,
                 }),
             ],
+            deny_unknown_fields: false,
             is_optional: false,
             is_list: false,
         };
@@ -64,6 +79,7 @@ mod tests {
     #[test]
     fn test_validation_macro() {
         #[derive(InstructMacro, Debug)]
+        #[allow(dead_code)]
         pub struct UserInfo {
             #[validate(custom = "validate_uppercase")]
             pub name: String,
@@ -102,12 +118,14 @@ mod tests {
     #[test]
     fn test_nested_struct_macro() {
         #[derive(InstructMacro, Debug)]
+        #[allow(dead_code)]
         pub struct Address {
             pub street: String,
             pub city: String,
         }
 
         #[derive(InstructMacro, Debug)]
+        #[allow(dead_code)]
         pub struct User {
             pub name: String,
             pub age: u8,
@@ -117,12 +135,14 @@ mod tests {
         let info = User::get_info();
         let desired_struct = StructInfo {
             name: "User".to_string(),
+            r#type: "User".to_string(),
             description: "".to_string(),
             parameters: vec![
                 Parameter::Field(ParameterInfo {
                     name: "name".to_string(),
                     r#type: "String".to_string(),
                     comment: "".to_string(),
+                    constraints: vec![],
                     is_optional: false,
                     is_list: false,
                 }),
@@ -130,17 +150,20 @@ mod tests {
                     name: "age".to_string(),
                     r#type: "u8".to_string(),
                     comment: "".to_string(),
+                    constraints: vec![],
                     is_optional: false,
                     is_list: false,
                 }),
                 Parameter::Struct(StructInfo {
                     name: "address".to_string(),
+                    r#type: "Address".to_string(),
                     description: "".to_string(),
                     parameters: vec![
                         Parameter::Field(ParameterInfo {
                             name: "street".to_string(),
                             r#type: "String".to_string(),
                             comment: "".to_string(),
+                            constraints: vec![],
                             is_optional: false,
                             is_list: false,
                         }),
@@ -148,14 +171,17 @@ mod tests {
                             name: "city".to_string(),
                             r#type: "String".to_string(),
                             comment: "".to_string(),
+                            constraints: vec![],
                             is_optional: false,
                             is_list: false,
                         }),
                     ],
+                    deny_unknown_fields: false,
                     is_optional: false,
                     is_list: false,
                 }),
             ],
+            deny_unknown_fields: false,
             is_optional: false,
             is_list: false,
         };
@@ -189,6 +215,7 @@ mod tests {
             ],
             r#type: "Status".to_string(),
             description: "".to_string(),
+            variants: unit_variants(&["Active", "Inactive", "Pending"]),
             is_optional: false,
             is_list: false,
         };
@@ -223,12 +250,14 @@ mod tests {
 
         let desired_struct = StructInfo {
             name: "User".to_string(),
+            r#type: "User".to_string(),
             description: "".to_string(),
             parameters: vec![
                 Parameter::Field(ParameterInfo {
                     name: "name".to_string(),
                     r#type: "String".to_string(),
                     comment: "".to_string(),
+                    constraints: vec![],
                     is_optional: false,
                     is_list: false,
                 }),
@@ -241,10 +270,12 @@ mod tests {
                     ],
                     r#type: "Status".to_string(),
                     description: "This is an enum representing the status of a person".to_string(),
+                    variants: unit_variants(&["Active", "Inactive", "Pending"]),
                     is_optional: false,
                     is_list: false,
                 }),
             ],
+            deny_unknown_fields: false,
             is_optional: false,
             is_list: false,
         };
@@ -259,4 +290,45 @@ mod tests {
 
         assert!(info_struct == desired_struct);
     }
+
+    #[test]
+    fn test_enum_variant_descriptions() {
+        #[derive(InstructMacro, Debug, serde::Deserialize)]
+        #[allow(dead_code)]
+        #[serde(rename_all = "snake_case")]
+        pub enum Status {
+            #[description("Waiting for someone to pick it up")]
+            Pending,
+            /// Paused until the customer replies
+            OnHold,
+            Closed,
+        }
+
+        let info = match Status::get_info() {
+            InstructMacroResult::Enum(e) => e,
+            _ => panic!("Expected EnumInfo"),
+        };
+
+        assert_eq!(info.r#enum, vec!["pending", "on_hold", "closed"]);
+        assert_eq!(
+            info.variants,
+            vec![
+                VariantInfo {
+                    name: "pending".to_string(),
+                    description: "Waiting for someone to pick it up".to_string(),
+                    content: None,
+                },
+                VariantInfo {
+                    name: "on_hold".to_string(),
+                    description: "Paused until the customer replies".to_string(),
+                    content: None,
+                },
+                VariantInfo {
+                    name: "closed".to_string(),
+                    description: "".to_string(),
+                    content: None,
+                },
+            ]
+        );
+    }
 }
@@ -0,0 +1,97 @@
+extern crate instruct_macros_types;
+
+use instruct_macros::InstructMacro;
+use instruct_macros_types::{
+    InstructMacro, InstructMacroResult, Parameter, ParameterInfo, StructInfo,
+};
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_doc_comments_describe_structs_and_fields() {
+        /// A user of the app.
+        ///
+        /// Only adults can sign up.
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        struct User {
+            /// The full name of the user,
+            /// as written on their passport
+            name: String,
+            /// Ignored, since the field has a description
+            #[description("The age of the user")]
+            age: u8,
+        }
+
+        let field = |name: &str, r#type: &str, comment: &str| {
+            Parameter::Field(ParameterInfo {
+                name: name.to_string(),
+                r#type: r#type.to_string(),
+                comment: comment.to_string(),
+                constraints: vec![],
+                is_optional: false,
+                is_list: false,
+            })
+        };
+
+        assert_eq!(
+            User::get_info(),
+            InstructMacroResult::Struct(StructInfo {
+                name: "User".to_string(),
+                r#type: "User".to_string(),
+                description: "A user of the app.\n\nOnly adults can sign up.".to_string(),
+                parameters: vec![
+                    field(
+                        "name",
+                        "String",
+                        "The full name of the user, as written on their passport"
+                    ),
+                    field("age", "u8", "The age of the user"),
+                ],
+                deny_unknown_fields: false,
+                is_optional: false,
+                is_list: false,
+            })
+        );
+    }
+
+    #[test]
+    fn test_doc_comments_describe_enums_and_variants() {
+        /// How far along a task is
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        enum Status {
+            Open,
+            Done,
+        }
+
+        /// What to do next
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        enum Action {
+            /// Search the web
+            Search { query: String },
+            /// Stop working
+            Stop,
+        }
+
+        match Status::get_info() {
+            InstructMacroResult::Enum(info) => {
+                assert_eq!(info.description, "How far along a task is")
+            }
+            _ => panic!("Expected EnumInfo but got a different InstructMacroResult variant"),
+        }
+
+        let info = match Action::get_info() {
+            InstructMacroResult::Union(info) => info,
+            _ => panic!("Expected UnionInfo but got a different InstructMacroResult variant"),
+        };
+        assert_eq!(info.description, "What to do next");
+        let descriptions: Vec<&str> = info
+            .variants
+            .iter()
+            .map(|variant| variant.description.as_str())
+            .collect();
+        assert_eq!(descriptions, vec!["Search the web", "Stop working"]);
+    }
+}
@@ -0,0 +1,156 @@
+extern crate instruct_macros_types;
+
+use instruct_macros::InstructMacro;
+use instruct_macros_types::{
+    InstructMacro, InstructMacroResult, Parameter, ParameterInfo, StructInfo,
+};
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+    #[description("A user")]
+    struct User {
+        name: String,
+    }
+
+    #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+    #[description("One page of results")]
+    struct Page<T> {
+        items: Vec<T>,
+        next: Option<String>,
+    }
+
+    fn struct_info<T: InstructMacro>() -> StructInfo {
+        match T::get_info() {
+            InstructMacroResult::Struct(info) => info,
+            _ => panic!("Expected StructInfo but got a different InstructMacroResult variant"),
+        }
+    }
+
+    #[test]
+    fn test_generic_struct_is_named_after_its_arguments() {
+        let info = struct_info::<Page<User>>();
+
+        assert_eq!(
+            info,
+            StructInfo {
+                name: "Page_User".to_string(),
+                r#type: "Page_User".to_string(),
+                description: "One page of results".to_string(),
+                parameters: vec![
+                    Parameter::Struct(StructInfo {
+                        name: "items".to_string(),
+                        r#type: "User".to_string(),
+                        description: "A user".to_string(),
+                        parameters: vec![Parameter::Field(ParameterInfo {
+                            name: "name".to_string(),
+                            r#type: "String".to_string(),
+                            comment: "".to_string(),
+                            constraints: vec![],
+                            is_optional: false,
+                            is_list: false,
+                        })],
+                        deny_unknown_fields: false,
+                        is_optional: false,
+                        is_list: true,
+                    }),
+                    Parameter::Field(ParameterInfo {
+                        name: "next".to_string(),
+                        r#type: "Option<String>".to_string(),
+                        comment: "".to_string(),
+                        constraints: vec![],
+                        is_optional: true,
+                        is_list: false,
+                    }),
+                ],
+                deny_unknown_fields: false,
+                is_optional: false,
+                is_list: false,
+            }
+        );
+        assert_eq!(struct_info::<Page<Page<User>>>().name, "Page_Page_User");
+    }
+
+    #[test]
+    fn test_generic_bounds_and_where_clauses() {
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        struct Pair<'a, A, B: Clone>
+        where
+            A: std::fmt::Debug,
+        {
+            first: A,
+            second: B,
+            label: &'a str,
+        }
+
+        #[derive(InstructMacro, Debug, Clone, Serialize, Deserialize)]
+        struct Score(u8);
+
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        enum Reply<T> {
+            Data(T),
+            Error { message: String },
+        }
+
+        assert_eq!(struct_info::<Pair<User, Score>>().name, "Pair_User_Score");
+        assert_eq!(Reply::<User>::get_info().name(), "Reply_User");
+        assert_eq!(Page::<Score>::get_info().name(), "Page_Score");
+    }
+
+    #[test]
+    fn test_generic_names_come_from_schema_name() {
+        struct Manual;
+
+        impl InstructMacro for Manual {
+            type Partial = ();
+
+            fn get_info() -> InstructMacroResult {
+                panic!("schema_name should not describe the type")
+            }
+
+            fn validate(&self) -> Result<(), String> {
+                Ok(())
+            }
+        }
+
+        // Naming a generic type doesn't describe its type arguments
+        assert_eq!(Manual::schema_name(), "Manual");
+        assert_eq!(Page::<Manual>::schema_name(), "Page_Manual");
+        assert_eq!(Page::<Box<Page<User>>>::schema_name(), "Page_Page_User");
+        assert_eq!(
+            Page::<Page<User>>::schema_name(),
+            struct_info::<Page<Page<User>>>().name
+        );
+    }
+
+    #[test]
+    fn test_generic_struct_validation() {
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        struct Labelled<T> {
+            #[validate(custom = "validate_label")]
+            label: String,
+            value: T,
+        }
+
+        fn validate_label(label: &str) -> Result<(), String> {
+            match label.is_empty() {
+                true => Err("must not be empty".to_string()),
+                false => Ok(()),
+            }
+        }
+
+        let labelled = Labelled {
+            label: "".to_string(),
+            value: User {
+                name: "Ann".to_string(),
+            },
+        };
+        assert_eq!(
+            labelled.validate().unwrap_err(),
+            "Validation failed for field 'label': must not be empty"
+        );
+    }
+}
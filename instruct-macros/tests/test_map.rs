@@ -0,0 +1,121 @@
+extern crate instruct_macros_types;
+
+use std::collections::{BTreeMap, HashMap};
+
+use instruct_macros::InstructMacro;
+use instruct_macros_types::{
+    InstructMacro, InstructMacroResult, ListInfo, MapInfo, Parameter, ParameterInfo, StructInfo,
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map_fields() {
+        #[derive(InstructMacro, Debug)]
+        #[allow(dead_code)]
+        struct Score {
+            value: f64,
+        }
+
+        #[derive(InstructMacro, Debug)]
+        #[allow(dead_code)]
+        struct Report {
+            #[description("Attribute name to value")]
+            attributes: HashMap<String, i32>,
+            scores: Option<BTreeMap<String, Score>>,
+            history: Vec<HashMap<String, bool>>,
+        }
+
+        let info = match Report::get_info() {
+            InstructMacroResult::Struct(info) => info,
+            _ => panic!("Expected StructInfo but got a different InstructMacroResult variant"),
+        };
+
+        assert_eq!(
+            info.parameters,
+            vec![
+                Parameter::Map(MapInfo {
+                    name: "attributes".to_string(),
+                    description: "Attribute name to value".to_string(),
+                    values: Box::new(Parameter::Field(ParameterInfo {
+                        name: "attributes".to_string(),
+                        r#type: "i32".to_string(),
+                        comment: "".to_string(),
+                        constraints: vec![],
+                        is_optional: false,
+                        is_list: false,
+                    })),
+                    is_optional: false,
+                }),
+                Parameter::Map(MapInfo {
+                    name: "scores".to_string(),
+                    description: "".to_string(),
+                    values: Box::new(Parameter::Struct(StructInfo {
+                        name: "scores".to_string(),
+                        r#type: "Score".to_string(),
+                        description: "".to_string(),
+                        parameters: vec![Parameter::Field(ParameterInfo {
+                            name: "value".to_string(),
+                            r#type: "f64".to_string(),
+                            comment: "".to_string(),
+                            constraints: vec![],
+                            is_optional: false,
+                            is_list: false,
+                        })],
+                        deny_unknown_fields: false,
+                        is_optional: false,
+                        is_list: false,
+                    })),
+                    is_optional: true,
+                }),
+                Parameter::List(ListInfo {
+                    name: "history".to_string(),
+                    description: "".to_string(),
+                    items: Box::new(Parameter::Map(MapInfo {
+                        name: "history".to_string(),
+                        description: "".to_string(),
+                        values: Box::new(Parameter::Field(ParameterInfo {
+                            name: "history".to_string(),
+                            r#type: "bool".to_string(),
+                            comment: "".to_string(),
+                            constraints: vec![],
+                            is_optional: false,
+                            is_list: false,
+                        })),
+                        is_optional: false,
+                    })),
+                    length: None,
+                    constraints: vec![],
+                    is_optional: false,
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_map_field_validation() {
+        #[derive(InstructMacro, Debug)]
+        struct Product {
+            #[validate(custom = "validate_attributes")]
+            attributes: HashMap<String, String>,
+        }
+
+        fn validate_attributes(attributes: &HashMap<String, String>) -> Result<(), String> {
+            match attributes.values().any(|value| value.is_empty()) {
+                true => Err("attribute values should not be empty".to_string()),
+                false => Ok(()),
+            }
+        }
+
+        let mut attributes = HashMap::new();
+        attributes.insert("color".to_string(), "".to_string());
+        let product = Product { attributes };
+
+        assert_eq!(
+            product.validate().unwrap_err(),
+            "Validation failed for field 'attributes': attribute values should not be empty"
+        );
+    }
+}
@@ -24,7 +24,9 @@ mod tests {
         let info = TestOptionStruct::get_info();
         let desired_struct = StructInfo {
             name: "TestOptionStruct".to_string(),
+            r#type: "TestOptionStruct".to_string(),
             description: "This is a struct with Option types".to_string(),
+            deny_unknown_fields: false,
             is_optional: false,
             is_list: false,
             parameters: vec![
@@ -32,6 +34,7 @@ mod tests {
                     name: "field1".to_string(),
                     r#type: "Option<String>".to_string(),
                     comment: "This is an optional string field".to_string(),
+                    constraints: vec![],
                     is_optional: true,
                     is_list: false,
                 }),
@@ -39,6 +42,7 @@ mod tests {
                     name: "field2".to_string(),
                     r#type: "Option<i32>".to_string(),
                     comment: "This is an optional integer field".to_string(),
+                    constraints: vec![],
                     is_optional: true,
                     is_list: false,
                 }),
@@ -76,15 +80,18 @@ mod tests {
         let info = MaybeUser::get_info();
         let desired_struct = StructInfo {
             name: "MaybeUser".to_string(),
+            r#type: "MaybeUser".to_string(),
             description: "This is a struct with Option<user> type".to_string(),
             parameters: vec![Parameter::Struct(StructInfo {
                 name: "user".to_string(),
+                r#type: "User".to_string(),
                 description: "This is an optional user field".to_string(),
                 parameters: vec![
                     Parameter::Field(ParameterInfo {
                         name: "name".to_string(),
                         r#type: "String".to_string(),
                         comment: "This is the user's name".to_string(),
+                        constraints: vec![],
                         is_optional: false,
                         is_list: false,
                     }),
@@ -92,13 +99,16 @@ mod tests {
                         name: "age".to_string(),
                         r#type: "i32".to_string(),
                         comment: "This is the user's age".to_string(),
+                        constraints: vec![],
                         is_optional: false,
                         is_list: false,
                     }),
                 ],
+                deny_unknown_fields: false,
                 is_optional: true,
                 is_list: false,
             })],
+            deny_unknown_fields: false,
             is_optional: false,
             is_list: false,
         };
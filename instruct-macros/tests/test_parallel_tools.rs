@@ -0,0 +1,100 @@
+extern crate instruct_macros_types;
+
+use instruct_macros::{validate, InstructMacro, ParallelTools};
+use instruct_macros_types::{
+    InstructMacro, InstructMacroResult, ParallelTools, Parameter, ParameterInfo, StructInfo,
+};
+use serde::Deserialize;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(InstructMacro, Debug, Deserialize, PartialEq)]
+    #[description("Looks up the weather in a city")]
+    struct Weather {
+        #[validate(custom = "validate_city")]
+        city: String,
+    }
+
+    #[validate]
+    fn validate_city(city: &str) -> Result<String, String> {
+        if city.is_empty() {
+            return Err("City should not be empty".to_string());
+        }
+        Ok(city.to_string())
+    }
+
+    #[derive(InstructMacro, Debug, Deserialize, PartialEq)]
+    #[description("Searches the web")]
+    struct Search {
+        query: String,
+    }
+
+    #[derive(ParallelTools, Debug, PartialEq)]
+    enum Action {
+        Weather(Weather),
+        WebSearch(Search),
+    }
+
+    #[test]
+    fn test_get_tools() {
+        let tools = Action::get_tools();
+
+        let search = match Search::get_info() {
+            InstructMacroResult::Struct(info) => info,
+            _ => panic!("Expected StructInfo"),
+        };
+        assert_eq!(tools.len(), 2);
+        assert_eq!(
+            tools[1],
+            Parameter::Struct(StructInfo {
+                name: "WebSearch".to_string(),
+                ..search
+            })
+        );
+        match &tools[0] {
+            Parameter::Struct(info) => {
+                assert_eq!(info.name, "Weather");
+                assert_eq!(info.description, "Looks up the weather in a city");
+                assert_eq!(
+                    info.parameters,
+                    vec![Parameter::Field(ParameterInfo {
+                        name: "city".to_string(),
+                        r#type: "String".to_string(),
+                        comment: "".to_string(),
+                        constraints: vec![],
+                        is_optional: false,
+                        is_list: false,
+                    })]
+                );
+            }
+            other => panic!("Expected a struct tool, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_from_tool_call() {
+        assert_eq!(
+            Action::from_tool_call("WebSearch", r#"{"query": "rust"}"#),
+            Ok(Action::WebSearch(Search {
+                query: "rust".to_string()
+            }))
+        );
+        assert_eq!(
+            Action::from_tool_call("Search", r#"{"query": "rust"}"#),
+            Err("Unknown tool Search".to_string())
+        );
+        assert!(Action::from_tool_call("Weather", r#"{"town": "Paris"}"#).is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        let action = Action::from_tool_call("Weather", r#"{"city": ""}"#).unwrap();
+
+        assert_eq!(
+            ParallelTools::validate(&action),
+            Err("Validation failed for field 'city': City should not be empty".to_string())
+        );
+    }
+}
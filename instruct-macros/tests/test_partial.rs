@@ -0,0 +1,161 @@
+extern crate instruct_macros_types;
+
+use std::collections::HashMap;
+
+use instruct_macros::InstructMacro;
+use instruct_macros_types::{InstructMacro, Parameter, ParameterInfo, StructInfo};
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(InstructMacro, Debug, Serialize, Deserialize, PartialEq)]
+    enum Role {
+        Admin,
+        Member,
+    }
+
+    #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+    struct Address {
+        city: String,
+        zip: u32,
+    }
+
+    #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+    struct Audit {
+        created_by: String,
+    }
+
+    #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct User {
+        full_name: String,
+        nickname: Option<String>,
+        role: Role,
+        addresses: Vec<Address>,
+        scores: HashMap<String, u8>,
+        #[serde(skip)]
+        #[allow(dead_code)]
+        secret: String,
+        #[serde(flatten)]
+        audit: Audit,
+    }
+
+    fn partial<T: InstructMacro>(json: serde_json::Value) -> T::Partial
+    where
+        T::Partial: serde::de::DeserializeOwned,
+    {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn test_partial_reads_what_has_arrived() {
+        let user = partial::<User>(serde_json::json!({
+            "fullName": "Jo",
+            "role": "Adm",
+            "addresses": [{"city": "Paris", "zip": 75001}, {"city": "Ly"}],
+            "scores": {"math": 9},
+            "created_by": "ad"
+        }));
+
+        assert_eq!(user.full_name, Some("Jo".to_string()));
+        assert_eq!(user.nickname, None);
+        // Enums only show up once they are complete
+        assert_eq!(user.role, None);
+        let addresses = user.addresses.unwrap();
+        assert_eq!(addresses[0].city, Some("Paris".to_string()));
+        assert_eq!(addresses[0].zip, Some(75001));
+        assert_eq!(addresses[1].city, Some("Ly".to_string()));
+        assert_eq!(addresses[1].zip, None);
+        assert_eq!(user.scores, Some(HashMap::from([("math".to_string(), 9)])));
+        assert_eq!(user.audit.unwrap().created_by, Some("ad".to_string()));
+
+        let user = partial::<User>(serde_json::json!({"role": "Admin", "nickname": null}));
+        assert_eq!(user.role, Some(Role::Admin));
+        assert_eq!(user.nickname, None);
+        assert_eq!(user.full_name, None);
+    }
+
+    #[test]
+    fn test_partial_of_generic_and_recursive_types() {
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        struct Page<T> {
+            items: Vec<T>,
+            next: Option<String>,
+        }
+
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        struct Node {
+            label: &'static str,
+            next: Option<Box<Node>>,
+        }
+
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        struct Folder {
+            name: String,
+            files: Vec<File>,
+        }
+
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        struct File {
+            name: String,
+            parent: Option<Box<Folder>>,
+        }
+
+        let page = partial::<Page<Address>>(serde_json::json!({"items": [{"city": "Paris"}]}));
+        assert_eq!(page.items.unwrap()[0].city, Some("Paris".to_string()));
+        assert_eq!(page.next, None);
+
+        let node = partial::<Node>(serde_json::json!({"label": "a", "next": {"label": "b"}}));
+        assert_eq!(node.label, Some("a".to_string()));
+        let next = node.next.unwrap();
+        assert_eq!(next.label, Some("b".to_string()));
+        assert!(next.next.is_none());
+
+        let folder = partial::<Folder>(serde_json::json!({
+            "name": "src",
+            "files": [{"name": "lib.rs", "parent": {"name": "s"}}]
+        }));
+        let file = &folder.files.unwrap()[0];
+        assert_eq!(file.name, Some("lib.rs".to_string()));
+        assert_eq!(file.parent.as_ref().unwrap().name, Some("s".to_string()));
+
+        assert_eq!(
+            format!(
+                "{:?}",
+                partial::<Address>(serde_json::json!({"city": "Paris"}))
+            ),
+            r#"AddressPartial { city: Some("Paris"), zip: None }"#
+        );
+    }
+
+    #[test]
+    fn test_types_that_cant_be_read_still_derive() {
+        // None of these derive `Deserialize` or `Debug`, so they have partials that
+        // can't be read, but they can still be described
+        #[derive(InstructMacro)]
+        #[allow(dead_code)]
+        enum Mood {
+            Happy,
+            Sad,
+        }
+
+        #[derive(InstructMacro)]
+        #[allow(dead_code)]
+        struct Rating(u8);
+
+        #[derive(InstructMacro)]
+        #[allow(dead_code)]
+        struct Review {
+            mood: Mood,
+            rating: Rating,
+            text: String,
+        }
+
+        assert_eq!(Review::get_info().name(), "Review");
+        assert_eq!(Mood::schema_name(), "Mood");
+        let partial = <Review as InstructMacro>::Partial::default();
+        assert!(partial.text.is_none());
+    }
+}
@@ -0,0 +1,120 @@
+extern crate instruct_macros_types;
+
+use instruct_macros::InstructMacro;
+use instruct_macros_types::{
+    InstructMacro, InstructMacroResult, Parameter, ParameterInfo, RefInfo, StructInfo,
+};
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+    #[description("A comment and its replies")]
+    struct Comment {
+        text: String,
+        #[description("Replies to the comment")]
+        replies: Vec<Comment>,
+    }
+
+    fn reference(name: &str, r#type: &str, is_optional: bool, is_list: bool) -> Parameter {
+        Parameter::Ref(RefInfo {
+            name: name.to_string(),
+            description: "".to_string(),
+            r#type: r#type.to_string(),
+            is_optional,
+            is_list,
+        })
+    }
+
+    #[test]
+    fn test_recursive_struct_refers_to_itself() {
+        assert_eq!(
+            Comment::get_info(),
+            InstructMacroResult::Struct(StructInfo {
+                name: "Comment".to_string(),
+                r#type: "Comment".to_string(),
+                description: "A comment and its replies".to_string(),
+                parameters: vec![
+                    Parameter::Field(ParameterInfo {
+                        name: "text".to_string(),
+                        r#type: "String".to_string(),
+                        comment: "".to_string(),
+                        constraints: vec![],
+                        is_optional: false,
+                        is_list: false,
+                    }),
+                    Parameter::Ref(RefInfo {
+                        name: "replies".to_string(),
+                        description: "Replies to the comment".to_string(),
+                        r#type: "Comment".to_string(),
+                        is_optional: false,
+                        is_list: true,
+                    }),
+                ],
+                deny_unknown_fields: false,
+                is_optional: false,
+                is_list: false,
+            })
+        );
+    }
+
+    #[test]
+    fn test_boxed_and_nested_recursion() {
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        struct Node {
+            value: i32,
+            next: Option<Box<Node>>,
+        }
+
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        enum Expr {
+            Number(f64),
+            Sum { terms: Vec<Expr> },
+            Negate(Box<Expr>),
+        }
+
+        let node = match Node::get_info() {
+            InstructMacroResult::Struct(info) => info,
+            _ => panic!("Expected StructInfo but got a different InstructMacroResult variant"),
+        };
+        assert_eq!(node.parameters[1], reference("next", "Node", true, false));
+
+        let expr = match Expr::get_info() {
+            InstructMacroResult::Union(info) => info,
+            _ => panic!("Expected UnionInfo but got a different InstructMacroResult variant"),
+        };
+        match &expr.variants[1].content {
+            Some(Parameter::Struct(sum)) => {
+                assert_eq!(
+                    sum.parameters,
+                    vec![reference("terms", "Expr", false, true)]
+                )
+            }
+            content => panic!("Expected a struct but got {:?}", content),
+        }
+        assert_eq!(
+            expr.variants[2].content,
+            Some(reference("Negate", "Expr", false, false))
+        );
+
+        // The same type describes itself in full again outside of its own description
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        struct Thread {
+            first: Comment,
+            last: Comment,
+        }
+
+        let thread = match Thread::get_info() {
+            InstructMacroResult::Struct(info) => info,
+            _ => panic!("Expected StructInfo but got a different InstructMacroResult variant"),
+        };
+        for param in thread.parameters.iter() {
+            match param {
+                Parameter::Struct(comment) => assert_eq!(comment.r#type, "Comment"),
+                param => panic!("Expected a struct but got {:?}", param),
+            }
+        }
+    }
+}
@@ -0,0 +1,169 @@
+extern crate instruct_macros_types;
+
+use instruct_macros::InstructMacro;
+use instruct_macros_types::{
+    InstructMacro, InstructMacroResult, Parameter, ParameterInfo, StructInfo,
+};
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn struct_info<T: InstructMacro>() -> StructInfo {
+        match T::get_info() {
+            InstructMacroResult::Struct(info) => info,
+            _ => panic!("Expected StructInfo but got a different InstructMacroResult variant"),
+        }
+    }
+
+    /// The name and whether it is optional of every parameter.
+    fn names(info: &StructInfo) -> Vec<(&str, bool)> {
+        info.parameters
+            .iter()
+            .map(|param| (param.name(), param.is_optional()))
+            .collect()
+    }
+
+    #[test]
+    fn test_field_names_follow_serde() {
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        #[allow(dead_code)]
+        #[serde(rename_all = "camelCase", deny_unknown_fields)]
+        struct Profile {
+            user_id: u32,
+            #[serde(rename = "handle")]
+            user_name: String,
+            #[serde(rename(serialize = "ignored", deserialize = "e-mail"))]
+            email: String,
+            #[serde(alias = "dob")]
+            date_of_birth: String,
+            #[serde(skip)]
+            cache: Vec<String>,
+            #[serde(skip_deserializing)]
+            computed: bool,
+            #[serde(default)]
+            retries: u8,
+            r#type: String,
+        }
+
+        let info = struct_info::<Profile>();
+        assert_eq!(
+            names(&info),
+            vec![
+                ("userId", false),
+                ("handle", false),
+                ("e-mail", false),
+                ("dateOfBirth", false),
+                ("retries", true),
+                ("type", false),
+            ]
+        );
+        assert!(info.deny_unknown_fields);
+        assert_eq!(
+            info.parameters[4],
+            Parameter::Field(ParameterInfo {
+                name: "retries".to_string(),
+                r#type: "u8".to_string(),
+                comment: "".to_string(),
+                constraints: vec![],
+                is_optional: true,
+                is_list: false,
+            })
+        );
+    }
+
+    #[test]
+    fn test_flatten_and_container_default() {
+        #[derive(InstructMacro, Debug, Default, Serialize, Deserialize)]
+        #[serde(default)]
+        struct Paging {
+            page: u32,
+            per_page: Option<u32>,
+        }
+
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        struct Timestamps {
+            created_at: String,
+        }
+
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        struct Query {
+            text: String,
+            #[serde(flatten)]
+            paging: Paging,
+            #[serde(flatten)]
+            timestamps: Option<Timestamps>,
+        }
+
+        assert_eq!(
+            names(&struct_info::<Paging>()),
+            vec![("page", true), ("per_page", true)]
+        );
+        assert_eq!(
+            names(&struct_info::<Query>()),
+            vec![
+                ("text", false),
+                ("page", true),
+                ("per_page", true),
+                ("created_at", true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_variant_names_follow_serde() {
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        #[serde(rename_all = "snake_case")]
+        enum Status {
+            InProgress,
+            #[serde(rename = "done")]
+            Finished,
+            #[serde(skip)]
+            #[allow(dead_code)]
+            Internal,
+        }
+
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        #[serde(
+            tag = "kind",
+            rename_all = "kebab-case",
+            rename_all_fields = "camelCase"
+        )]
+        enum Event {
+            PageView {
+                page_url: String,
+            },
+            #[serde(rename_all = "UPPERCASE")]
+            SignUp {
+                user_id: u32,
+            },
+        }
+
+        match Status::get_info() {
+            InstructMacroResult::Enum(info) => {
+                assert_eq!(info.r#enum, vec!["in_progress", "done"])
+            }
+            _ => panic!("Expected EnumInfo but got a different InstructMacroResult variant"),
+        }
+
+        let info = match Event::get_info() {
+            InstructMacroResult::Union(info) => info,
+            _ => panic!("Expected UnionInfo but got a different InstructMacroResult variant"),
+        };
+        let variants: Vec<(&str, &str)> = info
+            .variants
+            .iter()
+            .map(|variant| match &variant.content {
+                Some(Parameter::Struct(content)) => {
+                    (variant.name.as_str(), content.parameters[0].name())
+                }
+                content => panic!("Expected a struct but got {:?}", content),
+            })
+            .collect();
+        assert_eq!(
+            variants,
+            vec![("page-view", "pageUrl"), ("sign-up", "USER_ID")]
+        );
+    }
+}
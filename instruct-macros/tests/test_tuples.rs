@@ -0,0 +1,158 @@
+extern crate instruct_macros_types;
+
+use instruct_macros::InstructMacro;
+use instruct_macros_types::{
+    InstructMacro, InstructMacroResult, ListInfo, Parameter, ParameterInfo, StructInfo, TupleInfo,
+};
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+    #[description("An email address")]
+    struct Email(String);
+
+    #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+    #[description("A point on a map")]
+    struct Point(f64, f64);
+
+    fn field(name: &str, r#type: &str, comment: &str, is_list: bool) -> Parameter {
+        Parameter::Field(ParameterInfo {
+            name: name.to_string(),
+            r#type: r#type.to_string(),
+            comment: comment.to_string(),
+            constraints: vec![],
+            is_optional: false,
+            is_list,
+        })
+    }
+
+    #[test]
+    fn test_newtype_is_transparent() {
+        assert_eq!(
+            Email::get_info(),
+            InstructMacroResult::Parameter(field("Email", "String", "An email address", false))
+        );
+
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        struct Contact {
+            email: Email,
+            #[description("Other addresses to try")]
+            backups: Vec<Email>,
+        }
+
+        let info = match Contact::get_info() {
+            InstructMacroResult::Struct(info) => info,
+            _ => panic!("Expected StructInfo but got a different InstructMacroResult variant"),
+        };
+
+        assert_eq!(
+            info.parameters,
+            vec![
+                field("email", "String", "An email address", false),
+                field("backups", "String", "Other addresses to try", true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tuples_and_arrays() {
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        struct Route {
+            start: (f64, f64),
+            stops: Vec<Point>,
+            #[description("RGB color of the line")]
+            color: [u8; 3],
+        }
+
+        let point = |name: &str| {
+            Parameter::Tuple(TupleInfo {
+                name: name.to_string(),
+                description: "A point on a map".to_string(),
+                items: vec![
+                    field("Point", "f64", "", false),
+                    field("Point", "f64", "", false),
+                ],
+                is_optional: false,
+            })
+        };
+
+        let info = match Route::get_info() {
+            InstructMacroResult::Struct(info) => info,
+            _ => panic!("Expected StructInfo but got a different InstructMacroResult variant"),
+        };
+
+        assert_eq!(
+            info.parameters,
+            vec![
+                Parameter::Tuple(TupleInfo {
+                    name: "start".to_string(),
+                    description: "".to_string(),
+                    items: vec![
+                        field("start", "f64", "", false),
+                        field("start", "f64", "", false)
+                    ],
+                    is_optional: false,
+                }),
+                Parameter::List(ListInfo {
+                    name: "stops".to_string(),
+                    description: "A point on a map".to_string(),
+                    items: Box::new(point("Point")),
+                    length: None,
+                    constraints: vec![],
+                    is_optional: false,
+                }),
+                Parameter::List(ListInfo {
+                    name: "color".to_string(),
+                    description: "RGB color of the line".to_string(),
+                    items: Box::new(field("color", "u8", "", false)),
+                    length: Some(3),
+                    constraints: vec![],
+                    is_optional: false,
+                }),
+            ]
+        );
+        assert_eq!(
+            Point::get_info(),
+            InstructMacroResult::Parameter(point("Point"))
+        );
+    }
+
+    #[test]
+    fn test_unit_struct() {
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        #[description("Nothing to report")]
+        struct Empty;
+
+        assert_eq!(
+            Empty::get_info(),
+            InstructMacroResult::Parameter(field("Empty", "()", "Nothing to report", false))
+        );
+    }
+
+    #[test]
+    fn test_tuple_struct_validation() {
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        struct Shout(#[validate(custom = "validate_uppercase")] String);
+
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        struct Announcement(Shout);
+
+        fn validate_uppercase(text: &str) -> Result<(), String> {
+            match text.chars().any(|c| c.is_lowercase()) {
+                true => Err("should be uppercase".to_string()),
+                false => Ok(()),
+            }
+        }
+
+        assert!(Announcement(Shout("HELLO".to_string())).validate().is_ok());
+        assert_eq!(
+            Announcement(Shout("hello".to_string()))
+                .validate()
+                .unwrap_err(),
+            "Validation failed for field '0': should be uppercase"
+        );
+    }
+}
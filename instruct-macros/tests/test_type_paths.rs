@@ -0,0 +1,107 @@
+extern crate instruct_macros_types;
+
+use instruct_macros::InstructMacro;
+use instruct_macros_types::{
+    InstructMacro, InstructMacroResult, ListInfo, Parameter, ParameterInfo, StructInfo,
+};
+
+mod models {
+    use super::*;
+
+    #[derive(InstructMacro, Debug)]
+    #[allow(dead_code)]
+    #[description("A user")]
+    pub struct User {
+        pub name: std::string::String,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(name: &str, is_optional: bool, is_list: bool) -> Parameter {
+        Parameter::Struct(StructInfo {
+            name: name.to_string(),
+            r#type: "User".to_string(),
+            description: "A user".to_string(),
+            parameters: vec![Parameter::Field(ParameterInfo {
+                name: "name".to_string(),
+                r#type: "String".to_string(),
+                comment: "".to_string(),
+                constraints: vec![],
+                is_optional: false,
+                is_list: false,
+            })],
+            deny_unknown_fields: false,
+            is_optional,
+            is_list,
+        })
+    }
+
+    fn field(name: &str, r#type: &str, is_optional: bool, is_list: bool) -> Parameter {
+        Parameter::Field(ParameterInfo {
+            name: name.to_string(),
+            r#type: r#type.to_string(),
+            comment: "".to_string(),
+            constraints: vec![],
+            is_optional,
+            is_list,
+        })
+    }
+
+    #[test]
+    fn test_qualified_paths_and_nested_wrappers() {
+        #[derive(InstructMacro, Debug)]
+        #[allow(dead_code)]
+        struct Team {
+            owner: crate::models::User,
+            backup: Box<models::User>,
+            members: Option<Vec<models::User>>,
+            scores: Vec<Option<i32>>,
+            grid: Vec<Vec<f64>>,
+            groups: Vec<Vec<models::User>>,
+            motto: Option<std::string::String>,
+        }
+
+        let info = match Team::get_info() {
+            InstructMacroResult::Struct(info) => info,
+            _ => panic!("Expected StructInfo but got a different InstructMacroResult variant"),
+        };
+
+        assert_eq!(
+            info.parameters,
+            vec![
+                user("owner", false, false),
+                user("backup", false, false),
+                user("members", true, true),
+                // The items of a list of `Option`s may be null
+                Parameter::List(ListInfo {
+                    name: "scores".to_string(),
+                    description: "".to_string(),
+                    items: Box::new(field("scores", "Option<i32>", true, false)),
+                    length: None,
+                    constraints: vec![],
+                    is_optional: false,
+                }),
+                Parameter::List(ListInfo {
+                    name: "grid".to_string(),
+                    description: "".to_string(),
+                    items: Box::new(field("grid", "f64", false, true)),
+                    length: None,
+                    constraints: vec![],
+                    is_optional: false,
+                }),
+                Parameter::List(ListInfo {
+                    name: "groups".to_string(),
+                    description: "".to_string(),
+                    items: Box::new(user("groups", false, true)),
+                    length: None,
+                    constraints: vec![],
+                    is_optional: false,
+                }),
+                field("motto", "Option<String>", true, false),
+            ]
+        );
+    }
+}
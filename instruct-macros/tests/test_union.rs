@@ -0,0 +1,255 @@
+extern crate instruct_macros_types;
+
+use instruct_macros::InstructMacro;
+use instruct_macros_types::{
+    InstructMacro, InstructMacroResult, Parameter, ParameterInfo, StructInfo, UnionInfo,
+    UnionTagging, VariantInfo,
+};
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+    #[description("A reply to send")]
+    struct Message {
+        #[description("The text of the reply")]
+        text: String,
+    }
+
+    fn union_info<T: InstructMacro>() -> UnionInfo {
+        match T::get_info() {
+            InstructMacroResult::Union(info) => info,
+            _ => panic!("Expected UnionInfo but got a different InstructMacroResult variant"),
+        }
+    }
+
+    #[test]
+    fn test_externally_tagged_union() {
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        #[allow(dead_code)]
+        #[description("What to do next")]
+        enum Action {
+            #[description("Search the web")]
+            Search {
+                query: String,
+            },
+            Reply(Message),
+            Wait(u32),
+            Stop,
+        }
+
+        let info = union_info::<Action>();
+
+        assert_eq!(
+            info,
+            UnionInfo {
+                name: "Action".to_string(),
+                r#type: "Action".to_string(),
+                description: "What to do next".to_string(),
+                tagging: UnionTagging::External,
+                is_optional: false,
+                is_list: false,
+                variants: vec![
+                    VariantInfo {
+                        name: "Search".to_string(),
+                        description: "Search the web".to_string(),
+                        content: Some(Parameter::Struct(StructInfo {
+                            name: "Search".to_string(),
+                            r#type: "".to_string(),
+                            description: "Search the web".to_string(),
+                            parameters: vec![Parameter::Field(ParameterInfo {
+                                name: "query".to_string(),
+                                r#type: "String".to_string(),
+                                comment: "".to_string(),
+                                constraints: vec![],
+                                is_optional: false,
+                                is_list: false,
+                            })],
+                            deny_unknown_fields: false,
+                            is_optional: false,
+                            is_list: false,
+                        })),
+                    },
+                    VariantInfo {
+                        name: "Reply".to_string(),
+                        description: "".to_string(),
+                        content: Some(Parameter::Struct(StructInfo {
+                            name: "Reply".to_string(),
+                            r#type: "Message".to_string(),
+                            description: "A reply to send".to_string(),
+                            parameters: vec![Parameter::Field(ParameterInfo {
+                                name: "text".to_string(),
+                                r#type: "String".to_string(),
+                                comment: "The text of the reply".to_string(),
+                                constraints: vec![],
+                                is_optional: false,
+                                is_list: false,
+                            })],
+                            deny_unknown_fields: false,
+                            is_optional: false,
+                            is_list: false,
+                        })),
+                    },
+                    VariantInfo {
+                        name: "Wait".to_string(),
+                        description: "".to_string(),
+                        content: Some(Parameter::Field(ParameterInfo {
+                            name: "Wait".to_string(),
+                            r#type: "u32".to_string(),
+                            comment: "".to_string(),
+                            constraints: vec![],
+                            is_optional: false,
+                            is_list: false,
+                        })),
+                    },
+                    VariantInfo {
+                        name: "Stop".to_string(),
+                        description: "".to_string(),
+                        content: None,
+                    },
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn test_serde_tagging() {
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        #[serde(tag = "type")]
+        enum Internal {
+            Reply(Message),
+        }
+
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        #[serde(tag = "type", content = "data")]
+        enum Adjacent {
+            Reply(Message),
+        }
+
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        #[serde(untagged)]
+        enum Untagged {
+            Reply(Message),
+        }
+
+        assert_eq!(
+            union_info::<Internal>().tagging,
+            UnionTagging::Internal {
+                tag: "type".to_string()
+            }
+        );
+        assert_eq!(
+            union_info::<Adjacent>().tagging,
+            UnionTagging::Adjacent {
+                tag: "type".to_string(),
+                content: "data".to_string()
+            }
+        );
+        assert_eq!(union_info::<Untagged>().tagging, UnionTagging::Untagged);
+    }
+
+    #[test]
+    fn test_tagged_unit_enums_are_unions() {
+        #[derive(InstructMacro, Debug, PartialEq, Serialize, Deserialize)]
+        #[serde(tag = "type")]
+        enum Internal {
+            A,
+            B,
+        }
+
+        #[derive(InstructMacro, Debug, PartialEq, Serialize, Deserialize)]
+        #[serde(tag = "type", content = "data")]
+        enum Adjacent {
+            A,
+            B,
+        }
+
+        #[derive(InstructMacro, Debug, PartialEq, Serialize, Deserialize)]
+        #[serde(untagged)]
+        enum Untagged {
+            A,
+        }
+
+        let unit_variants = |names: &[&str]| -> Vec<VariantInfo> {
+            names
+                .iter()
+                .map(|name| VariantInfo {
+                    name: name.to_string(),
+                    description: "".to_string(),
+                    content: None,
+                })
+                .collect()
+        };
+
+        let internal = union_info::<Internal>();
+        assert_eq!(
+            internal.tagging,
+            UnionTagging::Internal {
+                tag: "type".to_string()
+            }
+        );
+        assert_eq!(internal.variants, unit_variants(&["A", "B"]));
+        assert_eq!(
+            serde_json::from_str::<Internal>(r#"{"type": "A"}"#).unwrap(),
+            Internal::A
+        );
+        assert!(serde_json::from_str::<Internal>(r#""A""#).is_err());
+
+        let adjacent = union_info::<Adjacent>();
+        assert_eq!(
+            adjacent.tagging,
+            UnionTagging::Adjacent {
+                tag: "type".to_string(),
+                content: "data".to_string()
+            }
+        );
+        assert_eq!(adjacent.variants, unit_variants(&["A", "B"]));
+        assert_eq!(
+            serde_json::from_str::<Adjacent>(r#"{"type": "B"}"#).unwrap(),
+            Adjacent::B
+        );
+        assert!(serde_json::from_str::<Adjacent>(r#""B""#).is_err());
+
+        let untagged = union_info::<Untagged>();
+        assert_eq!(untagged.tagging, UnionTagging::Untagged);
+        assert_eq!(untagged.variants, unit_variants(&["A"]));
+        assert_eq!(
+            serde_json::from_str::<Untagged>("null").unwrap(),
+            Untagged::A
+        );
+        assert!(Untagged::A.validate().is_ok());
+    }
+
+    #[test]
+    fn test_union_validates_the_active_variant() {
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        struct Shout {
+            #[validate(custom = "validate_uppercase")]
+            text: String,
+        }
+
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        enum Action {
+            Shout(Shout),
+            Stop,
+        }
+
+        fn validate_uppercase(text: &str) -> Result<(), String> {
+            match text.chars().any(|c| c.is_lowercase()) {
+                true => Err("should be uppercase".to_string()),
+                false => Ok(()),
+            }
+        }
+
+        let quiet = Action::Shout(Shout {
+            text: "hello".to_string(),
+        });
+        assert_eq!(
+            quiet.validate().unwrap_err(),
+            "Validation failed for field 'text': should be uppercase"
+        );
+        assert!(Action::Stop.validate().is_ok());
+    }
+}
@@ -0,0 +1,236 @@
+extern crate instruct_macros_types;
+
+use instruct_macros::InstructMacro;
+use instruct_macros_types::{
+    Constraint, InstructMacro, InstructMacroResult, ListInfo, Parameter, ParameterInfo, StructInfo,
+};
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+    struct Signup {
+        #[validate(length(min = 2, max = 20), regex = "^[a-z0-9_]+$")]
+        username: String,
+        #[validate(email)]
+        email: String,
+        #[validate(range(min = 13, max = 130))]
+        age: u8,
+        #[validate(non_empty, url)]
+        links: Vec<String>,
+        #[validate(one_of("free", "pro"))]
+        plan: Option<String>,
+        #[validate(contains = "!")]
+        greeting: String,
+    }
+
+    fn signup() -> Signup {
+        Signup {
+            username: "ann_1".to_string(),
+            email: "ann@example.com".to_string(),
+            age: 30,
+            links: vec!["https://example.com".to_string()],
+            plan: None,
+            greeting: "Hi!".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_builtin_validators() {
+        assert!(signup().validate().is_ok());
+
+        let cases: Vec<(Signup, &str)> = vec![
+            (
+                Signup {
+                    username: "a".to_string(),
+                    ..signup()
+                },
+                "Validation failed for field 'username': length must be at least 2, found 1",
+            ),
+            (
+                Signup {
+                    username: "Ann".to_string(),
+                    ..signup()
+                },
+                "Validation failed for field 'username': 'Ann' does not match the pattern '^[a-z0-9_]+$'",
+            ),
+            (
+                Signup {
+                    email: "ann".to_string(),
+                    ..signup()
+                },
+                "Validation failed for field 'email': 'ann' is not a valid email address",
+            ),
+            (
+                Signup {
+                    age: 12,
+                    ..signup()
+                },
+                "Validation failed for field 'age': 12 is less than the minimum of 13",
+            ),
+            (
+                Signup {
+                    links: vec![],
+                    ..signup()
+                },
+                "Validation failed for field 'links': must not be empty",
+            ),
+            (
+                Signup {
+                    links: vec!["https://example.com".to_string(), "example".to_string()],
+                    ..signup()
+                },
+                "Validation failed for field 'links': 'example' is not a valid URL",
+            ),
+            (
+                Signup {
+                    plan: Some("team".to_string()),
+                    ..signup()
+                },
+                "Validation failed for field 'plan': 'team' must be one of: free, pro",
+            ),
+            (
+                Signup {
+                    greeting: "Hi".to_string(),
+                    ..signup()
+                },
+                "Validation failed for field 'greeting': 'Hi' does not contain '!'",
+            ),
+        ];
+        for (signup, error) in cases {
+            assert_eq!(signup.validate().unwrap_err(), error);
+        }
+    }
+
+    #[test]
+    fn test_validators_constrain_the_parameters() {
+        let info = match Signup::get_info() {
+            InstructMacroResult::Struct(info) => info,
+            _ => panic!("Expected StructInfo but got a different InstructMacroResult variant"),
+        };
+        let constraints: Vec<&Vec<Constraint>> = info
+            .parameters
+            .iter()
+            .map(|param| match param {
+                Parameter::Field(field) => &field.constraints,
+                param => panic!("Expected a field but got {:?}", param),
+            })
+            .collect();
+
+        assert_eq!(
+            constraints,
+            vec![
+                &vec![
+                    Constraint::Length {
+                        min: Some(2),
+                        max: Some(20)
+                    },
+                    Constraint::Pattern("^[a-z0-9_]+$".to_string()),
+                ],
+                &vec![Constraint::Email],
+                &vec![Constraint::Range {
+                    min: Some(13.0),
+                    max: Some(130.0)
+                }],
+                &vec![Constraint::NonEmpty, Constraint::Url],
+                &vec![Constraint::OneOf(vec!["free".into(), "pro".into()])],
+                &vec![Constraint::Contains("!".to_string())],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validators_on_models_and_variants() {
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        struct Tag {
+            name: String,
+        }
+
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        struct Post {
+            #[validate(length(max = 2))]
+            tags: Vec<Tag>,
+            #[validate(range(min = -1.5), custom = "validate_rating")]
+            rating: f64,
+        }
+
+        fn validate_rating(rating: &f64) -> Result<(), String> {
+            match rating.is_nan() {
+                true => Err("must be a number".to_string()),
+                false => Ok(()),
+            }
+        }
+
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        enum Action {
+            Rate {
+                #[validate(one_of(1, 2, 3))]
+                stars: u8,
+            },
+            Stop,
+        }
+
+        let info = match Post::get_info() {
+            InstructMacroResult::Struct(info) => info,
+            _ => panic!("Expected StructInfo but got a different InstructMacroResult variant"),
+        };
+        assert_eq!(
+            info.parameters[0],
+            Parameter::List(ListInfo {
+                name: "tags".to_string(),
+                description: "".to_string(),
+                items: Box::new(Parameter::Struct(StructInfo {
+                    name: "tags".to_string(),
+                    r#type: "Tag".to_string(),
+                    description: "".to_string(),
+                    parameters: vec![Parameter::Field(ParameterInfo {
+                        name: "name".to_string(),
+                        r#type: "String".to_string(),
+                        comment: "".to_string(),
+                        constraints: vec![],
+                        is_optional: false,
+                        is_list: false,
+                    })],
+                    deny_unknown_fields: false,
+                    is_optional: false,
+                    is_list: false,
+                })),
+                length: None,
+                constraints: vec![Constraint::Length {
+                    min: None,
+                    max: Some(2)
+                }],
+                is_optional: false,
+            })
+        );
+
+        let tag = |name: &str| Tag {
+            name: name.to_string(),
+        };
+        let post = Post {
+            tags: vec![tag("a"), tag("b"), tag("c")],
+            rating: f64::NAN,
+        };
+        assert_eq!(
+            post.validate().unwrap_err(),
+            "Validation failed for field 'tags': length must be at most 2, found 3"
+        );
+        let post = Post {
+            tags: vec![],
+            rating: f64::NAN,
+        };
+        assert_eq!(
+            post.validate().unwrap_err(),
+            "Validation failed for field 'rating': must be a number"
+        );
+
+        assert!(Action::Rate { stars: 2 }.validate().is_ok());
+        assert!(Action::Stop.validate().is_ok());
+        assert_eq!(
+            Action::Rate { stars: 5 }.validate().unwrap_err(),
+            "Validation failed for field 'stars': 5 must be one of: 1, 2, 3"
+        );
+    }
+}
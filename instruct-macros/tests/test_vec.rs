@@ -12,6 +12,7 @@ mod tests {
     #[test]
     fn test_vec_simple_type() {
         #[derive(InstructMacro, Debug)]
+        #[allow(dead_code)]
         #[description("This is a struct with Option types")]
         struct Numbers {
             #[description("This is a list of numbers")]
@@ -22,14 +23,17 @@ mod tests {
 
         let desired_struct = StructInfo {
             name: "Numbers".to_string(),
+            r#type: "Numbers".to_string(),
             description: "This is a struct with Option types".to_string(),
             parameters: vec![Parameter::Field(ParameterInfo {
                 name: "numbers".to_string(),
                 r#type: "i32".to_string(),
                 comment: "This is a list of numbers".to_string(),
+                constraints: vec![],
                 is_optional: false,
                 is_list: true,
             })],
+            deny_unknown_fields: false,
             is_optional: false,
             is_list: false,
         };
@@ -45,6 +49,7 @@ mod tests {
     #[test]
     fn test_vec_struct() {
         #[derive(InstructMacro, Debug)]
+        #[allow(dead_code)]
         #[description("This is a struct with Option types")]
         struct User {
             #[description("This is a list of numbers")]
@@ -52,6 +57,7 @@ mod tests {
         }
 
         #[derive(InstructMacro, Debug)]
+        #[allow(dead_code)]
         #[description("This is a struct with Option types")]
         struct Users {
             #[description("This is a list of users")]
@@ -62,20 +68,25 @@ mod tests {
 
         let desired_struct = StructInfo {
             name: "Users".to_string(),
+            r#type: "Users".to_string(),
             description: "This is a struct with Option types".to_string(),
             parameters: vec![Parameter::Struct(StructInfo {
                 name: "users".to_string(),
+                r#type: "User".to_string(),
                 description: "This is a list of users".to_string(),
                 parameters: vec![Parameter::Field(ParameterInfo {
                     name: "name".to_string(),
                     r#type: "String".to_string(),
                     comment: "This is a list of numbers".to_string(),
+                    constraints: vec![],
                     is_optional: false,
                     is_list: false,
                 })],
+                deny_unknown_fields: false,
                 is_optional: false,
                 is_list: true,
             })],
+            deny_unknown_fields: false,
             is_optional: false,
             is_list: false,
         };
@@ -0,0 +1,273 @@
+use instruct_macros_types::StructInfo;
+use openai_api_rs::v1::chat_completion::{
+    ChatCompletionRequest, Content, ContentType, MessageRole,
+};
+use serde_json::{json, Value};
+
+#[cfg(feature = "async")]
+use super::ChatFuture;
+use super::{first_tool, http, schema_prompt, Backend, BackendResponse, Reask, ToolCall};
+use crate::{helpers, InstructorError, Mode};
+
+const API_URL_V1: &str = "https://api.anthropic.com/v1";
+const API_VERSION: &str = "2023-06-01";
+const DEFAULT_MAX_TOKENS: i64 = 4096;
+
+/// Backend for the Anthropic Messages API.
+///
+/// The response model is sent as the only tool and the model is forced to call it.
+/// Parallel tool calls offer every tool and let Claude call any number of them.
+/// Failed attempts are replayed as the assistant's `tool_use` block followed by a
+/// `tool_result` block with `is_error: true`. JSON and Markdown-JSON mode describe the
+/// schema in the system prompt instead. JSON Schema mode is not supported.
+pub struct AnthropicBackend {
+    pub api_endpoint: String,
+    pub api_key: String,
+}
+
+impl AnthropicBackend {
+    pub fn new(api_key: String) -> Self {
+        let endpoint =
+            std::env::var("ANTHROPIC_API_BASE").unwrap_or_else(|_| API_URL_V1.to_owned());
+        Self::new_with_endpoint(endpoint, api_key)
+    }
+
+    pub fn new_with_endpoint(api_endpoint: String, api_key: String) -> Self {
+        Self {
+            api_endpoint,
+            api_key,
+        }
+    }
+
+    fn build_body(
+        req: &ChatCompletionRequest,
+        response_model: &StructInfo,
+        mode: Mode,
+        reasks: &[Reask],
+    ) -> Result<Value, InstructorError> {
+        if mode == Mode::JsonSchema {
+            return Err(InstructorError::UnsupportedMode {
+                backend: "Anthropic".to_string(),
+                mode,
+            });
+        }
+
+        let mut system = Vec::new();
+        let mut messages = Vec::new();
+
+        for message in req.messages.iter() {
+            match message.role {
+                MessageRole::system => {
+                    if let Content::Text(text) = &message.content {
+                        system.push(text.clone());
+                    }
+                }
+                MessageRole::user | MessageRole::function => messages.push(json!({
+                    "role": "user",
+                    "content": convert_content(&message.content),
+                })),
+                MessageRole::assistant => messages.push(json!({
+                    "role": "assistant",
+                    "content": convert_content(&message.content),
+                })),
+            }
+        }
+
+        if let Some(prompt) = schema_prompt(response_model, mode)? {
+            system.push(prompt);
+        }
+        for reask in reasks.iter() {
+            messages.extend(reask_messages(reask));
+        }
+
+        let mut body = json!({
+            "model": req.model,
+            "max_tokens": req.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS),
+            "messages": messages,
+        });
+
+        if mode == Mode::Tools {
+            body["tools"] = json!([tool(response_model)?]);
+            body["tool_choice"] = json!({"type": "tool", "name": response_model.name});
+        }
+
+        if !system.is_empty() {
+            body["system"] = json!(system.join("\n"));
+        }
+        if let Some(temperature) = req.temperature {
+            body["temperature"] = json!(temperature);
+        }
+        if let Some(top_p) = req.top_p {
+            body["top_p"] = json!(top_p);
+        }
+        if let Some(stop) = &req.stop {
+            body["stop_sequences"] = json!(stop);
+        }
+
+        Ok(body)
+    }
+
+    fn parse_body(body: &Value) -> Result<BackendResponse, InstructorError> {
+        let content = body["content"].as_array().ok_or_else(|| {
+            InstructorError::Transport(format!("Unexpected response from Anthropic: {}", body))
+        })?;
+
+        let tool_calls: Vec<ToolCall> = content
+            .iter()
+            .filter(|block| block["type"] == "tool_use")
+            .map(|block| ToolCall {
+                id: block["id"].as_str().unwrap_or_default().to_string(),
+                name: block["name"].as_str().unwrap_or_default().to_string(),
+                arguments: block["input"].to_string(),
+            })
+            .collect();
+
+        if !tool_calls.is_empty() {
+            return Ok(BackendResponse::ToolCalls(tool_calls));
+        }
+
+        let text = content
+            .iter()
+            .filter_map(|block| block["text"].as_str())
+            .collect::<Vec<&str>>()
+            .join("");
+        Ok(BackendResponse::Text(text))
+    }
+}
+
+impl Backend for AnthropicBackend {
+    fn chat(
+        &self,
+        req: &ChatCompletionRequest,
+        response_model: &StructInfo,
+        mode: Mode,
+        reasks: &[Reask],
+    ) -> Result<BackendResponse, InstructorError> {
+        let body = Self::build_body(req, response_model, mode, reasks)?;
+        let url = format!("{}/messages", self.api_endpoint);
+        let headers = [
+            ("x-api-key", self.api_key.as_str()),
+            ("anthropic-version", API_VERSION),
+        ];
+
+        let response = http::post_json(&url, &headers, &body)?;
+        Self::parse_body(&response)
+    }
+
+    #[cfg(feature = "async")]
+    fn chat_async<'a>(
+        &'a self,
+        req: &'a ChatCompletionRequest,
+        response_model: &'a StructInfo,
+        mode: Mode,
+        reasks: &'a [Reask],
+    ) -> ChatFuture<'a> {
+        Box::pin(async move {
+            let body = Self::build_body(req, response_model, mode, reasks)?;
+            let url = format!("{}/messages", self.api_endpoint);
+            let headers = [
+                ("x-api-key", self.api_key.as_str()),
+                ("anthropic-version", API_VERSION),
+            ];
+
+            let response = http::post_json_async(&url, &headers, &body).await?;
+            Self::parse_body(&response)
+        })
+    }
+
+    fn chat_parallel(
+        &self,
+        req: &ChatCompletionRequest,
+        tools: &[StructInfo],
+        reasks: &[Reask],
+    ) -> Result<BackendResponse, InstructorError> {
+        let mut body = Self::build_body(req, first_tool(tools)?, Mode::Tools, reasks)?;
+        body["tools"] = json!(tools
+            .iter()
+            .map(tool)
+            .collect::<Result<Vec<Value>, InstructorError>>()?);
+        body["tool_choice"] = json!({"type": "any"});
+        let url = format!("{}/messages", self.api_endpoint);
+        let headers = [
+            ("x-api-key", self.api_key.as_str()),
+            ("anthropic-version", API_VERSION),
+        ];
+
+        let response = http::post_json(&url, &headers, &body)?;
+        Self::parse_body(&response)
+    }
+}
+
+fn tool(response_model: &StructInfo) -> Result<Value, InstructorError> {
+    Ok(json!({
+        "name": response_model.name,
+        "description": response_model.description,
+        "input_schema": helpers::get_response_model(response_model.clone())?,
+    }))
+}
+
+fn convert_content(content: &Content) -> Value {
+    match content {
+        Content::Text(text) => json!(text),
+        Content::ImageUrl(parts) => Value::Array(
+            parts
+                .iter()
+                .filter_map(|part| {
+                    match part.r#type {
+                    ContentType::text => part
+                        .text
+                        .as_ref()
+                        .map(|text| json!({"type": "text", "text": text})),
+                    ContentType::image_url => part.image_url.as_ref().map(|image| {
+                        json!({"type": "image", "source": {"type": "url", "url": image.url}})
+                    }),
+                }
+                })
+                .collect(),
+        ),
+    }
+}
+
+fn reask_messages(reask: &Reask) -> Vec<Value> {
+    match &reask.response {
+        BackendResponse::ToolCalls(tool_calls) => {
+            let tool_uses: Vec<Value> = tool_calls
+                .iter()
+                .map(|tool_call| {
+                    let input: Value =
+                        serde_json::from_str(&tool_call.arguments).unwrap_or_else(|_| json!({}));
+                    json!({
+                        "type": "tool_use",
+                        "id": tool_call.id,
+                        "name": tool_call.name,
+                        "input": input,
+                    })
+                })
+                .collect();
+            let tool_results: Vec<Value> = tool_calls
+                .iter()
+                .map(|tool_call| {
+                    json!({
+                        "type": "tool_result",
+                        "tool_use_id": tool_call.id,
+                        "content": reask.error,
+                        "is_error": true,
+                    })
+                })
+                .collect();
+
+            vec![
+                json!({"role": "assistant", "content": tool_uses}),
+                json!({"role": "user", "content": tool_results}),
+            ]
+        }
+        // Anthropic rejects empty text blocks, so an empty answer only gets the error.
+        BackendResponse::Json(text) | BackendResponse::Text(text) if text.is_empty() => {
+            vec![json!({"role": "user", "content": reask.error})]
+        }
+        BackendResponse::Json(text) | BackendResponse::Text(text) => vec![
+            json!({"role": "assistant", "content": text}),
+            json!({"role": "user", "content": reask.error}),
+        ],
+    }
+}
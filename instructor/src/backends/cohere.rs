@@ -0,0 +1,201 @@
+use instruct_macros_types::StructInfo;
+use openai_api_rs::v1::chat_completion::ChatCompletionRequest;
+use serde_json::{json, Value};
+
+#[cfg(feature = "async")]
+use super::ChatFuture;
+use super::{
+    function_tool, http, json_schema, schema_prompt, text_messages, with_system_prompt, Backend,
+    BackendResponse, Reask, ToolCall,
+};
+use crate::{InstructorError, Mode};
+
+const API_URL_V2: &str = "https://api.cohere.com/v2";
+
+/// Backend for Cohere's v2 chat API.
+///
+/// Cohere returns tool calls inside `message.tool_calls` next to a free text
+/// `tool_plan`, rather than inside `choices` like OpenAI. Failed attempts are replayed
+/// as the assistant's tool calls followed by a `tool` message carrying the error.
+pub struct CohereBackend {
+    pub api_endpoint: String,
+    pub api_key: String,
+}
+
+impl CohereBackend {
+    pub fn new(api_key: String) -> Self {
+        let endpoint = std::env::var("COHERE_API_BASE").unwrap_or_else(|_| API_URL_V2.to_owned());
+        Self::new_with_endpoint(endpoint, api_key)
+    }
+
+    pub fn new_with_endpoint(api_endpoint: String, api_key: String) -> Self {
+        Self {
+            api_endpoint,
+            api_key,
+        }
+    }
+
+    fn build_body(
+        req: &ChatCompletionRequest,
+        response_model: &StructInfo,
+        mode: Mode,
+        reasks: &[Reask],
+    ) -> Result<Value, InstructorError> {
+        let mut messages = text_messages(req);
+        if let Some(prompt) = schema_prompt(response_model, mode)? {
+            with_system_prompt(&mut messages, &prompt);
+        }
+        for reask in reasks.iter() {
+            messages.extend(reask_messages(reask));
+        }
+
+        let mut body = json!({
+            "model": req.model,
+            "messages": messages,
+        });
+
+        match mode {
+            Mode::Tools => {
+                body["tools"] = json!([function_tool(response_model)?]);
+                body["tool_choice"] = json!("REQUIRED");
+            }
+            Mode::Json => body["response_format"] = json!({"type": "json_object"}),
+            Mode::JsonSchema => {
+                body["response_format"] = json!({
+                    "type": "json_object",
+                    "json_schema": json_schema(response_model)?,
+                })
+            }
+            Mode::MarkdownJson => {}
+        }
+
+        if let Some(temperature) = req.temperature {
+            body["temperature"] = json!(temperature);
+        }
+        if let Some(top_p) = req.top_p {
+            body["p"] = json!(top_p);
+        }
+        if let Some(max_tokens) = req.max_tokens {
+            body["max_tokens"] = json!(max_tokens);
+        }
+        if let Some(stop) = &req.stop {
+            body["stop_sequences"] = json!(stop);
+        }
+        if let Some(seed) = req.seed {
+            body["seed"] = json!(seed);
+        }
+
+        Ok(body)
+    }
+
+    fn parse_body(body: &Value) -> Result<BackendResponse, InstructorError> {
+        let message = body["message"].as_object().ok_or_else(|| {
+            InstructorError::Transport(format!("Unexpected response from Cohere: {}", body))
+        })?;
+
+        let tool_calls: Vec<ToolCall> = message
+            .get("tool_calls")
+            .and_then(|tool_calls| tool_calls.as_array())
+            .map(|tool_calls| {
+                tool_calls
+                    .iter()
+                    .map(|tool_call| ToolCall {
+                        id: tool_call["id"].as_str().unwrap_or_default().to_string(),
+                        name: tool_call["function"]["name"]
+                            .as_str()
+                            .unwrap_or_default()
+                            .to_string(),
+                        arguments: match &tool_call["function"]["arguments"] {
+                            Value::String(arguments) => arguments.clone(),
+                            arguments => arguments.to_string(),
+                        },
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        if !tool_calls.is_empty() {
+            return Ok(BackendResponse::ToolCalls(tool_calls));
+        }
+
+        let text = message
+            .get("content")
+            .and_then(|content| content.as_array())
+            .map(|content| {
+                content
+                    .iter()
+                    .filter_map(|block| block["text"].as_str())
+                    .collect::<Vec<&str>>()
+                    .join("")
+            })
+            .unwrap_or_default();
+        Ok(BackendResponse::Text(text))
+    }
+}
+
+impl Backend for CohereBackend {
+    fn chat(
+        &self,
+        req: &ChatCompletionRequest,
+        response_model: &StructInfo,
+        mode: Mode,
+        reasks: &[Reask],
+    ) -> Result<BackendResponse, InstructorError> {
+        let body = Self::build_body(req, response_model, mode, reasks)?;
+        let url = format!("{}/chat", self.api_endpoint);
+        let authorization = format!("Bearer {}", self.api_key);
+        let headers = [("Authorization", authorization.as_str())];
+
+        let response = http::post_json(&url, &headers, &body)?;
+        Self::parse_body(&response)
+    }
+
+    #[cfg(feature = "async")]
+    fn chat_async<'a>(
+        &'a self,
+        req: &'a ChatCompletionRequest,
+        response_model: &'a StructInfo,
+        mode: Mode,
+        reasks: &'a [Reask],
+    ) -> ChatFuture<'a> {
+        Box::pin(async move {
+            let body = Self::build_body(req, response_model, mode, reasks)?;
+            let url = format!("{}/chat", self.api_endpoint);
+            let authorization = format!("Bearer {}", self.api_key);
+            let headers = [("Authorization", authorization.as_str())];
+
+            let response = http::post_json_async(&url, &headers, &body).await?;
+            Self::parse_body(&response)
+        })
+    }
+}
+
+fn reask_messages(reask: &Reask) -> Vec<Value> {
+    match &reask.response {
+        BackendResponse::ToolCalls(tool_calls) => {
+            let mut messages = vec![json!({
+                "role": "assistant",
+                "tool_calls": tool_calls
+                    .iter()
+                    .map(|tool_call| json!({
+                        "id": tool_call.id,
+                        "type": "function",
+                        "function": {"name": tool_call.name, "arguments": tool_call.arguments},
+                    }))
+                    .collect::<Vec<Value>>(),
+            })];
+            messages.extend(tool_calls.iter().map(|tool_call| {
+                json!({
+                    "role": "tool",
+                    "tool_call_id": tool_call.id,
+                    "content": reask.error,
+                })
+            }));
+            messages
+        }
+        BackendResponse::Json(text) | BackendResponse::Text(text) => vec![
+            json!({"role": "assistant", "content": text}),
+            json!({"role": "user", "content": reask.error}),
+        ],
+    }
+}
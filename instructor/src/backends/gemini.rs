@@ -0,0 +1,236 @@
+use instruct_macros_types::StructInfo;
+use openai_api_rs::v1::chat_completion::{
+    ChatCompletionRequest, Content, ContentType, MessageRole,
+};
+use serde_json::{json, Map, Value};
+
+#[cfg(feature = "async")]
+use super::ChatFuture;
+use super::{http, schema_prompt, Backend, BackendResponse, Reask, ToolCall};
+use crate::{helpers, InstructorError, Mode};
+
+const API_URL_V1BETA: &str = "https://generativelanguage.googleapis.com/v1beta";
+
+/// Backend for Gemini's `generateContent` API.
+///
+/// The response model is translated into a function declaration and the model is forced
+/// to call it. Failed attempts are replayed as the model's `functionCall` followed by a
+/// `functionResponse` carrying the error. JSON mode sets `responseMimeType` and JSON
+/// Schema mode also passes the translated schema as `responseSchema`.
+pub struct GeminiBackend {
+    pub api_endpoint: String,
+    pub api_key: String,
+}
+
+impl GeminiBackend {
+    pub fn new(api_key: String) -> Self {
+        let endpoint =
+            std::env::var("GEMINI_API_BASE").unwrap_or_else(|_| API_URL_V1BETA.to_owned());
+        Self::new_with_endpoint(endpoint, api_key)
+    }
+
+    pub fn new_with_endpoint(api_endpoint: String, api_key: String) -> Self {
+        Self {
+            api_endpoint,
+            api_key,
+        }
+    }
+
+    fn build_body(
+        req: &ChatCompletionRequest,
+        response_model: &StructInfo,
+        mode: Mode,
+        reasks: &[Reask],
+    ) -> Result<Value, InstructorError> {
+        let mut system = Vec::new();
+        let mut contents = Vec::new();
+
+        for message in req.messages.iter() {
+            let text = text_content(&message.content);
+            match message.role {
+                MessageRole::system => system.push(json!({"text": text})),
+                MessageRole::user | MessageRole::function => {
+                    contents.push(json!({"role": "user", "parts": [{"text": text}]}))
+                }
+                MessageRole::assistant => {
+                    contents.push(json!({"role": "model", "parts": [{"text": text}]}))
+                }
+            }
+        }
+
+        if let Some(prompt) = schema_prompt(response_model, mode)? {
+            system.push(json!({"text": prompt}));
+        }
+        for reask in reasks.iter() {
+            contents.extend(reask_contents(reask));
+        }
+
+        let mut body = json!({ "contents": contents });
+        let mut generation_config = Map::new();
+
+        match mode {
+            Mode::Tools => {
+                body["tools"] = json!([{
+                    "functionDeclarations": [{
+                        "name": response_model.name,
+                        "description": response_model.description,
+                        "parameters": helpers::get_gemini_response_model(response_model)?,
+                    }]
+                }]);
+                body["toolConfig"] = json!({
+                    "functionCallingConfig": {
+                        "mode": "ANY",
+                        "allowedFunctionNames": [response_model.name],
+                    }
+                });
+            }
+            Mode::Json => {
+                generation_config.insert("responseMimeType".to_string(), json!("application/json"));
+            }
+            Mode::JsonSchema => {
+                generation_config.insert("responseMimeType".to_string(), json!("application/json"));
+                generation_config.insert(
+                    "responseSchema".to_string(),
+                    helpers::get_gemini_response_model(response_model)?,
+                );
+            }
+            Mode::MarkdownJson => {}
+        }
+
+        if !system.is_empty() {
+            body["systemInstruction"] = json!({ "parts": system });
+        }
+
+        if let Some(temperature) = req.temperature {
+            generation_config.insert("temperature".to_string(), json!(temperature));
+        }
+        if let Some(top_p) = req.top_p {
+            generation_config.insert("topP".to_string(), json!(top_p));
+        }
+        if let Some(max_tokens) = req.max_tokens {
+            generation_config.insert("maxOutputTokens".to_string(), json!(max_tokens));
+        }
+        if let Some(stop) = &req.stop {
+            generation_config.insert("stopSequences".to_string(), json!(stop));
+        }
+        if !generation_config.is_empty() {
+            body["generationConfig"] = Value::Object(generation_config);
+        }
+
+        Ok(body)
+    }
+
+    fn parse_body(body: &Value) -> Result<BackendResponse, InstructorError> {
+        let parts = body["candidates"][0]["content"]["parts"]
+            .as_array()
+            .ok_or_else(|| {
+                InstructorError::Transport(format!("Unexpected response from Gemini: {}", body))
+            })?;
+
+        let tool_calls: Vec<ToolCall> = parts
+            .iter()
+            .filter_map(|part| part.get("functionCall"))
+            .map(|function_call| ToolCall {
+                id: function_call["id"].as_str().unwrap_or_default().to_string(),
+                name: function_call["name"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string(),
+                arguments: function_call["args"].to_string(),
+            })
+            .collect();
+
+        if !tool_calls.is_empty() {
+            return Ok(BackendResponse::ToolCalls(tool_calls));
+        }
+
+        let text = parts
+            .iter()
+            .filter_map(|part| part["text"].as_str())
+            .collect::<Vec<&str>>()
+            .join("");
+        Ok(BackendResponse::Text(text))
+    }
+}
+
+impl Backend for GeminiBackend {
+    fn chat(
+        &self,
+        req: &ChatCompletionRequest,
+        response_model: &StructInfo,
+        mode: Mode,
+        reasks: &[Reask],
+    ) -> Result<BackendResponse, InstructorError> {
+        let body = Self::build_body(req, response_model, mode, reasks)?;
+        let url = format!("{}/models/{}:generateContent", self.api_endpoint, req.model);
+        let headers = [("x-goog-api-key", self.api_key.as_str())];
+
+        let response = http::post_json(&url, &headers, &body)?;
+        Self::parse_body(&response)
+    }
+
+    #[cfg(feature = "async")]
+    fn chat_async<'a>(
+        &'a self,
+        req: &'a ChatCompletionRequest,
+        response_model: &'a StructInfo,
+        mode: Mode,
+        reasks: &'a [Reask],
+    ) -> ChatFuture<'a> {
+        Box::pin(async move {
+            let body = Self::build_body(req, response_model, mode, reasks)?;
+            let url = format!("{}/models/{}:generateContent", self.api_endpoint, req.model);
+            let headers = [("x-goog-api-key", self.api_key.as_str())];
+
+            let response = http::post_json_async(&url, &headers, &body).await?;
+            Self::parse_body(&response)
+        })
+    }
+}
+
+fn text_content(content: &Content) -> String {
+    match content {
+        Content::Text(text) => text.clone(),
+        Content::ImageUrl(parts) => parts
+            .iter()
+            .filter(|part| part.r#type == ContentType::text)
+            .filter_map(|part| part.text.clone())
+            .collect::<Vec<String>>()
+            .join("\n"),
+    }
+}
+
+fn reask_contents(reask: &Reask) -> Vec<Value> {
+    match &reask.response {
+        BackendResponse::ToolCalls(tool_calls) => {
+            let function_calls: Vec<Value> = tool_calls
+                .iter()
+                .map(|tool_call| {
+                    let args: Value =
+                        serde_json::from_str(&tool_call.arguments).unwrap_or_else(|_| json!({}));
+                    json!({"functionCall": {"name": tool_call.name, "args": args}})
+                })
+                .collect();
+            let function_responses: Vec<Value> = tool_calls
+                .iter()
+                .map(|tool_call| {
+                    json!({
+                        "functionResponse": {
+                            "name": tool_call.name,
+                            "response": {"error": reask.error},
+                        }
+                    })
+                })
+                .collect();
+
+            vec![
+                json!({"role": "model", "parts": function_calls}),
+                json!({"role": "user", "parts": function_responses}),
+            ]
+        }
+        BackendResponse::Json(text) | BackendResponse::Text(text) => vec![
+            json!({"role": "model", "parts": [{"text": text}]}),
+            json!({"role": "user", "parts": [{"text": reask.error}]}),
+        ],
+    }
+}
@@ -0,0 +1,317 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde_json::Value;
+
+use crate::InstructorError;
+
+/// Posts a JSON body and returns the decoded JSON response.
+pub(crate) fn post_json(
+    url: &str,
+    headers: &[(&str, &str)],
+    body: &Value,
+) -> Result<Value, InstructorError> {
+    let mut request = minreq::post(url).with_header("Content-Type", "application/json");
+    for (key, value) in headers {
+        request = request.with_header(*key, *value);
+    }
+    send_json(request, body)
+}
+
+/// Sends a prepared request with a JSON body and returns the decoded JSON response.
+///
+/// Network failures are reported as [`InstructorError::Transport`] and non 2xx
+/// responses as [`InstructorError::Http`], along with any `Retry-After` delay.
+pub(crate) fn send_json(request: minreq::Request, body: &Value) -> Result<Value, InstructorError> {
+    let response = request
+        .with_json(body)
+        .and_then(|request| request.send())
+        .map_err(|e| InstructorError::Transport(e.to_string()))?;
+
+    if !(200..=299).contains(&response.status_code) {
+        return Err(InstructorError::Http {
+            status: response.status_code as u16,
+            body: response.as_str().unwrap_or_default().to_string(),
+            retry_after: response
+                .headers
+                .get("retry-after")
+                .and_then(|value| parse_retry_after(value)),
+        });
+    }
+
+    response
+        .json::<Value>()
+        .map_err(|e| InstructorError::Transport(e.to_string()))
+}
+
+/// The client async requests are sent with, shared so that connections are reused.
+/// Requests through a proxy get a client of their own.
+#[cfg(feature = "async")]
+pub(crate) fn async_client(proxy: Option<&str>) -> Result<reqwest::Client, InstructorError> {
+    static CLIENT: std::sync::OnceLock<reqwest::Client> = std::sync::OnceLock::new();
+
+    match proxy {
+        Some(proxy) => reqwest::Proxy::all(proxy)
+            .and_then(|proxy| reqwest::Client::builder().proxy(proxy).build())
+            .map_err(|e| InstructorError::Transport(e.to_string())),
+        None => Ok(CLIENT.get_or_init(reqwest::Client::new).clone()),
+    }
+}
+
+/// Async counterpart of [`post_json`].
+#[cfg(feature = "async")]
+pub(crate) async fn post_json_async(
+    url: &str,
+    headers: &[(&str, &str)],
+    body: &Value,
+) -> Result<Value, InstructorError> {
+    let mut request = async_client(None)?.post(url);
+    for (key, value) in headers {
+        request = request.header(*key, *value);
+    }
+    send_json_async(request, body).await
+}
+
+/// Async counterpart of [`send_json`], with the same errors.
+#[cfg(feature = "async")]
+pub(crate) async fn send_json_async(
+    request: reqwest::RequestBuilder,
+    body: &Value,
+) -> Result<Value, InstructorError> {
+    let response = request
+        .json(body)
+        .send()
+        .await
+        .map_err(|e| InstructorError::Transport(e.to_string()))?;
+
+    if !response.status().is_success() {
+        let status = response.status().as_u16();
+        let retry_after = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_retry_after);
+        return Err(InstructorError::Http {
+            status,
+            body: response.text().await.unwrap_or_default(),
+            retry_after,
+        });
+    }
+
+    response
+        .json::<Value>()
+        .await
+        .map_err(|e| InstructorError::Transport(e.to_string()))
+}
+
+/// Sends a prepared request with a JSON body and returns the server-sent events of
+/// the response, each decoded from JSON. The stream ends at the `[DONE]` event or
+/// when the connection closes.
+///
+/// Errors before the stream starts are reported like [`send_json`].
+pub(crate) fn send_sse(
+    request: minreq::Request,
+    body: &Value,
+) -> Result<SseEvents, InstructorError> {
+    let response = request
+        .with_header("Accept", "text/event-stream")
+        .with_json(body)
+        .and_then(|request| request.send_lazy())
+        .map_err(|e| InstructorError::Transport(e.to_string()))?;
+
+    if !(200..=299).contains(&response.status_code) {
+        let status = response.status_code as u16;
+        let retry_after = response
+            .headers
+            .get("retry-after")
+            .and_then(|value| parse_retry_after(value));
+        let body: Vec<u8> = response
+            .map(|byte| byte.map(|(byte, _)| byte))
+            .collect::<Result<_, _>>()
+            .map_err(|e| InstructorError::Transport(e.to_string()))?;
+        return Err(InstructorError::Http {
+            status,
+            body: String::from_utf8_lossy(&body).into_owned(),
+            retry_after,
+        });
+    }
+
+    Ok(SseEvents {
+        response,
+        done: false,
+    })
+}
+
+/// The `data` events of a server-sent event stream.
+pub(crate) struct SseEvents {
+    response: minreq::ResponseLazy,
+    done: bool,
+}
+
+impl SseEvents {
+    /// Reads the next line, without its line ending. `None` once the connection closes.
+    fn next_line(&mut self) -> Option<Result<String, InstructorError>> {
+        let mut line = Vec::new();
+        loop {
+            match self.response.next() {
+                Some(Ok((b'\n', _))) => break,
+                Some(Ok((byte, _))) => line.push(byte),
+                Some(Err(e)) => return Some(Err(InstructorError::Transport(e.to_string()))),
+                None if line.is_empty() => return None,
+                None => break,
+            }
+        }
+        if line.last() == Some(&b'\r') {
+            line.pop();
+        }
+        Some(Ok(String::from_utf8_lossy(&line).into_owned()))
+    }
+}
+
+impl Iterator for SseEvents {
+    type Item = Result<Value, InstructorError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            let line = match self.next_line() {
+                Some(Ok(line)) => line,
+                Some(Err(error)) => {
+                    self.done = true;
+                    return Some(Err(error));
+                }
+                None => break,
+            };
+
+            let Some(data) = line.strip_prefix("data:") else {
+                continue;
+            };
+            let data = data.trim();
+            if data == "[DONE]" {
+                break;
+            }
+            return Some(serde_json::from_str(data).map_err(|e| {
+                InstructorError::Transport(format!("Invalid stream event {}: {}", data, e))
+            }));
+        }
+        self.done = true;
+        None
+    }
+}
+
+/// Reads a `Retry-After` header, either a delay in whole seconds or an HTTP date in the
+/// IMF-fixdate form (`Sun, 06 Nov 1994 08:49:37 GMT`). A date in the past is no delay,
+/// and a delay too long to represent saturates; [`RetryPolicy`](crate::RetryPolicy)
+/// caps it before waiting.
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    retry_after_at(value, SystemTime::now())
+}
+
+fn retry_after_at(value: &str, now: SystemTime) -> Option<Duration> {
+    let value = value.trim();
+    if !value.is_empty() && value.bytes().all(|byte| byte.is_ascii_digit()) {
+        return Some(Duration::from_secs(value.parse().unwrap_or(u64::MAX)));
+    }
+    let date = UNIX_EPOCH.checked_add(Duration::from_secs(parse_http_date(value)?));
+    Some(date.map_or(Duration::MAX, |date| {
+        date.duration_since(now).unwrap_or(Duration::ZERO)
+    }))
+}
+
+/// The seconds since the Unix epoch of an IMF-fixdate, the HTTP date format servers
+/// must send. The obsolete RFC 850 and asctime forms are not supported.
+fn parse_http_date(value: &str) -> Option<u64> {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+
+    let parts: Vec<&str> = value.split_whitespace().collect();
+    let [weekday, day, month, year, time, "GMT"] = parts.as_slice() else {
+        return None;
+    };
+    if weekday.len() != 4 || !weekday.ends_with(',') {
+        return None;
+    }
+    let day: u64 = day.parse().ok().filter(|day| (1..=31).contains(day))?;
+    let month = MONTHS.iter().position(|name| name == month)? as u64 + 1;
+    let year: u64 = year.parse().ok().filter(|year| *year >= 1970)?;
+    let time: Vec<u64> = time
+        .split(':')
+        .map(|part| part.parse().ok())
+        .collect::<Option<_>>()?;
+    let [hours, minutes, seconds] = time.as_slice() else {
+        return None;
+    };
+    if *hours > 23 || *minutes > 59 || *seconds > 60 {
+        return None;
+    }
+
+    // Days since the epoch of a date in the proleptic Gregorian calendar, counting
+    // years from March so that the leap day falls at the end of the year
+    let (year, month) = if month <= 2 {
+        (year - 1, month + 9)
+    } else {
+        (year, month - 3)
+    };
+    let (era, year_of_era) = (year / 400, year % 400);
+    let day_of_year = (153 * month + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era
+        .checked_mul(146_097)?
+        .checked_add(day_of_era)?
+        .checked_sub(719_468)?;
+
+    days.checked_mul(86_400)?
+        .checked_add(hours * 3_600 + minutes * 60 + seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_retry_after() {
+        let now = UNIX_EPOCH + Duration::from_secs(784_111_770);
+        assert_eq!(retry_after_at("2", now), Some(Duration::from_secs(2)));
+        assert_eq!(retry_after_at(" 0 ", now), Some(Duration::ZERO));
+        assert_eq!(
+            retry_after_at("99999999999999999999999", now),
+            Some(Duration::from_secs(u64::MAX))
+        );
+        for invalid in ["-1", "0.5", "1e30", "inf", "+5", ""] {
+            assert_eq!(retry_after_at(invalid, now), None, "{}", invalid);
+        }
+
+        assert_eq!(parse_http_date("Thu, 01 Jan 1970 00:00:00 GMT"), Some(0));
+        assert_eq!(
+            parse_http_date("Tue, 29 Feb 2000 12:00:00 GMT"),
+            Some(951_825_600)
+        );
+        assert_eq!(
+            retry_after_at("Sun, 06 Nov 1994 08:49:37 GMT", now),
+            Some(Duration::from_secs(7))
+        );
+        assert_eq!(
+            retry_after_at("Sun, 06 Nov 1994 08:49:00 GMT", now),
+            Some(Duration::ZERO)
+        );
+        // Dates past what `SystemTime` holds saturate, dates past what a u64 holds are
+        // rejected
+        assert_eq!(
+            retry_after_at("Fri, 01 Jan 400000000000 00:00:00 GMT", now),
+            Some(Duration::MAX)
+        );
+        assert_eq!(
+            parse_http_date("Fri, 01 Jan 18446744073709551615 00:00:00 GMT"),
+            None
+        );
+        for invalid in [
+            "Sunday, 06-Nov-94 08:49:37 GMT",
+            "Sun Nov  6 08:49:37 1994",
+            "Sun, 06 Nov 1994 08:49:37 UTC",
+            "Sun, 32 Nov 1994 08:49:37 GMT",
+            "Sun, 06 Nov 1994 24:00:00 GMT",
+            "soon",
+        ] {
+            assert_eq!(retry_after_at(invalid, now), None, "{}", invalid);
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use instruct_macros_types::StructInfo;
+use openai_api_rs::v1::chat_completion::ChatCompletionRequest;
+use serde_json::{json, Value};
+
+#[cfg(feature = "async")]
+use super::ChatFuture;
+use super::{
+    http, schema_prompt, text_messages, text_reask_messages, with_system_prompt, Backend,
+    BackendResponse, Reask,
+};
+use crate::{helpers, InstructorError, Mode};
+
+const API_URL: &str = "http://localhost:8080";
+
+/// Backend for the llama.cpp server's OpenAI compatible `/v1/chat/completions` endpoint.
+///
+/// The response model's JSON schema is passed as the `json_schema` parameter, which the
+/// server turns into a grammar that constrains generation. Tools mode behaves like JSON
+/// Schema mode, and JSON mode asks for a `json_object` with the schema in the system
+/// prompt.
+pub struct LlamaCppBackend {
+    pub api_endpoint: String,
+    pub api_key: Option<String>,
+}
+
+impl LlamaCppBackend {
+    pub fn new() -> Self {
+        let endpoint = std::env::var("LLAMA_CPP_API_BASE").unwrap_or_else(|_| API_URL.to_owned());
+        Self::new_with_endpoint(endpoint)
+    }
+
+    pub fn new_with_endpoint(api_endpoint: String) -> Self {
+        Self {
+            api_endpoint,
+            api_key: None,
+        }
+    }
+
+    /// Sets the key passed to a server started with `--api-key`.
+    pub fn with_api_key(mut self, api_key: String) -> Self {
+        self.api_key = Some(api_key);
+        self
+    }
+
+    fn build_body(
+        req: &ChatCompletionRequest,
+        response_model: &StructInfo,
+        mode: Mode,
+        reasks: &[Reask],
+    ) -> Result<Value, InstructorError> {
+        let mut messages = text_messages(req);
+        if let Some(prompt) = schema_prompt(response_model, mode)? {
+            with_system_prompt(&mut messages, &prompt);
+        }
+        for reask in reasks.iter() {
+            messages.extend(text_reask_messages(reask));
+        }
+
+        let mut body = json!({
+            "model": req.model,
+            "messages": messages,
+        });
+
+        match mode {
+            Mode::Tools | Mode::JsonSchema => {
+                body["json_schema"] = json!(helpers::get_response_model(response_model.clone())?)
+            }
+            Mode::Json => body["response_format"] = json!({"type": "json_object"}),
+            Mode::MarkdownJson => {}
+        }
+
+        if let Some(temperature) = req.temperature {
+            body["temperature"] = json!(temperature);
+        }
+        if let Some(top_p) = req.top_p {
+            body["top_p"] = json!(top_p);
+        }
+        if let Some(max_tokens) = req.max_tokens {
+            body["max_tokens"] = json!(max_tokens);
+        }
+        if let Some(stop) = &req.stop {
+            body["stop"] = json!(stop);
+        }
+        if let Some(seed) = req.seed {
+            body["seed"] = json!(seed);
+        }
+
+        Ok(body)
+    }
+
+    fn parse_body(body: &Value, mode: Mode) -> Result<BackendResponse, InstructorError> {
+        match body["choices"][0]["message"]["content"].as_str() {
+            Some(content) if mode == Mode::MarkdownJson => {
+                Ok(BackendResponse::Text(content.to_string()))
+            }
+            Some(content) => Ok(BackendResponse::Json(content.to_string())),
+            None => Err(InstructorError::Transport(format!(
+                "Unexpected response from llama.cpp: {}",
+                body
+            ))),
+        }
+    }
+}
+
+impl Default for LlamaCppBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Backend for LlamaCppBackend {
+    fn chat(
+        &self,
+        req: &ChatCompletionRequest,
+        response_model: &StructInfo,
+        mode: Mode,
+        reasks: &[Reask],
+    ) -> Result<BackendResponse, InstructorError> {
+        let body = Self::build_body(req, response_model, mode, reasks)?;
+        let url = format!("{}/v1/chat/completions", self.api_endpoint);
+        let authorization = self
+            .api_key
+            .as_ref()
+            .map(|api_key| format!("Bearer {}", api_key));
+        let headers: Vec<(&str, &str)> = authorization
+            .iter()
+            .map(|value| ("Authorization", value.as_str()))
+            .collect();
+
+        let response = http::post_json(&url, &headers, &body)?;
+        Self::parse_body(&response, mode)
+    }
+
+    #[cfg(feature = "async")]
+    fn chat_async<'a>(
+        &'a self,
+        req: &'a ChatCompletionRequest,
+        response_model: &'a StructInfo,
+        mode: Mode,
+        reasks: &'a [Reask],
+    ) -> ChatFuture<'a> {
+        Box::pin(async move {
+            let body = Self::build_body(req, response_model, mode, reasks)?;
+            let url = format!("{}/v1/chat/completions", self.api_endpoint);
+            let authorization = self
+                .api_key
+                .as_ref()
+                .map(|api_key| format!("Bearer {}", api_key));
+            let headers: Vec<(&str, &str)> = authorization
+                .iter()
+                .map(|value| ("Authorization", value.as_str()))
+                .collect();
+
+            let response = http::post_json_async(&url, &headers, &body).await?;
+            Self::parse_body(&response, mode)
+        })
+    }
+}
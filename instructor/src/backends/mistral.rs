@@ -0,0 +1,225 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use instruct_macros_types::StructInfo;
+use openai_api_rs::v1::chat_completion::ChatCompletionRequest;
+use serde_json::{json, Value};
+
+#[cfg(feature = "async")]
+use super::ChatFuture;
+use super::{
+    first_tool, function_tool, http, json_schema, parse_chat_completion, schema_prompt,
+    strict_json_schema, text_messages, with_system_prompt, Backend, BackendResponse, Reask,
+};
+use crate::{InstructorError, Mode};
+
+const API_URL_V1: &str = "https://api.mistral.ai/v1";
+const TOOL_CALL_ID_LENGTH: usize = 9;
+
+/// Backend for Mistral's chat completions API.
+///
+/// Mistral only forces a tool call with `tool_choice: "any"` and rejects tool call ids
+/// that aren't exactly 9 alphanumeric characters, so ids are normalised before failed
+/// attempts are replayed. Parallel tool calls are supported.
+pub struct MistralBackend {
+    pub api_endpoint: String,
+    pub api_key: String,
+}
+
+impl MistralBackend {
+    pub fn new(api_key: String) -> Self {
+        let endpoint = std::env::var("MISTRAL_API_BASE").unwrap_or_else(|_| API_URL_V1.to_owned());
+        Self::new_with_endpoint(endpoint, api_key)
+    }
+
+    pub fn new_with_endpoint(api_endpoint: String, api_key: String) -> Self {
+        Self {
+            api_endpoint,
+            api_key,
+        }
+    }
+
+    fn build_body(
+        req: &ChatCompletionRequest,
+        response_model: &StructInfo,
+        mode: Mode,
+        reasks: &[Reask],
+    ) -> Result<Value, InstructorError> {
+        let mut messages = text_messages(req);
+        if let Some(prompt) = schema_prompt(response_model, mode)? {
+            with_system_prompt(&mut messages, &prompt);
+        }
+        for reask in reasks.iter() {
+            messages.extend(reask_messages(reask));
+        }
+
+        let mut body = json!({
+            "model": req.model,
+            "messages": messages,
+        });
+
+        match mode {
+            Mode::Tools => {
+                body["tools"] = json!([function_tool(response_model)?]);
+                body["tool_choice"] = json!("any");
+            }
+            Mode::Json => body["response_format"] = json!({"type": "json_object"}),
+            Mode::JsonSchema => {
+                body["response_format"] = json!({
+                    "type": "json_schema",
+                    "json_schema": {
+                        "name": response_model.name,
+                        "schema": strict_json_schema(json_schema(response_model)?),
+                        "strict": true,
+                    },
+                })
+            }
+            Mode::MarkdownJson => {}
+        }
+
+        if let Some(temperature) = req.temperature {
+            body["temperature"] = json!(temperature);
+        }
+        if let Some(top_p) = req.top_p {
+            body["top_p"] = json!(top_p);
+        }
+        if let Some(max_tokens) = req.max_tokens {
+            body["max_tokens"] = json!(max_tokens);
+        }
+        if let Some(stop) = &req.stop {
+            body["stop"] = json!(stop);
+        }
+        if let Some(seed) = req.seed {
+            body["random_seed"] = json!(seed);
+        }
+
+        Ok(body)
+    }
+}
+
+impl Backend for MistralBackend {
+    fn chat(
+        &self,
+        req: &ChatCompletionRequest,
+        response_model: &StructInfo,
+        mode: Mode,
+        reasks: &[Reask],
+    ) -> Result<BackendResponse, InstructorError> {
+        let body = Self::build_body(req, response_model, mode, reasks)?;
+        let url = format!("{}/chat/completions", self.api_endpoint);
+        let authorization = format!("Bearer {}", self.api_key);
+        let headers = [("Authorization", authorization.as_str())];
+
+        let response = http::post_json(&url, &headers, &body)?;
+        parse_chat_completion(&response, "Mistral")
+    }
+
+    #[cfg(feature = "async")]
+    fn chat_async<'a>(
+        &'a self,
+        req: &'a ChatCompletionRequest,
+        response_model: &'a StructInfo,
+        mode: Mode,
+        reasks: &'a [Reask],
+    ) -> ChatFuture<'a> {
+        Box::pin(async move {
+            let body = Self::build_body(req, response_model, mode, reasks)?;
+            let url = format!("{}/chat/completions", self.api_endpoint);
+            let authorization = format!("Bearer {}", self.api_key);
+            let headers = [("Authorization", authorization.as_str())];
+
+            let response = http::post_json_async(&url, &headers, &body).await?;
+            parse_chat_completion(&response, "Mistral")
+        })
+    }
+
+    fn chat_parallel(
+        &self,
+        req: &ChatCompletionRequest,
+        tools: &[StructInfo],
+        reasks: &[Reask],
+    ) -> Result<BackendResponse, InstructorError> {
+        let mut body = Self::build_body(req, first_tool(tools)?, Mode::Tools, reasks)?;
+        body["tools"] = json!(tools
+            .iter()
+            .map(function_tool)
+            .collect::<Result<Vec<Value>, InstructorError>>()?);
+        body["parallel_tool_calls"] = json!(true);
+        let url = format!("{}/chat/completions", self.api_endpoint);
+        let authorization = format!("Bearer {}", self.api_key);
+        let headers = [("Authorization", authorization.as_str())];
+
+        let response = http::post_json(&url, &headers, &body)?;
+        parse_chat_completion(&response, "Mistral")
+    }
+}
+
+/// Returns `id` unchanged if Mistral would accept it, otherwise derives a stable 9
+/// character alphanumeric id from it.
+fn mistral_tool_call_id(id: &str) -> String {
+    if id.len() == TOOL_CALL_ID_LENGTH && id.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return id.to_string();
+    }
+
+    const ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+    let mut hasher = DefaultHasher::new();
+    id.hash(&mut hasher);
+    let mut hash = hasher.finish();
+
+    (0..TOOL_CALL_ID_LENGTH)
+        .map(|_| {
+            let c = ALPHABET[(hash % ALPHABET.len() as u64) as usize] as char;
+            hash /= ALPHABET.len() as u64;
+            c
+        })
+        .collect()
+}
+
+fn reask_messages(reask: &Reask) -> Vec<Value> {
+    match &reask.response {
+        BackendResponse::ToolCalls(tool_calls) => {
+            let mut messages = vec![json!({
+                "role": "assistant",
+                "content": "",
+                "tool_calls": tool_calls
+                    .iter()
+                    .map(|tool_call| json!({
+                        "id": mistral_tool_call_id(&tool_call.id),
+                        "type": "function",
+                        "function": {"name": tool_call.name, "arguments": tool_call.arguments},
+                    }))
+                    .collect::<Vec<Value>>(),
+            })];
+            messages.extend(tool_calls.iter().map(|tool_call| {
+                json!({
+                    "role": "tool",
+                    "name": tool_call.name,
+                    "tool_call_id": mistral_tool_call_id(&tool_call.id),
+                    "content": reask.error,
+                })
+            }));
+            messages
+        }
+        BackendResponse::Json(text) | BackendResponse::Text(text) => vec![
+            json!({"role": "assistant", "content": text}),
+            json!({"role": "user", "content": reask.error}),
+        ],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mistral_tool_call_id() {
+        assert_eq!(mistral_tool_call_id("D681PevKs"), "D681PevKs");
+
+        for id in ["call_abc123", "", "toolu_01A09q90qw90lq917835lq9"] {
+            let normalised = mistral_tool_call_id(id);
+            assert_eq!(normalised.len(), 9);
+            assert!(normalised.chars().all(|c| c.is_ascii_alphanumeric()));
+            assert_eq!(normalised, mistral_tool_call_id(id));
+        }
+    }
+}
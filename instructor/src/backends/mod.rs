@@ -0,0 +1,520 @@
+pub mod anthropic;
+pub mod cohere;
+pub mod gemini;
+mod http;
+pub mod llama_cpp;
+pub mod mistral;
+pub mod ollama;
+pub mod openai;
+
+pub use anthropic::AnthropicBackend;
+pub use cohere::CohereBackend;
+pub use gemini::GeminiBackend;
+pub use llama_cpp::LlamaCppBackend;
+pub use mistral::MistralBackend;
+pub use ollama::OllamaBackend;
+pub use openai::OpenAIBackend;
+
+use instruct_macros_types::StructInfo;
+use openai_api_rs::v1::chat_completion::{ChatCompletionRequest, Content, ContentType};
+use serde_json::{json, Value};
+
+use crate::{InstructorError, Mode};
+
+/// A single tool call returned by the model.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolCall {
+    pub id: String,
+    pub name: String,
+    pub arguments: String,
+}
+
+/// What a provider sent back for a chat request.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BackendResponse {
+    /// The model called one or more tools. Each call carries its raw JSON arguments.
+    ToolCalls(Vec<ToolCall>),
+    /// The model answered with a JSON document in its message content. Backends that
+    /// constrain generation with a JSON schema instead of tools return this.
+    Json(String),
+    /// The model answered with plain text instead of calling a tool.
+    Text(String),
+}
+
+/// A previous attempt that was rejected, along with the reason it was rejected.
+///
+/// Backends replay these after the original conversation so the model can see what
+/// it produced and fix it, using whatever message shape their provider expects.
+/// `error` is the client's re-ask template with the rejection reason filled in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reask {
+    pub response: BackendResponse,
+    pub error: String,
+}
+
+/// The pieces of the response model's JSON as the provider streams them in.
+pub type ChunkStream = Box<dyn Iterator<Item = Result<String, InstructorError>> + Send>;
+
+/// A single round trip of [`Backend::chat_async`].
+#[cfg(feature = "async")]
+pub type ChatFuture<'a> = std::pin::Pin<
+    Box<dyn std::future::Future<Output = Result<BackendResponse, InstructorError>> + Send + 'a>,
+>;
+
+/// A chat provider that `InstructorClient` can drive.
+///
+/// Implementations receive the conversation as a `ChatCompletionRequest` together with
+/// the response model the caller asked for and any failed attempts so far. They are
+/// responsible for turning the response model into whatever tool schema the provider
+/// expects, sending the request and handing back either the tool arguments or the
+/// text the model produced. Validation and retries are handled by `InstructorClient`,
+/// so a backend only needs to make a single round trip.
+///
+/// `mode` says how the response model should be requested. Backends that can't honour
+/// a mode return [`InstructorError::UnsupportedMode`].
+///
+/// Backends that can stream implement `chat_stream` as well, yielding the tool call
+/// arguments or the message content as they arrive. Backends that can offer several
+/// tools at once implement `chat_parallel`, where the model may call any number of
+/// `tools`.
+///
+/// With the `async` feature, `chat_async` is the same round trip without blocking the
+/// calling thread. Backends that don't implement it are driven through `chat` on
+/// tokio's blocking thread pool instead.
+pub trait Backend: Send + Sync {
+    fn chat(
+        &self,
+        req: &ChatCompletionRequest,
+        response_model: &StructInfo,
+        mode: Mode,
+        reasks: &[Reask],
+    ) -> Result<BackendResponse, InstructorError>;
+
+    fn chat_stream(
+        &self,
+        _req: &ChatCompletionRequest,
+        _response_model: &StructInfo,
+        _mode: Mode,
+    ) -> Result<ChunkStream, InstructorError> {
+        Err(InstructorError::StreamingUnsupported)
+    }
+
+    fn chat_parallel(
+        &self,
+        _req: &ChatCompletionRequest,
+        _tools: &[StructInfo],
+        _reasks: &[Reask],
+    ) -> Result<BackendResponse, InstructorError> {
+        Err(InstructorError::ParallelToolsUnsupported)
+    }
+
+    #[cfg(feature = "async")]
+    fn chat_async<'a>(
+        &'a self,
+        _req: &'a ChatCompletionRequest,
+        _response_model: &'a StructInfo,
+        _mode: Mode,
+        _reasks: &'a [Reask],
+    ) -> ChatFuture<'a> {
+        Box::pin(async { Err(InstructorError::AsyncUnsupported) })
+    }
+}
+
+/// Converts the conversation into plain `{"role", "content"}` messages, keeping only the
+/// text of multi-part messages.
+pub(crate) fn text_messages(req: &ChatCompletionRequest) -> Vec<Value> {
+    req.messages
+        .iter()
+        .map(|message| {
+            let content = match &message.content {
+                Content::Text(text) => text.clone(),
+                Content::ImageUrl(parts) => parts
+                    .iter()
+                    .filter(|part| part.r#type == ContentType::text)
+                    .filter_map(|part| part.text.clone())
+                    .collect::<Vec<String>>()
+                    .join("\n"),
+            };
+            json!({"role": message.role, "content": content})
+        })
+        .collect()
+}
+
+/// Replays a failed attempt as the assistant's answer followed by a user message with
+/// the error. This is the shape local servers such as Ollama and llama.cpp understand.
+pub(crate) fn text_reask_messages(reask: &Reask) -> Vec<Value> {
+    let answer = match &reask.response {
+        BackendResponse::ToolCalls(tool_calls) => tool_calls
+            .iter()
+            .map(|tool_call| tool_call.arguments.clone())
+            .collect::<Vec<String>>()
+            .join("\n"),
+        BackendResponse::Json(content) | BackendResponse::Text(content) => content.clone(),
+    };
+
+    vec![
+        json!({"role": "assistant", "content": answer}),
+        json!({"role": "user", "content": reask.error}),
+    ]
+}
+
+/// The first of `tools`, which backends use to build the conversation before swapping
+/// in every tool.
+pub(crate) fn first_tool(tools: &[StructInfo]) -> Result<&StructInfo, InstructorError> {
+    tools
+        .first()
+        .ok_or_else(|| InstructorError::ResponseModel("Expected at least one tool".to_string()))
+}
+
+/// The response model as an OpenAI style `function` tool, which Mistral and Cohere
+/// accept as well.
+pub(crate) fn function_tool(response_model: &StructInfo) -> Result<Value, InstructorError> {
+    Ok(json!({
+        "type": "function",
+        "function": {
+            "name": response_model.name,
+            "description": response_model.description,
+            "parameters": json_schema(response_model)?,
+        }
+    }))
+}
+
+/// Reads the first choice of an OpenAI style chat completion, which Mistral returns as
+/// well. `provider` names the service in the error for bodies of any other shape.
+pub(crate) fn parse_chat_completion(
+    body: &Value,
+    provider: &str,
+) -> Result<BackendResponse, InstructorError> {
+    let message = body["choices"][0]["message"].as_object().ok_or_else(|| {
+        InstructorError::Transport(format!("Unexpected response from {}: {}", provider, body))
+    })?;
+
+    let tool_calls: Vec<ToolCall> = message
+        .get("tool_calls")
+        .and_then(|tool_calls| tool_calls.as_array())
+        .map(|tool_calls| {
+            tool_calls
+                .iter()
+                .map(|tool_call| ToolCall {
+                    id: tool_call["id"].as_str().unwrap_or_default().to_string(),
+                    name: tool_call["function"]["name"]
+                        .as_str()
+                        .unwrap_or_default()
+                        .to_string(),
+                    arguments: match &tool_call["function"]["arguments"] {
+                        Value::String(arguments) => arguments.clone(),
+                        arguments => arguments.to_string(),
+                    },
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    if !tool_calls.is_empty() {
+        return Ok(BackendResponse::ToolCalls(tool_calls));
+    }
+
+    let content = message
+        .get("content")
+        .and_then(|content| content.as_str())
+        .unwrap_or_default();
+    Ok(BackendResponse::Text(content.to_string()))
+}
+
+/// The response model's JSON schema.
+pub(crate) fn json_schema(response_model: &StructInfo) -> Result<Value, InstructorError> {
+    crate::helpers::get_response_model(response_model.clone())
+}
+
+/// Instructions describing the schema for the modes that rely on the prompt rather
+/// than the API to shape the answer.
+pub(crate) fn schema_prompt(
+    response_model: &StructInfo,
+    mode: Mode,
+) -> Result<Option<String>, InstructorError> {
+    let instruction = match mode {
+        Mode::Json => "Make sure to return an instance of the JSON, not the schema itself",
+        Mode::MarkdownJson => {
+            "Return the correct JSON response within a ```json codeblock, not the JSON schema"
+        }
+        Mode::Tools | Mode::JsonSchema => return Ok(None),
+    };
+    let schema = serde_json::to_string_pretty(&json_schema(response_model)?)
+        .map_err(|e| InstructorError::ResponseModel(e.to_string()))?;
+
+    Ok(Some(format!(
+        "As a genius expert, your task is to understand the content and provide the parsed objects in json that match the following json_schema:\n\n{}\n\n{}",
+        schema, instruction
+    )))
+}
+
+/// Adds `prompt` to the leading system message, or inserts one if there is none.
+pub(crate) fn with_system_prompt(messages: &mut Vec<Value>, prompt: &str) {
+    match messages.first_mut() {
+        Some(message) if message["role"] == "system" => {
+            let content = message["content"].as_str().unwrap_or_default();
+            message["content"] = json!(format!("{}\n\n{}", content, prompt));
+        }
+        _ => messages.insert(0, json!({"role": "system", "content": prompt})),
+    }
+}
+
+/// Adapts a schema to the subset accepted by strict structured outputs: every object
+/// lists all of its properties as required and forbids any others, and optional
+/// properties accept `null` instead.
+pub(crate) fn strict_json_schema(mut schema: Value) -> Value {
+    make_strict(&mut schema);
+    schema
+}
+
+fn make_strict(schema: &mut Value) {
+    let Some(object) = schema.as_object_mut() else {
+        return;
+    };
+
+    if let Some(values) = object.remove("enum_values") {
+        object.insert("enum".to_string(), values);
+    }
+    // Strict mode only accepts `anyOf`, which means the same for the disjoint `const`
+    // variants of an enum
+    if let Some(variants) = object.remove("oneOf") {
+        object.insert("anyOf".to_string(), variants);
+    }
+    if let Some(items) = object.get_mut("items") {
+        make_strict(items);
+    }
+    if let Some(Value::Array(variants)) = object.get_mut("anyOf") {
+        variants.iter_mut().for_each(make_strict);
+    }
+    if let Some(Value::Array(elements)) = object.get_mut("prefixItems") {
+        elements.iter_mut().for_each(make_strict);
+    }
+    if let Some(Value::Object(definitions)) = object.get_mut("$defs") {
+        definitions.values_mut().for_each(make_strict);
+    }
+    if let Some(values) = object.get_mut("additionalProperties") {
+        make_strict(values);
+    }
+
+    // Properties left out of `required` are optional, and become nullable instead
+    let required: Option<Vec<Value>> = object
+        .get("required")
+        .and_then(|required| required.as_array())
+        .cloned();
+
+    if let Some(Value::Object(properties)) = object.get_mut("properties") {
+        for (name, property) in properties.iter_mut() {
+            make_strict(property);
+            let is_optional = required
+                .as_ref()
+                .is_some_and(|required| !required.contains(&json!(name)));
+            if is_optional {
+                if let Some(kind) = property.get("type").cloned() {
+                    property["type"] = json!([kind, "null"]);
+                }
+                if let Some(Value::Array(variants)) = property.get_mut("anyOf") {
+                    variants.push(json!({"type": "null"}));
+                } else if let Some(reference) =
+                    property.as_object_mut().and_then(|p| p.remove("$ref"))
+                {
+                    property["anyOf"] = json!([{"$ref": reference}, {"type": "null"}]);
+                }
+            }
+        }
+        let names: Vec<String> = properties.keys().cloned().collect();
+        object.insert("required".to_string(), json!(names));
+        object.insert("additionalProperties".to_string(), json!(false));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_system_prompt() {
+        let mut messages = vec![json!({"role": "user", "content": "Hi"})];
+        with_system_prompt(&mut messages, "Answer in JSON");
+        assert_eq!(
+            messages[0],
+            json!({"role": "system", "content": "Answer in JSON"})
+        );
+        assert_eq!(messages.len(), 2);
+
+        with_system_prompt(&mut messages, "Be brief");
+        assert_eq!(messages[0]["content"], "Answer in JSON\n\nBe brief");
+        assert_eq!(messages.len(), 2);
+    }
+
+    #[test]
+    fn test_strict_json_schema() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "name": {"type": "string"},
+                "nickname": {"type": "string"},
+                "status": {"type": "string", "enum_values": ["active", "inactive"]},
+                "address": {
+                    "type": "object",
+                    "properties": {"city": {"type": "string"}}
+                }
+            },
+            "required": ["name", "status", "address"]
+        });
+
+        let strict = strict_json_schema(schema);
+
+        assert_eq!(strict["additionalProperties"], false);
+        assert_eq!(
+            strict["required"].as_array().unwrap().len(),
+            4,
+            "every property should be required"
+        );
+        assert_eq!(strict["properties"]["name"]["type"], "string");
+        assert_eq!(
+            strict["properties"]["nickname"]["type"],
+            json!(["string", "null"])
+        );
+        assert_eq!(
+            strict["properties"]["status"]["enum"],
+            json!(["active", "inactive"])
+        );
+        assert!(strict["properties"]["status"].get("enum_values").is_none());
+        assert_eq!(
+            strict["properties"]["address"]["additionalProperties"],
+            false
+        );
+        assert_eq!(strict["properties"]["address"]["required"], json!(["city"]));
+    }
+
+    #[test]
+    fn test_strict_json_schema_with_union() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "action": {
+                    "anyOf": [{
+                        "type": "object",
+                        "properties": {"query": {"type": "string"}},
+                        "required": ["query"]
+                    }]
+                }
+            },
+            "required": []
+        });
+
+        let strict = strict_json_schema(schema);
+
+        let variants = &strict["properties"]["action"]["anyOf"];
+        assert_eq!(variants[0]["additionalProperties"], false);
+        assert_eq!(variants[1], json!({"type": "null"}));
+    }
+
+    #[test]
+    fn test_strict_json_schema_with_definitions() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "billing": {"$ref": "#/$defs/Address", "description": ""}
+            },
+            "required": [],
+            "$defs": {
+                "Address": {
+                    "type": "object",
+                    "properties": {"city": {"type": "string"}}
+                }
+            }
+        });
+
+        let strict = strict_json_schema(schema);
+
+        assert_eq!(
+            strict["properties"]["billing"],
+            json!({
+                "anyOf": [{"$ref": "#/$defs/Address"}, {"type": "null"}],
+                "description": ""
+            })
+        );
+        assert_eq!(strict["$defs"]["Address"]["additionalProperties"], false);
+        assert_eq!(strict["$defs"]["Address"]["required"], json!(["city"]));
+    }
+
+    #[test]
+    fn test_strict_json_schema_keeps_nested_optional_fields_nullable() {
+        use crate::helpers::get_response_model;
+        use instruct_macros::InstructMacro;
+        use instruct_macros_types::{
+            InstructMacro, InstructMacroResult, Parameter, ParameterInfo, StructInfo,
+        };
+        use serde::Deserialize;
+
+        #[derive(InstructMacro, Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Address {
+            city: String,
+            zip: Option<String>,
+        }
+
+        #[derive(InstructMacro, Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Person {
+            home: Address,
+        }
+
+        #[derive(InstructMacro, Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Order {
+            shipping: Address,
+            billing: Address,
+        }
+
+        let strict = |info| match info {
+            InstructMacroResult::Struct(info) => {
+                strict_json_schema(get_response_model(info).unwrap())
+            }
+            _ => panic!("Expected StructInfo but got a different InstructMacroResult variant"),
+        };
+        let address = json!({
+            "type": "object",
+            "properties": {
+                "city": {"type": "string", "description": ""},
+                "zip": {"type": ["string", "null"], "description": ""}
+            },
+            "required": ["city", "zip"],
+            "additionalProperties": false
+        });
+
+        let mut home = strict(Person::get_info())["properties"]["home"].clone();
+        home.as_object_mut().unwrap().remove("description");
+        assert_eq!(home, address);
+        assert_eq!(strict(Order::get_info())["$defs"]["Address"], address);
+    }
+
+    #[test]
+    fn test_strict_json_schema_with_described_enum() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "status": {
+                    "type": "string",
+                    "oneOf": [{"const": "open", "description": "Still open"}, {"const": "done"}]
+                }
+            },
+            "required": []
+        });
+
+        let strict = strict_json_schema(schema);
+
+        assert_eq!(
+            strict["properties"]["status"],
+            json!({
+                "type": ["string", "null"],
+                "anyOf": [
+                    {"const": "open", "description": "Still open"},
+                    {"const": "done"},
+                    {"type": "null"}
+                ]
+            })
+        );
+    }
+}
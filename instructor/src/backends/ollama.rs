@@ -0,0 +1,199 @@
+use instruct_macros_types::StructInfo;
+use openai_api_rs::v1::chat_completion::ChatCompletionRequest;
+use serde_json::{json, Map, Value};
+
+#[cfg(feature = "async")]
+use super::ChatFuture;
+use super::{
+    http, schema_prompt, text_messages, text_reask_messages, with_system_prompt, Backend,
+    BackendResponse, Reask,
+};
+use crate::{helpers, InstructorError, Mode};
+
+const API_URL: &str = "http://localhost:11434";
+const DEFAULT_PORT: u16 = 11434;
+
+/// Backend for Ollama's `/api/chat` endpoint.
+///
+/// The response model's JSON schema is passed as the `format` parameter so the model is
+/// constrained to produce a matching JSON document. Ollama has no forced tool calls, so
+/// Tools mode behaves like JSON Schema mode. JSON mode passes `format: "json"` and
+/// describes the schema in the system prompt.
+pub struct OllamaBackend {
+    pub api_endpoint: String,
+}
+
+impl OllamaBackend {
+    pub fn new() -> Self {
+        let endpoint = match std::env::var("OLLAMA_HOST") {
+            Ok(host) => host_url(&host),
+            Err(_) => API_URL.to_owned(),
+        };
+        Self::new_with_endpoint(endpoint)
+    }
+
+    pub fn new_with_endpoint(api_endpoint: String) -> Self {
+        Self { api_endpoint }
+    }
+
+    fn build_body(
+        req: &ChatCompletionRequest,
+        response_model: &StructInfo,
+        mode: Mode,
+        reasks: &[Reask],
+    ) -> Result<Value, InstructorError> {
+        let mut messages = text_messages(req);
+        if let Some(prompt) = schema_prompt(response_model, mode)? {
+            with_system_prompt(&mut messages, &prompt);
+        }
+        for reask in reasks.iter() {
+            messages.extend(text_reask_messages(reask));
+        }
+
+        let mut options = Map::new();
+        if let Some(temperature) = req.temperature {
+            options.insert("temperature".to_string(), json!(temperature));
+        }
+        if let Some(top_p) = req.top_p {
+            options.insert("top_p".to_string(), json!(top_p));
+        }
+        if let Some(max_tokens) = req.max_tokens {
+            options.insert("num_predict".to_string(), json!(max_tokens));
+        }
+        if let Some(stop) = &req.stop {
+            options.insert("stop".to_string(), json!(stop));
+        }
+        if let Some(seed) = req.seed {
+            options.insert("seed".to_string(), json!(seed));
+        }
+
+        let mut body = json!({
+            "model": req.model,
+            "messages": messages,
+            "stream": false,
+            "options": options,
+        });
+
+        match mode {
+            Mode::Tools | Mode::JsonSchema => {
+                body["format"] = json!(helpers::get_response_model(response_model.clone())?)
+            }
+            Mode::Json => body["format"] = json!("json"),
+            Mode::MarkdownJson => {}
+        }
+
+        Ok(body)
+    }
+
+    fn parse_body(body: &Value, mode: Mode) -> Result<BackendResponse, InstructorError> {
+        match body["message"]["content"].as_str() {
+            Some(content) if mode == Mode::MarkdownJson => {
+                Ok(BackendResponse::Text(content.to_string()))
+            }
+            Some(content) => Ok(BackendResponse::Json(content.to_string())),
+            None => Err(InstructorError::Transport(format!(
+                "Unexpected response from Ollama: {}",
+                body
+            ))),
+        }
+    }
+}
+
+/// Turns an `OLLAMA_HOST` value into a URL the way the Ollama CLI reads it: without a
+/// scheme it is `http://`, and without a port it is 11434, so `0.0.0.0` and
+/// `127.0.0.1:11434` both work. A URL with a scheme is used as is.
+fn host_url(host: &str) -> String {
+    let host = host.trim().trim_end_matches('/');
+    if host.contains("://") {
+        return host.to_string();
+    }
+
+    let (host_port, path) = match host.find('/') {
+        Some(index) => host.split_at(index),
+        None => (host, ""),
+    };
+    let host_port = match host_port {
+        "" => format!("localhost:{}", DEFAULT_PORT),
+        // A bracketed IPv6 address, with or without a port
+        _ if host_port.starts_with('[') => match host_port.rsplit_once("]:") {
+            Some(_) => host_port.to_string(),
+            None => format!("{}:{}", host_port, DEFAULT_PORT),
+        },
+        // A bare IPv6 address can't have a port
+        _ if host_port.matches(':').count() > 1 => format!("[{}]:{}", host_port, DEFAULT_PORT),
+        _ if host_port.contains(':') => host_port.to_string(),
+        _ => format!("{}:{}", host_port, DEFAULT_PORT),
+    };
+    format!("http://{}{}", host_port, path)
+}
+
+impl Default for OllamaBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Backend for OllamaBackend {
+    fn chat(
+        &self,
+        req: &ChatCompletionRequest,
+        response_model: &StructInfo,
+        mode: Mode,
+        reasks: &[Reask],
+    ) -> Result<BackendResponse, InstructorError> {
+        let body = Self::build_body(req, response_model, mode, reasks)?;
+        let url = format!("{}/api/chat", self.api_endpoint);
+
+        let response = http::post_json(&url, &[], &body)?;
+        Self::parse_body(&response, mode)
+    }
+
+    #[cfg(feature = "async")]
+    fn chat_async<'a>(
+        &'a self,
+        req: &'a ChatCompletionRequest,
+        response_model: &'a StructInfo,
+        mode: Mode,
+        reasks: &'a [Reask],
+    ) -> ChatFuture<'a> {
+        Box::pin(async move {
+            let body = Self::build_body(req, response_model, mode, reasks)?;
+            let url = format!("{}/api/chat", self.api_endpoint);
+
+            let response = http::post_json_async(&url, &[], &body).await?;
+            Self::parse_body(&response, mode)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_host_url_fills_in_scheme_and_port() {
+        assert_eq!(host_url("127.0.0.1:11434"), "http://127.0.0.1:11434");
+        assert_eq!(host_url("0.0.0.0"), "http://0.0.0.0:11434");
+        assert_eq!(
+            host_url("ollama.internal:8000"),
+            "http://ollama.internal:8000"
+        );
+        assert_eq!(
+            host_url("ollama.internal/proxy/"),
+            "http://ollama.internal:11434/proxy"
+        );
+        assert_eq!(host_url("[::1]"), "http://[::1]:11434");
+        assert_eq!(host_url("[::1]:8000"), "http://[::1]:8000");
+        assert_eq!(host_url("::1"), "http://[::1]:11434");
+        assert_eq!(host_url(" "), API_URL);
+    }
+
+    #[test]
+    fn test_host_url_keeps_urls_with_a_scheme() {
+        assert_eq!(host_url("http://localhost:11434"), "http://localhost:11434");
+        assert_eq!(
+            host_url("https://ollama.example.com/"),
+            "https://ollama.example.com"
+        );
+    }
+}
@@ -0,0 +1,203 @@
+use instruct_macros_types::StructInfo;
+use openai_api_rs::v1::{api::Client, chat_completion::ChatCompletionRequest};
+use serde_json::{json, Value};
+
+#[cfg(feature = "async")]
+use super::ChatFuture;
+use super::{
+    first_tool, function_tool, http, json_schema, parse_chat_completion, schema_prompt,
+    strict_json_schema, with_system_prompt, Backend, BackendResponse, ChunkStream, Reask,
+};
+use crate::{InstructorError, Mode};
+
+/// Backend for the OpenAI chat completions API.
+///
+/// Supports every [`Mode`], streaming and parallel tool calls. In Tools mode the model
+/// is forced to call the response model's tool, and JSON Schema mode uses strict
+/// structured outputs. Failed attempts are replayed as the assistant message carrying the rejected
+/// `tool_calls`, followed by a `tool` message with the error for each call. The
+/// `openai_api_rs` request types can't express either, so the request is serialized
+/// and extended before it is sent with the client's credentials.
+pub struct OpenAIBackend {
+    client: Client,
+}
+
+impl OpenAIBackend {
+    pub fn new(client: Client) -> Self {
+        Self { client }
+    }
+
+    fn build_body(
+        req: &ChatCompletionRequest,
+        response_model: &StructInfo,
+        mode: Mode,
+        reasks: &[Reask],
+    ) -> Result<Value, InstructorError> {
+        let mut body = serde_json::to_value(req).map_err(|e| {
+            InstructorError::Transport(format!("Unable to serialize request: {}", e))
+        })?;
+
+        match mode {
+            Mode::Tools => {
+                body["tools"] = json!([function_tool(response_model)?]);
+                body["tool_choice"] = json!({
+                    "type": "function",
+                    "function": {"name": response_model.name},
+                });
+            }
+            Mode::Json => body["response_format"] = json!({"type": "json_object"}),
+            Mode::JsonSchema => {
+                body["response_format"] = json!({
+                    "type": "json_schema",
+                    "json_schema": {
+                        "name": response_model.name,
+                        "schema": strict_json_schema(json_schema(response_model)?),
+                        "strict": true,
+                    },
+                })
+            }
+            Mode::MarkdownJson => {}
+        }
+
+        if let Some(messages) = body["messages"].as_array_mut() {
+            if let Some(prompt) = schema_prompt(response_model, mode)? {
+                with_system_prompt(messages, &prompt);
+            }
+            for reask in reasks.iter() {
+                messages.extend(reask_messages(reask));
+            }
+        }
+
+        Ok(body)
+    }
+
+    /// The async counterpart of `Client::build_request`: a POST to `url` with the
+    /// client's credentials, proxy and timeout.
+    #[cfg(feature = "async")]
+    fn build_async_request(&self, url: String) -> Result<reqwest::RequestBuilder, InstructorError> {
+        let mut request = http::async_client(self.client.proxy.as_deref())?
+            .post(url)
+            .bearer_auth(&self.client.api_key);
+        if let Some(organization) = &self.client.organization {
+            request = request.header("openai-organization", organization);
+        }
+        if let Some(timeout) = self.client.timeout {
+            request = request.timeout(std::time::Duration::from_secs(timeout));
+        }
+        Ok(request)
+    }
+}
+
+impl Backend for OpenAIBackend {
+    fn chat(
+        &self,
+        req: &ChatCompletionRequest,
+        response_model: &StructInfo,
+        mode: Mode,
+        reasks: &[Reask],
+    ) -> Result<BackendResponse, InstructorError> {
+        let body = Self::build_body(req, response_model, mode, reasks)?;
+        let url = format!("{}/chat/completions", self.client.api_endpoint);
+        let request = self.client.build_request(minreq::post(url), false);
+
+        let response = http::send_json(request, &body)?;
+        parse_chat_completion(&response, "OpenAI")
+    }
+
+    #[cfg(feature = "async")]
+    fn chat_async<'a>(
+        &'a self,
+        req: &'a ChatCompletionRequest,
+        response_model: &'a StructInfo,
+        mode: Mode,
+        reasks: &'a [Reask],
+    ) -> ChatFuture<'a> {
+        Box::pin(async move {
+            let body = Self::build_body(req, response_model, mode, reasks)?;
+            let url = format!("{}/chat/completions", self.client.api_endpoint);
+            let request = self.build_async_request(url)?;
+
+            let response = http::send_json_async(request, &body).await?;
+            parse_chat_completion(&response, "OpenAI")
+        })
+    }
+
+    fn chat_parallel(
+        &self,
+        req: &ChatCompletionRequest,
+        tools: &[StructInfo],
+        reasks: &[Reask],
+    ) -> Result<BackendResponse, InstructorError> {
+        let mut body = Self::build_body(req, first_tool(tools)?, Mode::Tools, reasks)?;
+        body["tools"] = json!(tools
+            .iter()
+            .map(function_tool)
+            .collect::<Result<Vec<Value>, InstructorError>>()?);
+        body["tool_choice"] = json!("required");
+        body["parallel_tool_calls"] = json!(true);
+        let url = format!("{}/chat/completions", self.client.api_endpoint);
+        let request = self.client.build_request(minreq::post(url), false);
+
+        let response = http::send_json(request, &body)?;
+        parse_chat_completion(&response, "OpenAI")
+    }
+
+    fn chat_stream(
+        &self,
+        req: &ChatCompletionRequest,
+        response_model: &StructInfo,
+        mode: Mode,
+    ) -> Result<ChunkStream, InstructorError> {
+        let mut body = Self::build_body(req, response_model, mode, &[])?;
+        body["stream"] = json!(true);
+        let url = format!("{}/chat/completions", self.client.api_endpoint);
+        let request = self.client.build_request(minreq::post(url), false);
+
+        let events = http::send_sse(request, &body)?;
+        Ok(Box::new(events.filter_map(move |event| {
+            event.map(|event| stream_delta(&event, mode)).transpose()
+        })))
+    }
+}
+
+/// The piece of the answer carried by a streamed chunk: the tool call arguments in
+/// Tools mode, the message content otherwise.
+fn stream_delta(event: &Value, mode: Mode) -> Option<String> {
+    let delta = &event["choices"][0]["delta"];
+    let text = match mode {
+        Mode::Tools => &delta["tool_calls"][0]["function"]["arguments"],
+        _ => &delta["content"],
+    };
+    text.as_str().map(str::to_string)
+}
+
+fn reask_messages(reask: &Reask) -> Vec<Value> {
+    match &reask.response {
+        BackendResponse::ToolCalls(tool_calls) => {
+            let mut messages = vec![json!({
+                "role": "assistant",
+                "content": null,
+                "tool_calls": tool_calls
+                    .iter()
+                    .map(|tool_call| json!({
+                        "id": tool_call.id,
+                        "type": "function",
+                        "function": {"name": tool_call.name, "arguments": tool_call.arguments},
+                    }))
+                    .collect::<Vec<Value>>(),
+            })];
+            messages.extend(tool_calls.iter().map(|tool_call| {
+                json!({
+                    "role": "tool",
+                    "tool_call_id": tool_call.id,
+                    "content": reask.error,
+                })
+            }));
+            messages
+        }
+        BackendResponse::Json(text) | BackendResponse::Text(text) => vec![
+            json!({"role": "assistant", "content": text}),
+            json!({"role": "user", "content": reask.error}),
+        ],
+    }
+}
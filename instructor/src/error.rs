@@ -0,0 +1,138 @@
+use std::fmt;
+use std::time::Duration;
+
+use openai_api_rs::v1::error::APIError;
+
+use crate::backends::BackendResponse;
+use crate::Mode;
+
+/// Errors returned by [`InstructorClient`](crate::InstructorClient) and the backends.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InstructorError {
+    /// The request never produced a usable response: the network call failed or the
+    /// body was not in the shape we expect.
+    Transport(String),
+    /// The provider answered with a non 2xx status. `retry_after` holds the delay the
+    /// provider asked for in its `Retry-After` header, if any.
+    Http {
+        status: u16,
+        body: String,
+        retry_after: Option<Duration>,
+    },
+    /// The model answered but its output could not be deserialized into the response model.
+    Deserialization { message: String, content: String },
+    /// The output deserialized but was rejected by the response model's validators.
+    Validation(String),
+    /// The model answered with plain text instead of calling the tool.
+    NoToolCall(String),
+    /// The response model cannot be described to the model, either because it is not a
+    /// struct or because one of its fields has a type we have no schema for.
+    ResponseModel(String),
+    /// The backend has no way to request the response model in the chosen mode.
+    UnsupportedMode { backend: String, mode: Mode },
+    /// The backend can't stream its answer.
+    StreamingUnsupported,
+    /// The backend can't offer several tools in one request.
+    ParallelToolsUnsupported,
+    /// The backend has no async transport. The async methods of
+    /// [`InstructorClient`](crate::InstructorClient) fall back to its blocking one.
+    AsyncUnsupported,
+    /// Every attempt failed. Holds each attempt in the order it was made.
+    RetriesExhausted(Vec<Attempt>),
+}
+
+/// A single failed call to the model, kept so the whole exchange can be inspected
+/// once the retries run out. `response` is `None` when the request itself failed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attempt {
+    pub response: Option<BackendResponse>,
+    pub error: InstructorError,
+}
+
+impl fmt::Display for InstructorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InstructorError::Transport(message) => write!(f, "Request failed: {}", message),
+            InstructorError::Http { status, body, .. } => write!(f, "{}: {}", status, body),
+            InstructorError::Deserialization { message, content } => {
+                write!(f, "Invalid response: {}. Response: {}", message, content)
+            }
+            InstructorError::Validation(message) => write!(f, "{}", message),
+            InstructorError::NoToolCall(_) => write!(
+                f,
+                "The model answered without calling a tool. Please make sure to generate a response and call a tool"
+            ),
+            InstructorError::ResponseModel(message) => {
+                write!(f, "Unsupported response model: {}", message)
+            }
+            InstructorError::UnsupportedMode { backend, mode } => {
+                write!(f, "{} does not support {:?} mode", backend, mode)
+            }
+            InstructorError::StreamingUnsupported => {
+                write!(f, "This backend does not support streaming")
+            }
+            InstructorError::ParallelToolsUnsupported => {
+                write!(f, "This backend does not support parallel tool calls")
+            }
+            InstructorError::AsyncUnsupported => {
+                write!(f, "This backend does not support async requests")
+            }
+            InstructorError::RetriesExhausted(attempts) => {
+                write!(
+                    f,
+                    "Unable to derive model after {} attempts",
+                    attempts.len()
+                )?;
+                match attempts.last() {
+                    Some(attempt) => write!(f, ": {}", attempt.error),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+}
+
+impl InstructorError {
+    /// Whether sending the same request again may succeed.
+    ///
+    /// Network failures, malformed bodies, timeouts, rate limits and server errors are
+    /// worth another try. Other 4xx statuses such as a bad API key are not.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            InstructorError::Transport(_) => true,
+            InstructorError::Http { status, .. } => {
+                matches!(status, 408 | 429) || (500..=599).contains(status)
+            }
+            _ => false,
+        }
+    }
+
+    /// The delay the provider asked for before the next request.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            InstructorError::Http { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+}
+
+impl std::error::Error for InstructorError {}
+
+/// `openai_api_rs` reports non 2xx responses as `"<status>: <body>"`, without headers.
+impl From<APIError> for InstructorError {
+    fn from(error: APIError) -> Self {
+        let status = error
+            .message
+            .split_once(": ")
+            .and_then(|(status, body)| Some((status.parse::<u16>().ok()?, body)));
+
+        match status {
+            Some((status, body)) => InstructorError::Http {
+                status,
+                body: body.to_string(),
+                retry_after: None,
+            },
+            None => InstructorError::Transport(error.message),
+        }
+    }
+}
@@ -0,0 +1,490 @@
+//! Gemini's `functionDeclarations` accept an OpenAPI subset rather than JSON Schema.
+//!
+//! Types are upper case, there is no `additionalProperties` (so maps can't be
+//! described), enums are strings with `format: "enum"` and optional values are marked
+//! with `nullable: true` instead of being left out of `required` alone.
+
+use instruct_macros_types::{
+    EnumInfo, Parameter, ParameterInfo, StructInfo, UnionInfo, UnionTagging, VariantInfo,
+};
+use serde_json::{json, Map, Value};
+
+use super::response_model::{
+    constraint_keywords, convert_parameter_type, innermost_items, integer_bounds,
+};
+use crate::InstructorError;
+
+fn get_base_type(field_info: &ParameterInfo) -> &str {
+    if field_info.r#type.starts_with("Option<") && field_info.r#type.ends_with('>') {
+        &field_info.r#type[7..field_info.r#type.len() - 1]
+    } else {
+        &field_info.r#type
+    }
+}
+
+fn with_list_and_nullable(
+    schema: Map<String, Value>,
+    description: &str,
+    is_list: bool,
+    is_optional: bool,
+) -> Value {
+    let mut schema = if is_list {
+        let mut array = Map::new();
+        array.insert("type".to_string(), json!("ARRAY"));
+        array.insert("items".to_string(), Value::Object(schema));
+        array
+    } else {
+        schema
+    };
+
+    if !description.is_empty() {
+        schema.insert("description".to_string(), json!(description));
+    }
+    if is_optional {
+        schema.insert("nullable".to_string(), json!(true));
+    }
+    Value::Object(schema)
+}
+
+fn field_schema(field_info: &ParameterInfo) -> Result<Value, InstructorError> {
+    let base_type = get_base_type(field_info);
+    let mut schema = Map::new();
+    schema.insert(
+        "type".to_string(),
+        json!(convert_parameter_type(base_type)?.to_uppercase()),
+    );
+
+    let (minimum, maximum) = integer_bounds(base_type);
+    if let Some(minimum) = minimum {
+        schema.insert("minimum".to_string(), minimum);
+    }
+    if let Some(maximum) = maximum {
+        schema.insert("maximum".to_string(), maximum);
+    }
+
+    let is_string = schema["type"] == "STRING";
+    let (keywords, list_keywords) =
+        constraint_keywords(&field_info.constraints, is_string, field_info.is_list);
+    schema.extend(gemini_keywords(keywords, is_string));
+    let mut schema = with_list_and_nullable(
+        schema,
+        &field_info.comment,
+        field_info.is_list,
+        field_info.is_optional,
+    );
+    schema.as_object_mut().unwrap().extend(list_keywords);
+    Ok(schema)
+}
+
+/// Keeps the constraint keywords Gemini understands. Its string formats are only
+/// `enum` and `date-time`, and only strings can be an `enum`.
+fn gemini_keywords(keywords: Map<String, Value>, is_string: bool) -> Map<String, Value> {
+    let mut keywords: Map<String, Value> = keywords
+        .into_iter()
+        .filter(|(keyword, _)| keyword != "format" && (keyword != "enum" || is_string))
+        .collect();
+    if keywords.contains_key("enum") {
+        keywords.insert("format".to_string(), json!("enum"));
+    }
+    keywords
+}
+
+fn enum_schema(enum_info: &EnumInfo) -> Value {
+    let mut schema = Map::new();
+    schema.insert("type".to_string(), json!("STRING"));
+    schema.insert("format".to_string(), json!("enum"));
+    schema.insert("enum".to_string(), json!(enum_info.r#enum));
+    with_list_and_nullable(
+        schema,
+        &enum_info.description,
+        enum_info.is_list,
+        enum_info.is_optional,
+    )
+}
+
+fn literal_schema(name: &str) -> Value {
+    json!({"type": "STRING", "format": "enum", "enum": [name]})
+}
+
+fn tagged_object(properties: Vec<(&str, Value)>) -> Value {
+    let required: Vec<&str> = properties.iter().map(|(name, _)| *name).collect();
+    let properties: Map<String, Value> = properties
+        .into_iter()
+        .map(|(name, schema)| (name.to_string(), schema))
+        .collect();
+    json!({"type": "OBJECT", "properties": properties, "required": required})
+}
+
+/// A variant laid out the way serde reads it for the union's tagging. Gemini has no
+/// `null` type, so unit variants of untagged enums can't be described.
+fn variant_schema(union_info: &UnionInfo, variant: &VariantInfo) -> Result<Value, InstructorError> {
+    let content = variant.content.as_ref().map(parameter_schema).transpose()?;
+    let name = variant.name.as_str();
+
+    let mut schema = match (&union_info.tagging, content) {
+        (UnionTagging::External, None) => literal_schema(name),
+        (UnionTagging::External, Some(content)) => tagged_object(vec![(name, content)]),
+        (UnionTagging::Internal { tag }, None) | (UnionTagging::Adjacent { tag, .. }, None) => {
+            tagged_object(vec![(tag.as_str(), literal_schema(name))])
+        }
+        (UnionTagging::Internal { tag }, Some(mut content)) => {
+            match content.get_mut("properties").and_then(Value::as_object_mut) {
+                Some(properties) => {
+                    properties.insert(tag.clone(), literal_schema(name));
+                }
+                None => {
+                    return Err(InstructorError::ResponseModel(format!(
+                        "Variant `{}` of `{}` is internally tagged and must hold a struct",
+                        name, union_info.name
+                    )))
+                }
+            }
+            match content.get_mut("required").and_then(Value::as_array_mut) {
+                Some(required) => required.insert(0, json!(tag)),
+                None => content["required"] = json!([tag]),
+            }
+            content
+        }
+        (UnionTagging::Adjacent { tag, content: key }, Some(content)) => tagged_object(vec![
+            (tag.as_str(), literal_schema(name)),
+            (key.as_str(), content),
+        ]),
+        (UnionTagging::Untagged, None) => {
+            return Err(InstructorError::ResponseModel(format!(
+                "Unit variant `{}` of untagged `{}` is not supported by Gemini",
+                name, union_info.name
+            )))
+        }
+        (UnionTagging::Untagged, Some(content)) => content,
+    };
+
+    if !variant.description.is_empty() {
+        schema["description"] = json!(variant.description);
+    }
+    Ok(schema)
+}
+
+fn union_schema(union_info: &UnionInfo) -> Result<Value, InstructorError> {
+    let variants = union_info
+        .variants
+        .iter()
+        .map(|variant| variant_schema(union_info, variant))
+        .collect::<Result<Vec<Value>, InstructorError>>()?;
+
+    let mut schema = Map::new();
+    schema.insert("anyOf".to_string(), Value::Array(variants));
+    Ok(with_list_and_nullable(
+        schema,
+        &union_info.description,
+        union_info.is_list,
+        union_info.is_optional,
+    ))
+}
+
+fn parameter_schema(param: &Parameter) -> Result<Value, InstructorError> {
+    match param {
+        Parameter::Field(field_info) => field_schema(field_info),
+        Parameter::Enum(enum_info) => Ok(enum_schema(enum_info)),
+        Parameter::Struct(struct_info) => Ok(with_list_and_nullable(
+            object_schema(struct_info)?,
+            &struct_info.description,
+            struct_info.is_list,
+            struct_info.is_optional,
+        )),
+        Parameter::Union(union_info) => union_schema(union_info),
+        Parameter::List(list_info) => {
+            let mut schema = Map::new();
+            schema.insert("type".to_string(), json!("ARRAY"));
+            schema.insert("items".to_string(), parameter_schema(&list_info.items)?);
+            if let Some(length) = list_info.length {
+                schema.insert("minItems".to_string(), json!(length));
+                schema.insert("maxItems".to_string(), json!(length));
+            }
+            let (keywords, list_keywords) =
+                constraint_keywords(&list_info.constraints, false, true);
+            let items = innermost_items(&mut schema);
+            let is_string = items.get("type") == Some(&json!("STRING"));
+            items.extend(gemini_keywords(keywords, is_string));
+            schema.extend(list_keywords);
+            Ok(with_list_and_nullable(
+                schema,
+                &list_info.description,
+                false,
+                list_info.is_optional,
+            ))
+        }
+        Parameter::Map(map_info) => Err(InstructorError::ResponseModel(format!(
+            "Map field `{}` is not supported by Gemini",
+            map_info.name
+        ))),
+        Parameter::Tuple(tuple_info) => Err(InstructorError::ResponseModel(format!(
+            "Tuple field `{}` is not supported by Gemini",
+            tuple_info.name
+        ))),
+        Parameter::Ref(ref_info) => Err(InstructorError::ResponseModel(format!(
+            "Field `{}` refers back to `{}`, but recursive types are not supported by Gemini",
+            ref_info.name, ref_info.r#type
+        ))),
+    }
+}
+
+fn object_schema(t: &StructInfo) -> Result<Map<String, Value>, InstructorError> {
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+
+    for param in t.parameters.iter() {
+        if !param.is_optional() {
+            required.push(param.name().to_string());
+        }
+        properties.insert(param.name().to_string(), parameter_schema(param)?);
+    }
+
+    let mut schema = Map::new();
+    schema.insert("type".to_string(), json!("OBJECT"));
+    schema.insert("properties".to_string(), Value::Object(properties));
+    if !required.is_empty() {
+        schema.insert("required".to_string(), json!(required));
+    }
+    Ok(schema)
+}
+
+/// Translates a response model into the schema dialect used by Gemini function
+/// declarations.
+pub fn get_gemini_response_model(t: &StructInfo) -> Result<Value, InstructorError> {
+    Ok(Value::Object(object_schema(t)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use instruct_macros::InstructMacro;
+    use instruct_macros_types::{InstructMacro, InstructMacroResult, StructInfo};
+    use serde::{Deserialize, Serialize};
+
+    fn struct_info<T: InstructMacro>() -> StructInfo {
+        match T::get_info() {
+            InstructMacroResult::Struct(info) => info,
+            _ => panic!("Expected StructInfo but got a different InstructMacroResult variant"),
+        }
+    }
+
+    #[test]
+    fn test_simple_struct() {
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        struct SimpleStruct {
+            #[description("The name of the user")]
+            name: String,
+            #[description("The age of the user")]
+            age: u8,
+            #[description("The height of the user in meters")]
+            height: f64,
+            active: bool,
+            nothing: (),
+        }
+
+        assert_eq!(
+            get_gemini_response_model(&struct_info::<SimpleStruct>()).unwrap(),
+            json!({
+                "type": "OBJECT",
+                "properties": {
+                    "name": {"type": "STRING", "description": "The name of the user"},
+                    "age": {
+                        "type": "INTEGER",
+                        "minimum": 0,
+                        "maximum": 255,
+                        "description": "The age of the user"
+                    },
+                    "height": {"type": "NUMBER", "description": "The height of the user in meters"},
+                    "active": {"type": "BOOLEAN"},
+                    "nothing": {"type": "NULL"}
+                },
+                "required": ["name", "age", "height", "active", "nothing"]
+            })
+        );
+    }
+
+    #[test]
+    fn test_optional_fields_are_nullable() {
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        struct User {
+            name: String,
+        }
+
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        struct MaybeUser {
+            user: Option<User>,
+            nickname: Option<String>,
+        }
+
+        let schema = get_gemini_response_model(&struct_info::<MaybeUser>()).unwrap();
+
+        assert_eq!(
+            schema,
+            json!({
+                "type": "OBJECT",
+                "properties": {
+                    "user": {
+                        "type": "OBJECT",
+                        "properties": {"name": {"type": "STRING"}},
+                        "required": ["name"],
+                        "nullable": true
+                    },
+                    "nickname": {"type": "STRING", "nullable": true}
+                }
+            })
+        );
+        assert!(!schema.to_string().contains("additionalProperties"));
+
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        struct Answers {
+            scores: Vec<Option<u8>>,
+        }
+
+        assert_eq!(
+            get_gemini_response_model(&struct_info::<Answers>()).unwrap()["properties"]["scores"],
+            json!({
+                "type": "ARRAY",
+                "items": {"type": "INTEGER", "minimum": 0, "maximum": 255, "nullable": true}
+            })
+        );
+    }
+
+    #[test]
+    fn test_enums_and_lists() {
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        #[description("The job of the person")]
+        enum Job {
+            Developer,
+            Teacher,
+        }
+
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        struct Person {
+            job: Job,
+            #[description("Previous jobs")]
+            previous_jobs: Vec<Job>,
+            scores: Vec<i32>,
+        }
+
+        assert_eq!(
+            get_gemini_response_model(&struct_info::<Person>()).unwrap(),
+            json!({
+                "type": "OBJECT",
+                "properties": {
+                    "job": {
+                        "type": "STRING",
+                        "format": "enum",
+                        "enum": ["Developer", "Teacher"],
+                        "description": "The job of the person"
+                    },
+                    "previous_jobs": {
+                        "type": "ARRAY",
+                        "items": {
+                            "type": "STRING",
+                            "format": "enum",
+                            "enum": ["Developer", "Teacher"]
+                        },
+                        "description": "Previous jobs"
+                    },
+                    "scores": {
+                        "type": "ARRAY",
+                        "items": {"type": "INTEGER", "minimum": -2147483648, "maximum": 2147483647}
+                    }
+                },
+                "required": ["job", "previous_jobs", "scores"]
+            })
+        );
+    }
+
+    #[test]
+    fn test_unions_use_any_of() {
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        #[serde(tag = "type", content = "data")]
+        enum Action {
+            Wait(u32),
+            Stop,
+        }
+
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        struct Step {
+            action: Option<Action>,
+        }
+
+        assert_eq!(
+            get_gemini_response_model(&struct_info::<Step>()).unwrap(),
+            json!({
+                "type": "OBJECT",
+                "properties": {
+                    "action": {
+                        "anyOf": [
+                            {
+                                "type": "OBJECT",
+                                "properties": {
+                                    "type": {"type": "STRING", "format": "enum", "enum": ["Wait"]},
+                                    "data": {"type": "INTEGER", "minimum": 0, "maximum": 4294967295u32}
+                                },
+                                "required": ["type", "data"]
+                            },
+                            {
+                                "type": "OBJECT",
+                                "properties": {
+                                    "type": {"type": "STRING", "format": "enum", "enum": ["Stop"]}
+                                },
+                                "required": ["type"]
+                            }
+                        ],
+                        "nullable": true
+                    }
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_recursive_types_are_an_error() {
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        struct Comment {
+            text: String,
+            replies: Vec<Comment>,
+        }
+
+        let error = get_gemini_response_model(&struct_info::<Comment>()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            InstructorError::ResponseModel(
+                "Field `replies` refers back to `Comment`, but recursive types are not supported by Gemini".to_string()
+            )
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn test_validators_keep_what_gemini_supports() {
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        struct Signup {
+            #[validate(email, one_of("ann@example.com", "bob@example.com"))]
+            email: String,
+            #[validate(range(min = 13), one_of(13, 18))]
+            age: u8,
+            #[validate(length(min = 1, max = 3), length(max = 5))]
+            tags: Vec<String>,
+        }
+
+        assert_eq!(
+            get_gemini_response_model(&struct_info::<Signup>()).unwrap(),
+            json!({
+                "type": "OBJECT",
+                "properties": {
+                    "email": {
+                        "type": "STRING",
+                        "format": "enum",
+                        "enum": ["ann@example.com", "bob@example.com"]
+                    },
+                    "age": {"type": "INTEGER", "minimum": 13, "maximum": 255},
+                    "tags": {"type": "ARRAY", "items": {"type": "STRING"}, "minItems": 1, "maxItems": 3}
+                },
+                "required": ["email", "age", "tags"]
+            })
+        );
+    }
+}
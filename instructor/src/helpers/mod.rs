@@ -1,3 +1,5 @@
+pub mod gemini_schema;
 pub mod response_model;
 
+pub use gemini_schema::*;
 pub use response_model::*;
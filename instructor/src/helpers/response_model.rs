@@ -1,40 +1,56 @@
-use std::collections::HashMap;
+use instruct_macros_types::{
+    Constraint, EnumInfo, Parameter, ParameterInfo, StructInfo, UnionInfo, UnionTagging,
+    VariantInfo,
+};
+use serde_json::{json, Map, Value};
+use std::collections::{HashMap, HashSet};
 
-use instruct_macros_types::{Parameter, ParameterInfo, StructInfo};
-use openai_api_rs::v1::chat_completion::{self, JSONSchemaDefine};
+use crate::InstructorError;
 
 fn get_required_properties(info: &StructInfo) -> Vec<String> {
     let mut required = Vec::new();
 
     for param in info.parameters.iter() {
-        match param {
-            Parameter::Field(field_info) => {
-                if !field_info.is_optional {
-                    required.push(field_info.name.clone());
-                }
-            }
-            Parameter::Struct(struct_info) => {
-                if !struct_info.is_optional {
-                    required.push(struct_info.name.clone());
-                }
-            }
-            Parameter::Enum(enum_info) => {
-                if !enum_info.is_optional {
-                    required.push(enum_info.title.clone());
-                }
-            }
+        if !param.is_optional() {
+            required.push(param.name().to_string());
         }
     }
     required
 }
 
-fn convert_parameter_type(info: &str) -> chat_completion::JSONSchemaType {
+/// The JSON Schema type of a primitive Rust type, in lower case. Gemini's schema uses
+/// the same names in upper case.
+pub(crate) fn convert_parameter_type(info: &str) -> Result<&'static str, InstructorError> {
     match info {
-        "String" | "char" => chat_completion::JSONSchemaType::String,
+        "String" | "str" | "char" => Ok("string"),
         "u8" | "i8" | "u16" | "i16" | "u32" | "i32" | "u64" | "i64" | "u128" | "i128" | "usize"
-        | "isize" => chat_completion::JSONSchemaType::Number,
-        "bool" => chat_completion::JSONSchemaType::Boolean,
-        _ => panic!("Unsupported type: {}", info),
+        | "isize" => Ok("integer"),
+        "f32" | "f64" => Ok("number"),
+        "bool" => Ok("boolean"),
+        "()" => Ok("null"),
+        _ => Err(InstructorError::ResponseModel(format!(
+            "Unsupported type: {}",
+            info
+        ))),
+    }
+}
+
+/// The `minimum` and `maximum` of an integer type. The bounds of 128 bit integers don't
+/// fit in a JSON number, so `u128` only keeps its minimum and `i128` has none.
+pub(crate) fn integer_bounds(info: &str) -> (Option<Value>, Option<Value>) {
+    match info {
+        "u8" => (Some(json!(0)), Some(json!(u8::MAX))),
+        "u16" => (Some(json!(0)), Some(json!(u16::MAX))),
+        "u32" => (Some(json!(0)), Some(json!(u32::MAX))),
+        "u64" => (Some(json!(0)), Some(json!(u64::MAX))),
+        "usize" => (Some(json!(0)), Some(json!(usize::MAX))),
+        "u128" => (Some(json!(0)), None),
+        "i8" => (Some(json!(i8::MIN)), Some(json!(i8::MAX))),
+        "i16" => (Some(json!(i16::MIN)), Some(json!(i16::MAX))),
+        "i32" => (Some(json!(i32::MIN)), Some(json!(i32::MAX))),
+        "i64" => (Some(json!(i64::MIN)), Some(json!(i64::MAX))),
+        "isize" => (Some(json!(isize::MIN)), Some(json!(isize::MAX))),
+        _ => (None, None),
     }
 }
 
@@ -46,142 +62,540 @@ fn get_base_type(field_info: &ParameterInfo) -> &str {
     }
 }
 
-fn get_response_model_parameters(t: &StructInfo) -> HashMap<String, Box<JSONSchemaDefine>> {
-    let mut properties = HashMap::new();
+/// Wraps `schema` in an array for list parameters. The description goes on the
+/// outermost schema.
+fn with_list(schema: Map<String, Value>, description: &str, is_list: bool) -> Value {
+    let mut schema = if is_list {
+        let mut array = Map::new();
+        array.insert("type".to_string(), json!("array"));
+        array.insert("items".to_string(), Value::Object(schema));
+        array
+    } else {
+        schema
+    };
+    schema.insert("description".to_string(), json!(description));
+    Value::Object(schema)
+}
 
-    for param in t.parameters.iter() {
-        match param {
-            Parameter::Field(field_info) => {
-                let parameter_name = field_info.name.clone();
-                let parameter_description = field_info.comment.clone();
-
-                let base_type = get_base_type(field_info);
-                let parameter_type = if field_info.is_list {
-                    chat_completion::JSONSchemaType::Array
-                } else {
-                    convert_parameter_type(&base_type.to_string())
-                };
+/// A bound as a JSON number, written as an integer when it is one.
+fn bound(number: f64) -> Value {
+    match number.fract() == 0.0 && number.abs() < i64::MAX as f64 {
+        true => json!(number as i64),
+        false => json!(number),
+    }
+}
 
-                let items = if field_info.is_list {
-                    Some(Box::new(chat_completion::JSONSchemaDefine {
-                        schema_type: Some(convert_parameter_type(base_type)),
-                        ..Default::default()
-                    }))
-                } else {
-                    None
-                };
+/// Escapes the characters that have a meaning in a regex.
+fn escape_pattern(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        if "\\.+*?()|[]{}^$".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
 
-                properties.insert(
-                    parameter_name,
-                    Box::new(chat_completion::JSONSchemaDefine {
-                        schema_type: Some(parameter_type),
-                        description: Some(parameter_description),
-                        items: items,
-                        ..Default::default()
-                    }),
-                );
-            }
-            Parameter::Enum(enum_info) => {
-                let parameter_name = enum_info.title.clone();
-                let parameter_description = enum_info.description.clone();
-                let enum_values: Vec<String> = enum_info.r#enum.iter().map(|e| e.clone()).collect();
-
-                let parameter_type = if enum_info.is_list {
-                    chat_completion::JSONSchemaType::Array
-                } else {
-                    chat_completion::JSONSchemaType::String
-                };
+/// The keywords describing the constraints of built-in validators, split into the ones
+/// on each value and the ones on the list around them. The length of a string is
+/// `minLength`/`maxLength` and the length of a list is `minItems`/`maxItems`.
+pub(crate) fn constraint_keywords(
+    constraints: &[Constraint],
+    is_string: bool,
+    is_list: bool,
+) -> (Map<String, Value>, Map<String, Value>) {
+    let mut value = Map::new();
+    let (mut min_length, mut max_length): (Option<usize>, Option<usize>) = (None, None);
+    let has_pattern = constraints
+        .iter()
+        .any(|constraint| matches!(constraint, Constraint::Pattern(_)));
 
-                let items = if enum_info.is_list {
-                    Some(Box::new(chat_completion::JSONSchemaDefine {
-                        schema_type: Some(chat_completion::JSONSchemaType::String),
-                        enum_values: Some(enum_values.clone()),
-                        ..Default::default()
-                    }))
-                } else {
-                    None
+    for constraint in constraints {
+        match constraint {
+            Constraint::Length { min, max } => {
+                min_length = min_length.max(*min);
+                max_length = match (max_length, *max) {
+                    (Some(current), Some(max)) => Some(current.min(max)),
+                    (current, max) => current.or(max),
                 };
+            }
+            Constraint::NonEmpty => min_length = min_length.max(Some(1)),
+            Constraint::Range { min, max } => {
+                if let Some(min) = min {
+                    value.insert("minimum".to_string(), bound(*min));
+                }
+                if let Some(max) = max {
+                    value.insert("maximum".to_string(), bound(*max));
+                }
+            }
+            Constraint::Pattern(pattern) => {
+                value.insert("pattern".to_string(), json!(pattern));
+            }
+            Constraint::Email => {
+                value.insert("format".to_string(), json!("email"));
+            }
+            Constraint::Url => {
+                value.insert("format".to_string(), json!("uri"));
+            }
+            // A schema has a single pattern, so an explicit regex takes its place
+            Constraint::Contains(needle) if !has_pattern => {
+                value.insert("pattern".to_string(), json!(escape_pattern(needle)));
+            }
+            Constraint::Contains(_) => {}
+            Constraint::OneOf(options) => {
+                value.insert("enum".to_string(), json!(options));
+            }
+        }
+    }
 
-                properties.insert(
-                    parameter_name,
-                    Box::new(chat_completion::JSONSchemaDefine {
-                        schema_type: Some(parameter_type),
-                        description: Some(parameter_description),
-                        items: items,
-                        enum_values: if enum_info.is_list {
-                            None
-                        } else {
-                            Some(enum_values.clone()) // Clone here to avoid move error
-                        },
-                        ..Default::default()
-                    }),
-                );
+    let mut list = Map::new();
+    let (min_key, max_key, keywords) = match is_list {
+        true => ("minItems", "maxItems", &mut list),
+        false if is_string => ("minLength", "maxLength", &mut value),
+        false => return (value, list),
+    };
+    if let Some(min_length) = min_length {
+        keywords.insert(min_key.to_string(), json!(min_length));
+    }
+    if let Some(max_length) = max_length {
+        keywords.insert(max_key.to_string(), json!(max_length));
+    }
+    (value, list)
+}
+
+/// The schema of the items of a list, inside any lists nested in it.
+pub(crate) fn innermost_items(schema: &mut Map<String, Value>) -> &mut Map<String, Value> {
+    let mut items = schema.entry("items").or_insert_with(|| json!({}));
+    while items.get("items").is_some() {
+        items = &mut items["items"];
+    }
+    if !items.is_object() {
+        *items = json!({});
+    }
+    items.as_object_mut().unwrap()
+}
+
+fn field_schema(field_info: &ParameterInfo) -> Result<Map<String, Value>, InstructorError> {
+    let base_type = get_base_type(field_info);
+    let mut schema = Map::new();
+    let r#type = convert_parameter_type(base_type)?;
+    schema.insert("type".to_string(), json!(r#type));
+
+    let (minimum, maximum) = integer_bounds(base_type);
+    if let Some(minimum) = minimum {
+        schema.insert("minimum".to_string(), minimum);
+    }
+    if let Some(maximum) = maximum {
+        schema.insert("maximum".to_string(), maximum);
+    }
+
+    let (keywords, _) = constraint_keywords(
+        &field_info.constraints,
+        r#type == "string",
+        field_info.is_list,
+    );
+    schema.extend(keywords);
+    Ok(schema)
+}
+
+/// The variants of an enum. When any variant has a description, every variant is
+/// described on its own as a `const` under `oneOf`.
+fn enum_schema(enum_info: &EnumInfo) -> Map<String, Value> {
+    let mut schema = Map::new();
+    schema.insert("type".to_string(), json!("string"));
+    if enum_info
+        .variants
+        .iter()
+        .all(|variant| variant.description.is_empty())
+    {
+        schema.insert("enum".to_string(), json!(enum_info.r#enum));
+        return schema;
+    }
+
+    let variants: Vec<Value> = enum_info
+        .variants
+        .iter()
+        .map(|variant| match variant.description.is_empty() {
+            true => json!({"const": variant.name}),
+            false => json!({"const": variant.name, "description": variant.description}),
+        })
+        .collect();
+    schema.insert("oneOf".to_string(), Value::Array(variants));
+    schema
+}
+
+/// The structs and enums that are described once under `$defs` and referenced with
+/// `$ref`: recursive types and types that are used more than once.
+struct Definitions {
+    /// The type of the response model itself, which is referenced as `#`.
+    root: String,
+    shared: HashSet<String>,
+    schemas: Map<String, Value>,
+}
+
+impl Definitions {
+    fn new(root: &StructInfo) -> Self {
+        let mut uses = HashMap::new();
+        let mut referenced = HashSet::new();
+        for param in root.parameters.iter() {
+            count_types(param, &mut uses, &mut referenced);
+        }
+
+        let shared = uses
+            .into_iter()
+            .filter(|(_, count)| *count > 1)
+            .map(|(name, _)| name)
+            .chain(referenced)
+            .filter(|name| *name != root.r#type)
+            .collect();
+        Definitions {
+            root: root.r#type.clone(),
+            shared,
+            schemas: Map::new(),
+        }
+    }
+
+    fn is_shared(&self, r#type: &str) -> bool {
+        self.shared.contains(r#type)
+    }
+
+    /// Describes `r#type` under `$defs` with `describe`, unless it already is.
+    fn define(
+        &mut self,
+        r#type: &str,
+        describe: impl FnOnce(&mut Definitions) -> Result<Map<String, Value>, InstructorError>,
+    ) -> Result<(), InstructorError> {
+        if !self.schemas.contains_key(r#type) {
+            // Claim the name first, so that the type isn't described again while its
+            // own properties are
+            self.schemas.insert(r#type.to_string(), Value::Null);
+            let schema = describe(self)?;
+            self.schemas
+                .insert(r#type.to_string(), Value::Object(schema));
+        }
+        Ok(())
+    }
+
+    fn reference(&self, r#type: &str) -> Map<String, Value> {
+        let target = if r#type == self.root {
+            "#".to_string()
+        } else {
+            format!("#/$defs/{}", r#type)
+        };
+        let mut schema = Map::new();
+        schema.insert("$ref".to_string(), json!(target));
+        schema
+    }
+}
+
+/// Counts how often every named struct and enum is used, and collects the types that
+/// recursive types refer back to.
+///
+/// A type's body is only walked the first time it is used, since a shared type is
+/// described once and the types inside it are used once per description.
+fn count_types(
+    param: &Parameter,
+    uses: &mut HashMap<String, usize>,
+    referenced: &mut HashSet<String>,
+) {
+    match param {
+        Parameter::Struct(struct_info) => {
+            if !struct_info.r#type.is_empty() && !first_use(&struct_info.r#type, uses) {
+                return;
             }
-            Parameter::Struct(struct_info) => {
-                let parameter_name = struct_info.name.clone();
-                let parameter_description = struct_info.description.clone();
-
-                let parameter_type = if struct_info.is_list {
-                    chat_completion::JSONSchemaType::Array
-                } else {
-                    chat_completion::JSONSchemaType::Object
-                };
+            for param in struct_info.parameters.iter() {
+                count_types(param, uses, referenced);
+            }
+        }
+        Parameter::Union(union_info) => {
+            if !first_use(&union_info.r#type, uses) {
+                return;
+            }
+            for content in union_info
+                .variants
+                .iter()
+                .filter_map(|v| v.content.as_ref())
+            {
+                count_types(content, uses, referenced);
+            }
+        }
+        Parameter::List(list_info) => count_types(&list_info.items, uses, referenced),
+        Parameter::Map(map_info) => count_types(&map_info.values, uses, referenced),
+        Parameter::Tuple(tuple_info) => {
+            for item in tuple_info.items.iter() {
+                count_types(item, uses, referenced);
+            }
+        }
+        Parameter::Ref(ref_info) => {
+            referenced.insert(ref_info.r#type.clone());
+        }
+        Parameter::Field(_) | Parameter::Enum(_) => {}
+    }
+}
 
-                let items = if struct_info.is_list {
-                    Some(Box::new(chat_completion::JSONSchemaDefine {
-                        schema_type: Some(chat_completion::JSONSchemaType::Object),
-                        properties: Some(get_response_model_parameters(struct_info)),
-                        ..Default::default()
-                    }))
-                } else {
-                    None
-                };
+/// Counts a use of `r#type`, and returns whether it is the first one.
+fn first_use(r#type: &str, uses: &mut HashMap<String, usize>) -> bool {
+    let count = uses.entry(r#type.to_string()).or_default();
+    *count += 1;
+    *count == 1
+}
 
-                let struct_properties = if struct_info.is_list {
-                    None
-                } else {
-                    Some(get_response_model_parameters(struct_info))
-                };
+/// A struct, with its properties and which of them are required.
+fn struct_schema(
+    struct_info: &StructInfo,
+    defs: &mut Definitions,
+) -> Result<Map<String, Value>, InstructorError> {
+    let mut schema = Map::new();
+    schema.insert("type".to_string(), json!("object"));
+    schema.insert(
+        "properties".to_string(),
+        Value::Object(get_response_model_parameters(struct_info, defs)?),
+    );
+    schema.insert(
+        "required".to_string(),
+        json!(get_required_properties(struct_info)),
+    );
+    if struct_info.deny_unknown_fields {
+        schema.insert("additionalProperties".to_string(), json!(false));
+    }
+    Ok(schema)
+}
 
-                properties.insert(
-                    parameter_name,
-                    Box::new(chat_completion::JSONSchemaDefine {
-                        schema_type: Some(parameter_type),
-                        description: Some(parameter_description),
-                        items,
-                        properties: struct_properties,
-                        ..Default::default()
-                    }),
-                );
+/// A schema that only accepts the string `name`.
+fn literal_schema(name: &str) -> Value {
+    json!({"type": "string", "enum": [name]})
+}
+
+fn tagged_object(properties: Vec<(&str, Value)>) -> Value {
+    let required: Vec<&str> = properties.iter().map(|(name, _)| *name).collect();
+    let properties: Map<String, Value> = properties
+        .into_iter()
+        .map(|(name, schema)| (name.to_string(), schema))
+        .collect();
+    json!({"type": "object", "properties": properties, "required": required})
+}
+
+/// The schema of what a variant carries. Structs are always written out here rather
+/// than referenced, so that the alternatives of a union can be told apart.
+fn content_schema(content: &Parameter, defs: &mut Definitions) -> Result<Value, InstructorError> {
+    match content {
+        Parameter::Struct(struct_info) if !struct_info.is_list => {
+            if defs.is_shared(&struct_info.r#type) {
+                defs.define(&struct_info.r#type, |defs| struct_schema(struct_info, defs))?;
+            }
+            let mut schema = struct_schema(struct_info, defs)?;
+            if !struct_info.description.is_empty() {
+                schema.insert("description".to_string(), json!(struct_info.description));
             }
+            Ok(Value::Object(schema))
+        }
+        _ => parameter_schema(content, defs),
+    }
+}
+
+/// A variant laid out the way serde reads it for the union's tagging.
+fn variant_schema(
+    union_info: &UnionInfo,
+    variant: &VariantInfo,
+    defs: &mut Definitions,
+) -> Result<Value, InstructorError> {
+    let content = variant
+        .content
+        .as_ref()
+        .map(|content| content_schema(content, defs))
+        .transpose()?;
+    let name = variant.name.as_str();
+    let mut schema = match (&union_info.tagging, content) {
+        (UnionTagging::External, None) => literal_schema(name),
+        (UnionTagging::External, Some(content)) => tagged_object(vec![(name, content)]),
+        (UnionTagging::Internal { tag }, None) => {
+            tagged_object(vec![(tag.as_str(), literal_schema(name))])
+        }
+        (UnionTagging::Internal { tag }, Some(mut content)) => {
+            match content.get_mut("properties").and_then(Value::as_object_mut) {
+                Some(properties) => {
+                    properties.insert(tag.clone(), literal_schema(name));
+                }
+                None => {
+                    return Err(InstructorError::ResponseModel(format!(
+                        "Variant `{}` of `{}` is internally tagged and must hold a struct",
+                        name, union_info.name
+                    )))
+                }
+            }
+            if let Some(required) = content.get_mut("required").and_then(Value::as_array_mut) {
+                required.insert(0, json!(tag));
+            }
+            content
+        }
+        (UnionTagging::Adjacent { tag, .. }, None) => {
+            tagged_object(vec![(tag.as_str(), literal_schema(name))])
         }
+        (UnionTagging::Adjacent { tag, content: key }, Some(content)) => tagged_object(vec![
+            (tag.as_str(), literal_schema(name)),
+            (key.as_str(), content),
+        ]),
+        (UnionTagging::Untagged, None) => json!({"type": "null"}),
+        (UnionTagging::Untagged, Some(content)) => content,
+    };
+
+    if !variant.description.is_empty() {
+        schema["description"] = json!(variant.description);
     }
+    Ok(schema)
+}
 
-    properties
+fn union_schema(
+    union_info: &UnionInfo,
+    defs: &mut Definitions,
+) -> Result<Map<String, Value>, InstructorError> {
+    let variants = union_info
+        .variants
+        .iter()
+        .map(|variant| variant_schema(union_info, variant, defs))
+        .collect::<Result<Vec<Value>, InstructorError>>()?;
+
+    let mut schema = Map::new();
+    schema.insert("anyOf".to_string(), Value::Array(variants));
+    Ok(schema)
 }
 
-pub fn get_response_model(t: StructInfo) -> chat_completion::FunctionParameters {
-    // // TODO: Fix this up
-    let properties = get_response_model_parameters(&t);
-    let required_fields = get_required_properties(&t);
+/// Lets `schema` accept `null` as well.
+fn nullable(schema: Value) -> Value {
+    match schema {
+        Value::Object(mut schema)
+            if schema.get("type").is_some_and(Value::is_string) && !schema.contains_key("enum") =>
+        {
+            let r#type = schema["type"].clone();
+            schema.insert("type".to_string(), json!([r#type, "null"]));
+            Value::Object(schema)
+        }
+        schema => json!({"anyOf": [schema, {"type": "null"}]}),
+    }
+}
+
+/// The schema of a list item, map value or tuple element. Unlike a property, which can
+/// be left out, an optional one has to be `null`.
+fn item_schema(param: &Parameter, defs: &mut Definitions) -> Result<Value, InstructorError> {
+    let schema = parameter_schema(param, defs)?;
+    Ok(match param.is_optional() {
+        true => nullable(schema),
+        false => schema,
+    })
+}
 
-    chat_completion::FunctionParameters {
-        schema_type: chat_completion::JSONSchemaType::Object,
-        properties: Some(properties),
-        required: Some(required_fields),
+fn parameter_schema(param: &Parameter, defs: &mut Definitions) -> Result<Value, InstructorError> {
+    Ok(match param {
+        Parameter::Field(field_info) => {
+            let mut schema = with_list(
+                field_schema(field_info)?,
+                &field_info.comment,
+                field_info.is_list,
+            );
+            if field_info.is_list {
+                let (_, keywords) = constraint_keywords(&field_info.constraints, false, true);
+                schema.as_object_mut().unwrap().extend(keywords);
+            }
+            schema
+        }
+        Parameter::Enum(enum_info) => with_list(
+            enum_schema(enum_info),
+            &enum_info.description,
+            enum_info.is_list,
+        ),
+        Parameter::Struct(struct_info) => {
+            let schema = if defs.is_shared(&struct_info.r#type) {
+                defs.define(&struct_info.r#type, |defs| struct_schema(struct_info, defs))?;
+                defs.reference(&struct_info.r#type)
+            } else {
+                struct_schema(struct_info, defs)?
+            };
+            with_list(schema, &struct_info.description, struct_info.is_list)
+        }
+        Parameter::Union(union_info) => {
+            let schema = if defs.is_shared(&union_info.r#type) {
+                defs.define(&union_info.r#type, |defs| union_schema(union_info, defs))?;
+                defs.reference(&union_info.r#type)
+            } else {
+                union_schema(union_info, defs)?
+            };
+            with_list(schema, &union_info.description, union_info.is_list)
+        }
+        Parameter::Ref(ref_info) => with_list(
+            defs.reference(&ref_info.r#type),
+            &ref_info.description,
+            ref_info.is_list,
+        ),
+        Parameter::List(list_info) => {
+            let mut schema = json!({
+                "type": "array",
+                "description": list_info.description,
+                "items": item_schema(&list_info.items, defs)?,
+            });
+            if let Some(length) = list_info.length {
+                schema["minItems"] = json!(length);
+                schema["maxItems"] = json!(length);
+            }
+            let (keywords, list_keywords) =
+                constraint_keywords(&list_info.constraints, false, true);
+            let list = schema.as_object_mut().unwrap();
+            innermost_items(list).extend(keywords);
+            list.extend(list_keywords);
+            schema
+        }
+        Parameter::Map(map_info) => json!({
+            "type": "object",
+            "description": map_info.description,
+            "additionalProperties": item_schema(&map_info.values, defs)?,
+        }),
+        Parameter::Tuple(tuple_info) => json!({
+            "type": "array",
+            "description": tuple_info.description,
+            "prefixItems": tuple_info
+                .items
+                .iter()
+                .map(|item| item_schema(item, defs))
+                .collect::<Result<Vec<Value>, InstructorError>>()?,
+            "minItems": tuple_info.items.len(),
+            "maxItems": tuple_info.items.len(),
+        }),
+    })
+}
+
+fn get_response_model_parameters(
+    t: &StructInfo,
+    defs: &mut Definitions,
+) -> Result<Map<String, Value>, InstructorError> {
+    let mut properties = Map::new();
+
+    for param in t.parameters.iter() {
+        properties.insert(param.name().to_string(), parameter_schema(param, defs)?);
     }
+
+    Ok(properties)
+}
+
+/// The JSON schema of a response model. Recursive types, and types used more than
+/// once, are described once under `$defs` and referenced with `$ref`.
+pub fn get_response_model(t: StructInfo) -> Result<Value, InstructorError> {
+    let mut defs = Definitions::new(&t);
+    let mut schema = struct_schema(&t, &mut defs)?;
+    if !defs.schemas.is_empty() {
+        schema.insert("$defs".to_string(), Value::Object(defs.schemas));
+    }
+    Ok(Value::Object(schema))
 }
 
 #[cfg(test)]
 mod tests {
-    use std::vec;
-
     use super::*;
     use instruct_macros::InstructMacro;
     use instruct_macros_types::{
         InstructMacro, InstructMacroResult, Parameter, ParameterInfo, StructInfo,
     };
     use serde::{Deserialize, Serialize};
+    use serde_json::json;
 
     #[test]
     fn test_person_with_nested_address() {
@@ -213,74 +627,29 @@ mod tests {
                 panic!("Expected StructInfo but got a different InstructMacroResult variant");
             }
         };
-        let parameters = get_response_model(parsed_model);
-
-        let expected_parameters = chat_completion::FunctionParameters {
-            schema_type: chat_completion::JSONSchemaType::Object,
-            properties: Some({
-                let mut props = std::collections::HashMap::new();
-                props.insert(
-                    "name".to_string(),
-                    Box::new(chat_completion::JSONSchemaDefine {
-                        schema_type: Some(chat_completion::JSONSchemaType::String),
-                        description: Some("The name of the person".to_string()),
-                        ..Default::default()
-                    }),
-                );
-                props.insert(
-                    "age".to_string(),
-                    Box::new(chat_completion::JSONSchemaDefine {
-                        schema_type: Some(chat_completion::JSONSchemaType::Number),
-                        description: Some("The age of the person".to_string()),
-                        ..Default::default()
-                    }),
-                );
-                props.insert(
-                    "address".to_string(),
-                    Box::new(chat_completion::JSONSchemaDefine {
-                        schema_type: Some(chat_completion::JSONSchemaType::Object),
-                        description: Some("The address of the person".to_string()),
-                        properties: Some({
-                            let mut address_props = std::collections::HashMap::new();
-                            address_props.insert(
-                                "street".to_string(),
-                                Box::new(chat_completion::JSONSchemaDefine {
-                                    schema_type: Some(chat_completion::JSONSchemaType::String),
-                                    description: Some("The street of the address".to_string()),
-                                    ..Default::default()
-                                }),
-                            );
-                            address_props.insert(
-                                "city".to_string(),
-                                Box::new(chat_completion::JSONSchemaDefine {
-                                    schema_type: Some(chat_completion::JSONSchemaType::String),
-                                    description: Some("The city of the address".to_string()),
-                                    ..Default::default()
-                                }),
-                            );
-                            address_props.insert(
-                                "zip_code".to_string(),
-                                Box::new(chat_completion::JSONSchemaDefine {
-                                    schema_type: Some(chat_completion::JSONSchemaType::String),
-                                    description: Some("The zip code of the address".to_string()),
-                                    ..Default::default()
-                                }),
-                            );
-                            address_props
-                        }),
-                        ..Default::default()
-                    }),
-                );
-                props
-            }),
-            required: Some(vec![
-                "name".to_string(),
-                "age".to_string(),
-                "address".to_string(),
-            ]),
-        };
+        let parameters = get_response_model(parsed_model).unwrap();
 
-        assert_eq!(parameters, expected_parameters);
+        assert_eq!(
+            parameters,
+            json!({
+                "type": "object",
+                "properties": {
+                    "name": {"type": "string", "description": "The name of the person"},
+                    "age": {"type": "integer", "minimum": 0, "maximum": 255, "description": "The age of the person"},
+                    "address": {
+                        "type": "object",
+                        "description": "The address of the person",
+                        "properties": {
+                            "street": {"type": "string", "description": "The street of the address"},
+                            "city": {"type": "string", "description": "The city of the address"},
+                            "zip_code": {"type": "string", "description": "The zip code of the address"}
+                        },
+                        "required": ["street", "city", "zip_code"]
+                    }
+                },
+                "required": ["name", "age", "address"]
+            })
+        );
     }
 
     #[test]
@@ -312,51 +681,28 @@ mod tests {
                 panic!("Expected StructInfo but got a different InstructMacroResult variant");
             }
         };
-        let parameters = get_response_model(parsed_model);
-
-        let expected_parameters = chat_completion::FunctionParameters {
-            schema_type: chat_completion::JSONSchemaType::Object,
-            properties: Some({
-                let mut props = std::collections::HashMap::new();
-                props.insert(
-                    "name".to_string(),
-                    Box::new(chat_completion::JSONSchemaDefine {
-                        schema_type: Some(chat_completion::JSONSchemaType::String),
-                        description: Some("The name of the person".to_string()),
-                        ..Default::default()
-                    }),
-                );
-                props.insert(
-                    "age".to_string(),
-                    Box::new(chat_completion::JSONSchemaDefine {
-                        schema_type: Some(chat_completion::JSONSchemaType::Number),
-                        description: Some("The age of the person".to_string()),
-                        ..Default::default()
-                    }),
-                );
-                props.insert(
-                    "job".to_string(),
-                    Box::new(chat_completion::JSONSchemaDefine {
-                        schema_type: Some(chat_completion::JSONSchemaType::String),
-                        description: Some("The job of the person".to_string()),
-                        enum_values: Some(vec![
-                            "Developer".to_string(),
-                            "Teacher".to_string(),
-                            "Artist".to_string(),
-                        ]),
-                        ..Default::default()
-                    }),
-                );
-                props
-            }),
-            required: Some(vec![
-                "name".to_string(),
-                "age".to_string(),
-                "job".to_string(),
-            ]),
-        };
+        let parameters = get_response_model(parsed_model).unwrap();
 
-        assert_eq!(expected_parameters, parameters);
+        assert_eq!(
+            parameters,
+            json!({
+                "type": "object",
+                "properties": {
+                    "name": {"type": "string", "description": "The name of the person"},
+                    "age": {"type": "integer", "minimum": 0, "maximum": 255, "description": "The age of the person"},
+                    "job": {
+                        "type": "string",
+                        "description": "The job of the person",
+                        "oneOf": [
+                            {"const": "Developer", "description": "Software Developer"},
+                            {"const": "Teacher", "description": "Teacher"},
+                            {"const": "Artist", "description": "Artist"}
+                        ]
+                    }
+                },
+                "required": ["name", "age", "job"]
+            })
+        );
     }
 
     #[test]
@@ -376,34 +722,19 @@ mod tests {
                 panic!("Expected StructInfo but got a different InstructMacroResult variant");
             }
         };
-        let parameters = get_response_model(parsed_model);
-
-        let expected_parameters = chat_completion::FunctionParameters {
-            schema_type: chat_completion::JSONSchemaType::Object,
-            properties: Some({
-                let mut props = std::collections::HashMap::new();
-                props.insert(
-                    "name".to_string(),
-                    Box::new(chat_completion::JSONSchemaDefine {
-                        schema_type: Some(chat_completion::JSONSchemaType::String),
-                        description: Some("The name of the user".to_string()),
-                        ..Default::default()
-                    }),
-                );
-                props.insert(
-                    "age".to_string(),
-                    Box::new(chat_completion::JSONSchemaDefine {
-                        schema_type: Some(chat_completion::JSONSchemaType::Number),
-                        description: Some("The age of the user".to_string()),
-                        ..Default::default()
-                    }),
-                );
-                props
-            }),
-            required: Some(vec!["name".to_string(), "age".to_string()]),
-        };
+        let parameters = get_response_model(parsed_model).unwrap();
 
-        assert_eq!(expected_parameters, parameters);
+        assert_eq!(
+            parameters,
+            json!({
+                "type": "object",
+                "properties": {
+                    "name": {"type": "string", "description": "The name of the user"},
+                    "age": {"type": "integer", "minimum": 0, "maximum": 255, "description": "The age of the user"}
+                },
+                "required": ["name", "age"]
+            })
+        );
     }
 
     #[test]
@@ -423,34 +754,20 @@ mod tests {
                 panic!("Expected StructInfo but got a different InstructMacroResult variant");
             }
         };
-        let parameters = get_response_model(parsed_model);
-
-        let expected_parameters = chat_completion::FunctionParameters {
-            schema_type: chat_completion::JSONSchemaType::Object,
-            properties: Some({
-                let mut props = std::collections::HashMap::new();
-                props.insert(
-                    "name".to_string(),
-                    Box::new(chat_completion::JSONSchemaDefine {
-                        schema_type: Some(chat_completion::JSONSchemaType::String),
-                        description: Some("The name of the user".to_string()),
-                        ..Default::default()
-                    }),
-                );
-                props.insert(
-                    "age".to_string(),
-                    Box::new(chat_completion::JSONSchemaDefine {
-                        schema_type: Some(chat_completion::JSONSchemaType::Number),
-                        description: Some("The age of the user".to_string()),
-                        ..Default::default()
-                    }),
-                );
-                props
-            }),
-            required: Some(vec!["name".to_string()]), // Only "name" should be required
-        };
+        let parameters = get_response_model(parsed_model).unwrap();
 
-        assert_eq!(expected_parameters, parameters);
+        assert_eq!(
+            parameters,
+            json!({
+                "type": "object",
+                "properties": {
+                    "name": {"type": "string", "description": "The name of the user"},
+                    "age": {"type": "integer", "minimum": 0, "maximum": 255, "description": "The age of the user"}
+                },
+                // Only "name" should be required
+                "required": ["name"]
+            })
+        );
     }
 
     #[test]
@@ -473,46 +790,27 @@ mod tests {
                 panic!("Expected StructInfo but got a different InstructMacroResult variant");
             }
         };
-        let parameters = get_response_model(parsed_model);
-
-        let expected_parameters = chat_completion::FunctionParameters {
-            schema_type: chat_completion::JSONSchemaType::Object,
-            properties: Some({
-                let mut props = std::collections::HashMap::new();
-                props.insert(
-                    "user".to_string(),
-                    Box::new(chat_completion::JSONSchemaDefine {
-                        schema_type: Some(chat_completion::JSONSchemaType::Object),
-                        description: Some("".to_string()),
-                        properties: Some({
-                            let mut user_props = std::collections::HashMap::new();
-                            user_props.insert(
-                                "name".to_string(),
-                                Box::new(chat_completion::JSONSchemaDefine {
-                                    schema_type: Some(chat_completion::JSONSchemaType::String),
-                                    description: Some("".to_string()),
-                                    ..Default::default()
-                                }),
-                            );
-                            user_props.insert(
-                                "age".to_string(),
-                                Box::new(chat_completion::JSONSchemaDefine {
-                                    schema_type: Some(chat_completion::JSONSchemaType::Number),
-                                    description: Some("".to_string()),
-                                    ..Default::default()
-                                }),
-                            );
-                            user_props
-                        }),
-                        ..Default::default()
-                    }),
-                );
-                props
-            }),
-            required: Some(vec![]), // No required fields
-        };
+        let parameters = get_response_model(parsed_model).unwrap();
 
-        assert_eq!(expected_parameters, parameters);
+        assert_eq!(
+            parameters,
+            json!({
+                "type": "object",
+                "properties": {
+                    "user": {
+                        "type": "object",
+                        "description": "",
+                        "properties": {
+                            "name": {"type": "string", "description": ""},
+                            "age": {"type": "integer", "minimum": 0, "maximum": 255, "description": ""}
+                        },
+                        "required": ["name", "age"]
+                    }
+                },
+                // No required fields
+                "required": []
+            })
+        );
     }
 
     #[test]
@@ -530,30 +828,26 @@ mod tests {
                 panic!("Expected StructInfo but got a different InstructMacroResult variant");
             }
         };
-        let parameters = get_response_model(parsed_model);
-
-        let expected_parameters = chat_completion::FunctionParameters {
-            schema_type: chat_completion::JSONSchemaType::Object,
-            properties: Some({
-                let mut props = std::collections::HashMap::new();
-                props.insert(
-                    "numbers".to_string(),
-                    Box::new(chat_completion::JSONSchemaDefine {
-                        schema_type: Some(chat_completion::JSONSchemaType::Array),
-                        description: Some("A list of numbers".to_string()),
-                        items: Some(Box::new(chat_completion::JSONSchemaDefine {
-                            schema_type: Some(chat_completion::JSONSchemaType::Number),
-                            ..Default::default()
-                        })),
-                        ..Default::default()
-                    }),
-                );
-                props
-            }),
-            required: Some(vec!["numbers".to_string()]), // "numbers" is a required field
-        };
+        let parameters = get_response_model(parsed_model).unwrap();
 
-        assert_eq!(expected_parameters, parameters);
+        assert_eq!(
+            parameters,
+            json!({
+                "type": "object",
+                "properties": {
+                    "numbers": {
+                        "type": "array",
+                        "description": "A list of numbers",
+                        "items": {
+                            "type": "integer",
+                            "minimum": -2147483648,
+                            "maximum": 2147483647
+                        }
+                    }
+                },
+                "required": ["numbers"]
+            })
+        );
     }
 
     #[test]
@@ -575,42 +869,26 @@ mod tests {
                 panic!("Expected StructInfo but got a different InstructMacroResult variant");
             }
         };
-        let parameters = get_response_model(parsed_model);
-
-        let expected_parameters = chat_completion::FunctionParameters {
-            schema_type: chat_completion::JSONSchemaType::Object,
-            properties: Some({
-                let mut props = std::collections::HashMap::new();
-                props.insert(
-                    "users".to_string(),
-                    Box::new(chat_completion::JSONSchemaDefine {
-                        schema_type: Some(chat_completion::JSONSchemaType::Array),
-                        description: Some("A list of users".to_string()),
-                        required: None,
-                        items: Some(Box::new(chat_completion::JSONSchemaDefine {
-                            schema_type: Some(chat_completion::JSONSchemaType::Object),
-                            properties: Some({
-                                let mut user_props = std::collections::HashMap::new();
-                                user_props.insert(
-                                    "name".to_string(),
-                                    Box::new(chat_completion::JSONSchemaDefine {
-                                        schema_type: Some(chat_completion::JSONSchemaType::String),
-                                        description: Some("".to_string()),
-                                        ..Default::default()
-                                    }),
-                                );
-                                user_props
-                            }),
-                            ..Default::default()
-                        })),
-                        ..Default::default()
-                    }),
-                );
-                props
-            }),
-            required: Some(vec!["users".to_string()]), // "users" is a required field
-        };
-        assert_eq!(expected_parameters, parameters);
+        let parameters = get_response_model(parsed_model).unwrap();
+
+        assert_eq!(
+            parameters,
+            json!({
+                "type": "object",
+                "properties": {
+                    "users": {
+                        "type": "array",
+                        "description": "A list of users",
+                        "items": {
+                            "type": "object",
+                            "properties": {"name": {"type": "string", "description": ""}},
+                            "required": ["name"]
+                        }
+                    }
+                },
+                "required": ["users"]
+            })
+        );
     }
 
     #[test]
@@ -637,42 +915,845 @@ mod tests {
                 panic!("Expected StructInfo but got a different InstructMacroResult variant");
             }
         };
-        let parameters = get_response_model(parsed_model);
-
-        let expected_parameters = chat_completion::FunctionParameters {
-            schema_type: chat_completion::JSONSchemaType::Object,
-            properties: Some({
-                let mut props = std::collections::HashMap::new();
-                props.insert(
-                    "name".to_string(),
-                    Box::new(chat_completion::JSONSchemaDefine {
-                        schema_type: Some(chat_completion::JSONSchemaType::String),
-                        description: Some("The name of the person".to_string()),
-                        ..Default::default()
-                    }),
-                );
-                props.insert(
-                    "jobs".to_string(),
-                    Box::new(chat_completion::JSONSchemaDefine {
-                        schema_type: Some(chat_completion::JSONSchemaType::Array),
-                        description: Some("The jobs of the person".to_string()),
-                        items: Some(Box::new(chat_completion::JSONSchemaDefine {
-                            schema_type: Some(chat_completion::JSONSchemaType::String),
-                            enum_values: Some(vec![
-                                "Developer".to_string(),
-                                "Teacher".to_string(),
-                                "Artist".to_string(),
-                            ]),
-                            ..Default::default()
-                        })),
-                        ..Default::default()
+        let parameters = get_response_model(parsed_model).unwrap();
+
+        assert_eq!(
+            parameters,
+            json!({
+                "type": "object",
+                "properties": {
+                    "name": {"type": "string", "description": "The name of the person"},
+                    "jobs": {
+                        "type": "array",
+                        "description": "The jobs of the person",
+                        "items": {"type": "string", "enum": ["Developer", "Teacher", "Artist"]}
+                    }
+                },
+                "required": ["name", "jobs"]
+            })
+        );
+    }
+
+    #[test]
+    fn test_unsupported_type_is_an_error() {
+        let parsed_model = StructInfo {
+            name: "Event".to_string(),
+            r#type: "Event".to_string(),
+            description: "".to_string(),
+            parameters: vec![Parameter::Field(ParameterInfo {
+                name: "id".to_string(),
+                r#type: "Uuid".to_string(),
+                comment: "".to_string(),
+                constraints: vec![],
+                is_optional: false,
+                is_list: false,
+            })],
+            deny_unknown_fields: false,
+            is_optional: false,
+            is_list: false,
+        };
+
+        assert_eq!(
+            get_response_model(parsed_model).unwrap_err(),
+            InstructorError::ResponseModel("Unsupported type: Uuid".to_string())
+        );
+    }
+
+    #[test]
+    fn test_union_follows_serde_tagging() {
+        #[derive(InstructMacro, Debug, Serialize, Deserialize, PartialEq)]
+        struct Message {
+            text: String,
+        }
+
+        #[derive(InstructMacro, Debug, Serialize, Deserialize, PartialEq)]
+        #[description("What to do next")]
+        enum Action {
+            #[description("Search the web")]
+            Search {
+                query: String,
+            },
+            Reply(Message),
+            Stop,
+        }
+
+        #[derive(InstructMacro, Debug, Serialize, Deserialize, PartialEq)]
+        #[serde(tag = "type")]
+        enum Internal {
+            Search { query: String },
+            Stop,
+        }
+
+        #[derive(InstructMacro, Debug, Serialize, Deserialize, PartialEq)]
+        #[serde(tag = "type", content = "data")]
+        enum Adjacent {
+            Wait(u32),
+            Stop,
+        }
+
+        #[derive(InstructMacro, Debug, Serialize, Deserialize, PartialEq)]
+        #[serde(untagged)]
+        enum Untagged {
+            Reply(Message),
+            Count(u32),
+        }
+
+        #[derive(InstructMacro, Debug, Serialize, Deserialize, PartialEq)]
+        struct Plan {
+            action: Action,
+            internal: Internal,
+            adjacent: Adjacent,
+            untagged: Vec<Untagged>,
+        }
+
+        let parsed_model = match Plan::get_info() {
+            InstructMacroResult::Struct(info) => info,
+            _ => panic!("Expected StructInfo but got a different InstructMacroResult variant"),
+        };
+        let schema = get_response_model(parsed_model).unwrap();
+
+        assert_eq!(
+            schema["properties"]["action"],
+            json!({
+                "description": "What to do next",
+                "anyOf": [
+                    {
+                        "type": "object",
+                        "description": "Search the web",
+                        "properties": {
+                            "Search": {
+                                "type": "object",
+                                "description": "Search the web",
+                                "properties": {"query": {"type": "string", "description": ""}},
+                                "required": ["query"]
+                            }
+                        },
+                        "required": ["Search"]
+                    },
+                    {
+                        "type": "object",
+                        "properties": {
+                            "Reply": {
+                                "type": "object",
+                                "properties": {"text": {"type": "string", "description": ""}},
+                                "required": ["text"]
+                            }
+                        },
+                        "required": ["Reply"]
+                    },
+                    {"type": "string", "enum": ["Stop"]}
+                ]
+            })
+        );
+        assert_eq!(
+            schema["properties"]["internal"]["anyOf"],
+            json!([
+                {
+                    "type": "object",
+                    "properties": {
+                        "type": {"type": "string", "enum": ["Search"]},
+                        "query": {"type": "string", "description": ""}
+                    },
+                    "required": ["type", "query"]
+                },
+                {
+                    "type": "object",
+                    "properties": {"type": {"type": "string", "enum": ["Stop"]}},
+                    "required": ["type"]
+                }
+            ])
+        );
+        assert_eq!(
+            schema["properties"]["adjacent"]["anyOf"][0],
+            json!({
+                "type": "object",
+                "properties": {
+                    "type": {"type": "string", "enum": ["Wait"]},
+                    "data": {
+                        "type": "integer",
+                        "minimum": 0,
+                        "maximum": 4294967295u32,
+                        "description": ""
+                    }
+                },
+                "required": ["type", "data"]
+            })
+        );
+        assert_eq!(schema["properties"]["untagged"]["type"], "array");
+        assert_eq!(
+            schema["properties"]["untagged"]["items"]["anyOf"][1],
+            json!({
+                "type": "integer",
+                "minimum": 0,
+                "maximum": 4294967295u32,
+                "description": ""
+            })
+        );
+
+        // An answer shaped like the schema deserializes straight back into the enums
+        let answer = json!({
+            "action": {"Search": {"query": "rust unions"}},
+            "internal": {"type": "Search", "query": "serde"},
+            "adjacent": {"type": "Wait", "data": 5},
+            "untagged": [{"text": "hi"}, 3]
+        });
+        let plan: Plan = serde_json::from_value(answer).unwrap();
+        assert_eq!(
+            plan,
+            Plan {
+                action: Action::Search {
+                    query: "rust unions".to_string()
+                },
+                internal: Internal::Search {
+                    query: "serde".to_string()
+                },
+                adjacent: Adjacent::Wait(5),
+                untagged: vec![
+                    Untagged::Reply(Message {
+                        text: "hi".to_string()
                     }),
-                );
-                props
-            }),
-            required: Some(vec!["name".to_string(), "jobs".to_string()]),
+                    Untagged::Count(3)
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn test_internally_tagged_newtype_must_hold_a_struct() {
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        #[serde(tag = "type")]
+        enum Internal {
+            Wait(u32),
+        }
+
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        struct Plan {
+            internal: Internal,
+        }
+
+        let parsed_model = match Plan::get_info() {
+            InstructMacroResult::Struct(info) => info,
+            _ => panic!("Expected StructInfo but got a different InstructMacroResult variant"),
         };
 
-        assert_eq!(expected_parameters, parameters);
+        assert_eq!(
+            get_response_model(parsed_model).unwrap_err(),
+            InstructorError::ResponseModel(
+                "Variant `Wait` of `internal` is internally tagged and must hold a struct"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_struct_with_nested_lists() {
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        struct Matrix {
+            #[description("The rows of the matrix")]
+            rows: Vec<Vec<i32>>,
+            labels: Option<Vec<std::string::String>>,
+        }
+
+        let parsed_model = match Matrix::get_info() {
+            InstructMacroResult::Struct(info) => info,
+            _ => panic!("Expected StructInfo but got a different InstructMacroResult variant"),
+        };
+
+        assert_eq!(
+            get_response_model(parsed_model).unwrap(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "rows": {
+                        "type": "array",
+                        "description": "The rows of the matrix",
+                        "items": {
+                            "type": "array",
+                            "description": "",
+                            "items": {
+                                "type": "integer",
+                                "minimum": -2147483648,
+                                "maximum": 2147483647
+                            }
+                        }
+                    },
+                    "labels": {
+                        "type": "array",
+                        "description": "",
+                        "items": {"type": "string"}
+                    }
+                },
+                "required": ["rows"]
+            })
+        );
+    }
+
+    #[test]
+    fn test_nullable_items() {
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        enum Color {
+            Red,
+            Blue,
+        }
+
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        struct Survey {
+            answers: Vec<Option<u8>>,
+            colors: Option<Vec<Option<Color>>>,
+            notes: HashMap<String, Option<String>>,
+        }
+
+        let parsed_model = match Survey::get_info() {
+            InstructMacroResult::Struct(info) => info,
+            _ => panic!("Expected StructInfo but got a different InstructMacroResult variant"),
+        };
+
+        assert_eq!(
+            get_response_model(parsed_model).unwrap(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "answers": {
+                        "type": "array",
+                        "description": "",
+                        "items": {
+                            "type": ["integer", "null"],
+                            "description": "",
+                            "minimum": 0,
+                            "maximum": 255
+                        }
+                    },
+                    "colors": {
+                        "type": "array",
+                        "description": "",
+                        "items": {
+                            "anyOf": [
+                                {"type": "string", "description": "", "enum": ["Red", "Blue"]},
+                                {"type": "null"}
+                            ]
+                        }
+                    },
+                    "notes": {
+                        "type": "object",
+                        "description": "",
+                        "additionalProperties": {"type": ["string", "null"], "description": ""}
+                    }
+                },
+                "required": ["answers", "notes"]
+            })
+        );
+    }
+
+    #[test]
+    fn test_integers_and_floats() {
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        struct Product {
+            price: f64,
+            weight: Option<f32>,
+            stock: u64,
+            delta: i8,
+            serial: u128,
+        }
+
+        let parsed_model = match Product::get_info() {
+            InstructMacroResult::Struct(info) => info,
+            _ => panic!("Expected StructInfo but got a different InstructMacroResult variant"),
+        };
+        let schema = get_response_model(parsed_model).unwrap();
+
+        assert_eq!(
+            schema["properties"]["price"],
+            json!({"type": "number", "description": ""})
+        );
+        assert_eq!(
+            schema["properties"]["weight"],
+            json!({"type": "number", "description": ""})
+        );
+        assert_eq!(
+            schema["properties"]["stock"],
+            json!({"type": "integer", "minimum": 0, "maximum": u64::MAX, "description": ""})
+        );
+        assert_eq!(
+            schema["properties"]["delta"],
+            json!({"type": "integer", "minimum": -128, "maximum": 127, "description": ""})
+        );
+        assert_eq!(
+            schema["properties"]["serial"],
+            json!({"type": "integer", "minimum": 0, "description": ""})
+        );
+    }
+
+    #[test]
+    fn test_struct_with_maps() {
+        use std::collections::{BTreeMap, HashMap};
+
+        #[derive(InstructMacro, Debug, Serialize, Deserialize, PartialEq)]
+        #[description("A product")]
+        struct Product {
+            name: String,
+        }
+
+        #[derive(InstructMacro, Debug, Serialize, Deserialize, PartialEq)]
+        struct Catalog {
+            #[description("Attribute name to value")]
+            attributes: HashMap<String, String>,
+            products: BTreeMap<String, Product>,
+            tags: Option<HashMap<String, Vec<String>>>,
+        }
+
+        let parsed_model = match Catalog::get_info() {
+            InstructMacroResult::Struct(info) => info,
+            _ => panic!("Expected StructInfo but got a different InstructMacroResult variant"),
+        };
+
+        assert_eq!(
+            get_response_model(parsed_model).unwrap(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "attributes": {
+                        "type": "object",
+                        "description": "Attribute name to value",
+                        "additionalProperties": {"type": "string", "description": ""}
+                    },
+                    "products": {
+                        "type": "object",
+                        "description": "",
+                        "additionalProperties": {
+                            "type": "object",
+                            "description": "A product",
+                            "properties": {"name": {"type": "string", "description": ""}},
+                            "required": ["name"]
+                        }
+                    },
+                    "tags": {
+                        "type": "object",
+                        "description": "",
+                        "additionalProperties": {
+                            "type": "array",
+                            "description": "",
+                            "items": {"type": "string"}
+                        }
+                    }
+                },
+                "required": ["attributes", "products"]
+            })
+        );
+
+        let catalog: Catalog = serde_json::from_value(json!({
+            "attributes": {"color": "red"},
+            "products": {"p1": {"name": "Pen"}},
+            "tags": null
+        }))
+        .unwrap();
+        assert_eq!(catalog.attributes["color"], "red");
+        assert_eq!(catalog.products["p1"].name, "Pen");
+    }
+
+    #[test]
+    fn test_struct_with_tuples_and_newtypes() {
+        #[derive(InstructMacro, Debug, Serialize, Deserialize, PartialEq)]
+        #[description("An email address")]
+        struct Email(String);
+
+        #[derive(InstructMacro, Debug, Serialize, Deserialize, PartialEq)]
+        #[description("Latitude and longitude")]
+        struct Coordinates(f64, f64);
+
+        #[derive(InstructMacro, Debug, Serialize, Deserialize, PartialEq)]
+        struct Office {
+            email: Email,
+            location: Coordinates,
+            floors: (u8, Option<String>),
+            #[description("Opening hours per weekday")]
+            hours: [String; 5],
+        }
+
+        let parsed_model = match Office::get_info() {
+            InstructMacroResult::Struct(info) => info,
+            _ => panic!("Expected StructInfo but got a different InstructMacroResult variant"),
+        };
+
+        assert_eq!(
+            get_response_model(parsed_model).unwrap(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "email": {"type": "string", "description": "An email address"},
+                    "location": {
+                        "type": "array",
+                        "description": "Latitude and longitude",
+                        "prefixItems": [
+                            {"type": "number", "description": ""},
+                            {"type": "number", "description": ""}
+                        ],
+                        "minItems": 2,
+                        "maxItems": 2
+                    },
+                    "floors": {
+                        "type": "array",
+                        "description": "",
+                        "prefixItems": [
+                            {"type": "integer", "description": "", "minimum": 0, "maximum": 255},
+                            {"type": ["string", "null"], "description": ""}
+                        ],
+                        "minItems": 2,
+                        "maxItems": 2
+                    },
+                    "hours": {
+                        "type": "array",
+                        "description": "Opening hours per weekday",
+                        "items": {"type": "string", "description": ""},
+                        "minItems": 5,
+                        "maxItems": 5
+                    }
+                },
+                "required": ["email", "location", "floors", "hours"]
+            })
+        );
+
+        let office: Office = serde_json::from_value(json!({
+            "email": "hq@example.com",
+            "location": [52.5, 13.4],
+            "floors": [3, null],
+            "hours": ["9-5", "9-5", "9-5", "9-5", "9-1"]
+        }))
+        .unwrap();
+        assert_eq!(office.email, Email("hq@example.com".to_string()));
+        assert_eq!(office.location, Coordinates(52.5, 13.4));
+        assert_eq!(office.floors, (3, None));
+    }
+
+    #[test]
+    fn test_recursive_struct_refers_to_the_root() {
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        #[description("A comment and its replies")]
+        struct Comment {
+            text: String,
+            #[description("Replies to the comment")]
+            replies: Vec<Comment>,
+        }
+
+        let parsed_model = match Comment::get_info() {
+            InstructMacroResult::Struct(info) => info,
+            _ => panic!("Expected StructInfo but got a different InstructMacroResult variant"),
+        };
+
+        assert_eq!(
+            get_response_model(parsed_model).unwrap(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "text": {"type": "string", "description": ""},
+                    "replies": {
+                        "type": "array",
+                        "description": "Replies to the comment",
+                        "items": {"$ref": "#"}
+                    }
+                },
+                "required": ["text", "replies"]
+            })
+        );
+
+        let comment: Comment = serde_json::from_value(json!({
+            "text": "First",
+            "replies": [{"text": "Second", "replies": []}]
+        }))
+        .unwrap();
+        assert_eq!(comment.replies[0].text, "Second");
+    }
+
+    #[test]
+    fn test_shared_and_recursive_types_go_in_defs() {
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        #[description("A postal address")]
+        struct Address {
+            city: String,
+        }
+
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        enum Expr {
+            Number(f64),
+            Negate(Box<Expr>),
+        }
+
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        struct Order {
+            #[description("Where to send the order")]
+            shipping: Address,
+            billing: Option<Address>,
+            total: Expr,
+        }
+
+        let parsed_model = match Order::get_info() {
+            InstructMacroResult::Struct(info) => info,
+            _ => panic!("Expected StructInfo but got a different InstructMacroResult variant"),
+        };
+
+        assert_eq!(
+            get_response_model(parsed_model).unwrap(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "shipping": {
+                        "$ref": "#/$defs/Address",
+                        "description": "Where to send the order"
+                    },
+                    "billing": {"$ref": "#/$defs/Address", "description": "A postal address"},
+                    "total": {"$ref": "#/$defs/Expr", "description": ""}
+                },
+                "required": ["shipping", "total"],
+                "$defs": {
+                    "Address": {
+                        "type": "object",
+                        "properties": {"city": {"type": "string", "description": ""}},
+                        "required": ["city"]
+                    },
+                    "Expr": {
+                        "anyOf": [
+                            {
+                                "type": "object",
+                                "properties": {"Number": {"type": "number", "description": ""}},
+                                "required": ["Number"]
+                            },
+                            {
+                                "type": "object",
+                                "properties": {
+                                    "Negate": {"$ref": "#/$defs/Expr", "description": ""}
+                                },
+                                "required": ["Negate"]
+                            }
+                        ]
+                    }
+                }
+            })
+        );
+
+        let order: Order = serde_json::from_value(json!({
+            "shipping": {"city": "Oslo"},
+            "billing": null,
+            "total": {"Negate": {"Number": 4.5}}
+        }))
+        .unwrap();
+        assert_eq!(order.shipping.city, "Oslo");
+        assert!(matches!(order.total, Expr::Negate(_)));
+    }
+
+    #[test]
+    fn test_types_only_used_inside_a_shared_type_stay_inline() {
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        struct Country {
+            code: String,
+        }
+
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        struct Address {
+            city: String,
+            country: Country,
+        }
+
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        struct Order {
+            shipping: Address,
+            billing: Address,
+        }
+
+        let parsed_model = match Order::get_info() {
+            InstructMacroResult::Struct(info) => info,
+            _ => panic!("Expected StructInfo but got a different InstructMacroResult variant"),
+        };
+
+        assert_eq!(
+            get_response_model(parsed_model).unwrap(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "shipping": {"$ref": "#/$defs/Address", "description": ""},
+                    "billing": {"$ref": "#/$defs/Address", "description": ""}
+                },
+                "required": ["shipping", "billing"],
+                "$defs": {
+                    "Address": {
+                        "type": "object",
+                        "properties": {
+                            "city": {"type": "string", "description": ""},
+                            "country": {
+                                "type": "object",
+                                "description": "",
+                                "properties": {"code": {"type": "string", "description": ""}},
+                                "required": ["code"]
+                            }
+                        },
+                        "required": ["city", "country"]
+                    }
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_schema_follows_serde_attributes() {
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        #[serde(rename_all = "camelCase", deny_unknown_fields)]
+        struct Signup {
+            first_name: String,
+            #[serde(rename = "mail")]
+            email: String,
+            #[serde(default)]
+            newsletter: bool,
+            #[serde(skip)]
+            session: Option<String>,
+        }
+
+        let parsed_model = match Signup::get_info() {
+            InstructMacroResult::Struct(info) => info,
+            _ => panic!("Expected StructInfo but got a different InstructMacroResult variant"),
+        };
+
+        assert_eq!(
+            get_response_model(parsed_model).unwrap(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "firstName": {"type": "string", "description": ""},
+                    "mail": {"type": "string", "description": ""},
+                    "newsletter": {"type": "boolean", "description": ""}
+                },
+                "additionalProperties": false,
+                "required": ["firstName", "mail"]
+            })
+        );
+
+        let signup: Signup =
+            serde_json::from_value(json!({"firstName": "Ada", "mail": "ada@example.com"})).unwrap();
+        assert_eq!(signup.first_name, "Ada");
+        assert!(!signup.newsletter);
+        assert!(signup.session.is_none());
+    }
+
+    #[test]
+    fn test_enum_variants_with_descriptions() {
+        #[derive(InstructMacro, Debug, Serialize, Deserialize, PartialEq)]
+        #[serde(rename_all = "snake_case")]
+        enum Status {
+            #[description("Waiting for someone to pick it up")]
+            Pending,
+            #[description("Paused until the customer replies")]
+            OnHold,
+            Closed,
+        }
+
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        struct Ticket {
+            status: Status,
+        }
+
+        let parsed_model = match Ticket::get_info() {
+            InstructMacroResult::Struct(info) => info,
+            _ => panic!("Expected StructInfo but got a different InstructMacroResult variant"),
+        };
+
+        assert_eq!(
+            get_response_model(parsed_model).unwrap(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "status": {
+                        "type": "string",
+                        "description": "",
+                        "oneOf": [
+                            {"const": "pending", "description": "Waiting for someone to pick it up"},
+                            {"const": "on_hold", "description": "Paused until the customer replies"},
+                            {"const": "closed"}
+                        ]
+                    }
+                },
+                "required": ["status"]
+            })
+        );
+
+        let ticket: Ticket = serde_json::from_value(json!({"status": "on_hold"})).unwrap();
+        assert_eq!(ticket.status, Status::OnHold);
+    }
+
+    #[test]
+    fn test_validators_constrain_the_schema() {
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        struct Signup {
+            #[validate(length(min = 2, max = 20), regex = "^[a-z0-9_]+$")]
+            username: String,
+            #[validate(email)]
+            email: String,
+            #[validate(range(min = 13))]
+            age: u8,
+            #[validate(non_empty, url)]
+            links: Vec<String>,
+        }
+
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        struct Profile {
+            #[validate(contains = "(at)")]
+            handle: Option<String>,
+            #[validate(range(min = 0.5, max = 2.5), length(max = 2))]
+            scores: Vec<Vec<f64>>,
+        }
+
+        let parsed_model = match Signup::get_info() {
+            InstructMacroResult::Struct(info) => info,
+            _ => panic!("Expected StructInfo but got a different InstructMacroResult variant"),
+        };
+        assert_eq!(
+            get_response_model(parsed_model).unwrap(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "username": {
+                        "type": "string",
+                        "description": "",
+                        "minLength": 2,
+                        "maxLength": 20,
+                        "pattern": "^[a-z0-9_]+$"
+                    },
+                    "email": {"type": "string", "description": "", "format": "email"},
+                    "age": {"type": "integer", "description": "", "minimum": 13, "maximum": 255},
+                    "links": {
+                        "type": "array",
+                        "description": "",
+                        "items": {"type": "string", "format": "uri"},
+                        "minItems": 1
+                    }
+                },
+                "required": ["username", "email", "age", "links"]
+            })
+        );
+
+        let parsed_model = match Profile::get_info() {
+            InstructMacroResult::Struct(info) => info,
+            _ => panic!("Expected StructInfo but got a different InstructMacroResult variant"),
+        };
+        assert_eq!(
+            get_response_model(parsed_model).unwrap(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "handle": {
+                        "type": "string",
+                        "description": "",
+                        "pattern": "\\(at\\)"
+                    },
+                    "scores": {
+                        "type": "array",
+                        "description": "",
+                        "items": {
+                            "type": "array",
+                            "items": {"type": "number", "minimum": 0.5, "maximum": 2.5},
+                            "description": ""
+                        },
+                        "maxItems": 2
+                    }
+                },
+                "required": ["scores"]
+            })
+        );
     }
 }
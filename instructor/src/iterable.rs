@@ -0,0 +1,275 @@
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+
+use instruct_macros_types::{InstructMacro, Parameter, StructInfo};
+use serde_json::Value;
+
+use crate::backends::ChunkStream;
+use crate::partial::parse_partial_json;
+use crate::{mode, InstructorClient, InstructorError, Mode};
+
+/// The field of the wrapper model that holds the extracted items.
+const ITEMS_FIELD: &str = "tasks";
+
+/// Wraps a response model in `{"tasks": [T]}`, so that the model can answer with any
+/// number of instances.
+pub(crate) fn iterable_model(item: StructInfo) -> StructInfo {
+    StructInfo {
+        name: format!("Iterable{}", item.name),
+        r#type: format!("Iterable{}", item.name),
+        description: format!("Correctly segmented list of `{}` tasks", item.name),
+        parameters: vec![Parameter::Struct(StructInfo {
+            name: ITEMS_FIELD.to_string(),
+            is_optional: false,
+            is_list: true,
+            ..item
+        })],
+        deny_unknown_fields: false,
+        is_optional: false,
+        is_list: false,
+    }
+}
+
+/// The answer to [`InstructorClient::chat_completion_iterable`].
+///
+/// Yields every extracted item as soon as the model has finished writing it. Each
+/// item is parsed and validated on its own, so an item that fails shows up as an
+/// `Err` and the items after it still come through.
+pub struct IterableStream<T> {
+    chunks: ChunkStream,
+    mode: Mode,
+    buffer: String,
+    yielded: usize,
+    pending: VecDeque<Value>,
+    done: bool,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> IterableStream<T>
+where
+    T: InstructMacro + for<'de> serde::Deserialize<'de>,
+{
+    pub(crate) fn new(chunks: ChunkStream, mode: Mode) -> Self {
+        Self {
+            chunks,
+            mode,
+            buffer: String::new(),
+            yielded: 0,
+            pending: VecDeque::new(),
+            done: false,
+            marker: PhantomData,
+        }
+    }
+
+    /// Queues the items that are known to be complete. An item is complete once the
+    /// next one has started, or once the whole answer has arrived.
+    fn queue_items(&mut self, items: &[Value], complete: bool) {
+        let ready = if complete {
+            items.len()
+        } else {
+            items.len().saturating_sub(1)
+        };
+        if ready > self.yielded {
+            self.pending
+                .extend(items[self.yielded..ready].iter().cloned());
+            self.yielded = ready;
+        }
+    }
+
+    fn json_text(&self) -> Option<&str> {
+        match self.mode {
+            Mode::MarkdownJson => mode::partial_json_block(&self.buffer),
+            _ => Some(&self.buffer),
+        }
+    }
+
+    /// Reads the answer once the stream has ended and queues the items that are left.
+    fn finish_answer(&mut self) -> Result<(), InstructorError> {
+        let content = match self.mode {
+            Mode::MarkdownJson => mode::extract_json_block(&self.buffer).ok_or_else(|| {
+                InstructorError::Deserialization {
+                    message: "No ```json code block found in the response".to_string(),
+                    content: self.buffer.clone(),
+                }
+            })?,
+            _ => &self.buffer,
+        };
+        let answer: Value = InstructorClient::parse_json(content)?;
+        match answer.get(ITEMS_FIELD).and_then(Value::as_array) {
+            Some(items) => {
+                let items = items.clone();
+                self.queue_items(&items, true);
+                Ok(())
+            }
+            None => Err(InstructorError::Deserialization {
+                message: format!("Expected a `{}` list in the response", ITEMS_FIELD),
+                content: content.to_string(),
+            }),
+        }
+    }
+
+    fn item(value: Value) -> Result<T, InstructorError> {
+        let content = value.to_string();
+        serde_json::from_value(value)
+            .map_err(|e| InstructorError::Deserialization {
+                message: e.to_string(),
+                content,
+            })
+            .and_then(InstructorClient::validate_result)
+    }
+}
+
+impl<T> Iterator for IterableStream<T>
+where
+    T: InstructMacro + for<'de> serde::Deserialize<'de>,
+{
+    type Item = Result<T, InstructorError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.pending.pop_front() {
+                return Some(Self::item(item));
+            }
+            if self.done {
+                return None;
+            }
+
+            match self.chunks.next() {
+                Some(Ok(chunk)) => self.buffer.push_str(&chunk),
+                Some(Err(error)) => {
+                    self.done = true;
+                    return Some(Err(error));
+                }
+                None => {
+                    self.done = true;
+                    if let Err(error) = self.finish_answer() {
+                        return Some(Err(error));
+                    }
+                    continue;
+                }
+            }
+
+            let mut answer = self.json_text().and_then(parse_partial_json);
+            let items = answer
+                .as_mut()
+                .and_then(|answer| answer.get_mut(ITEMS_FIELD))
+                .map(Value::take);
+            if let Some(Value::Array(items)) = items {
+                self.queue_items(&items, false);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use instruct_macros::{validate, InstructMacro};
+    use instruct_macros_types::ParameterInfo;
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(InstructMacro, Debug, Deserialize, PartialEq)]
+    struct User {
+        #[validate(custom = "validate_name")]
+        name: String,
+    }
+
+    #[validate]
+    fn validate_name(name: &str) -> Result<String, String> {
+        match name.is_empty() {
+            true => Err("Name should not be empty".to_string()),
+            false => Ok(name.to_string()),
+        }
+    }
+
+    /// Streams `chunks` and counts how many of them have been read.
+    fn counted(chunks: &[&str]) -> (ChunkStream, Arc<AtomicUsize>) {
+        let read = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&read);
+        let chunks: Vec<String> = chunks.iter().map(|chunk| chunk.to_string()).collect();
+        let stream = chunks.into_iter().map(move |chunk| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Ok(chunk)
+        });
+        (Box::new(stream), read)
+    }
+
+    #[test]
+    fn test_items_are_yielded_as_they_complete() {
+        let (chunks, read) = counted(&[
+            r#"{"tasks": [{"name": "Jo"#,
+            r#"hn"}, {"na"#,
+            r#"me": ""}, {"name": "#,
+            r#""Ann"}]}"#,
+        ]);
+        let mut stream = IterableStream::<User>::new(chunks, Mode::Tools);
+
+        assert_eq!(
+            stream.next().unwrap().unwrap(),
+            User {
+                name: "John".to_string()
+            }
+        );
+        assert_eq!(read.load(Ordering::SeqCst), 2);
+
+        assert_eq!(
+            stream.next().unwrap().unwrap_err(),
+            InstructorError::Validation(
+                "Validation failed for field 'name': Name should not be empty".to_string()
+            )
+        );
+        assert_eq!(read.load(Ordering::SeqCst), 3);
+
+        assert_eq!(stream.next().unwrap().unwrap().name, "Ann");
+        assert!(stream.next().is_none());
+    }
+
+    #[test]
+    fn test_truncated_answer_keeps_earlier_items() {
+        let (chunks, _) = counted(&[r#"{"tasks": [{"name": "John"}, {"name": "An"#]);
+        let mut stream = IterableStream::<User>::new(chunks, Mode::Json);
+
+        assert_eq!(stream.next().unwrap().unwrap().name, "John");
+        assert!(matches!(
+            stream.next(),
+            Some(Err(InstructorError::Deserialization { .. }))
+        ));
+        assert!(stream.next().is_none());
+    }
+
+    #[test]
+    fn test_iterable_model() {
+        let item = StructInfo {
+            name: "User".to_string(),
+            r#type: "User".to_string(),
+            description: "A user".to_string(),
+            parameters: vec![Parameter::Field(ParameterInfo {
+                name: "name".to_string(),
+                r#type: "String".to_string(),
+                comment: "".to_string(),
+                constraints: vec![],
+                is_optional: false,
+                is_list: false,
+            })],
+            deny_unknown_fields: false,
+            is_optional: false,
+            is_list: false,
+        };
+
+        let model = iterable_model(item.clone());
+
+        assert_eq!(model.name, "IterableUser");
+        assert_eq!(
+            model.parameters,
+            vec![Parameter::Struct(StructInfo {
+                name: "tasks".to_string(),
+                is_list: true,
+                ..item
+            })]
+        );
+    }
+}
@@ -1,142 +1,450 @@
-use std::vec;
+use std::sync::Arc;
+pub mod backends;
+mod error;
 mod helpers;
-use openai_api_rs::v1::{
-    api::Client,
-    chat_completion::{self, ChatCompletionRequest},
-    error::APIError,
+mod iterable;
+mod mode;
+mod partial;
+mod retry;
+use openai_api_rs::v1::{api::Client, chat_completion::ChatCompletionRequest};
+
+use backends::{
+    AnthropicBackend, Backend, BackendResponse, ChunkStream, CohereBackend, GeminiBackend,
+    LlamaCppBackend, MistralBackend, OllamaBackend, OpenAIBackend, Reask,
+};
+pub use error::{Attempt, InstructorError};
+pub use helpers::{get_gemini_response_model, get_response_model};
+use instruct_macros_types::{
+    InstructMacro, InstructMacroResult, ParallelTools, Parameter, StructInfo,
 };
+pub use iterable::IterableStream;
+pub use mode::Mode;
+pub use partial::{Partial, PartialStream};
+pub use retry::RetryPolicy;
+use retry::{NextAttempt, RetryState};
 
-use instruct_macros_types::{InstructMacro, InstructMacroResult, StructInfo};
+/// The message sent back to the model after a rejected answer. `{error}` is replaced
+/// with the reason the answer was rejected.
+pub const DEFAULT_REASK_TEMPLATE: &str = "Validation Error: {error}. Please fix the issue";
 
+/// Cloning is cheap and shares the backend, so a clone can be reconfigured for a single
+/// call, e.g. `client.clone().with_mode(Mode::Json)`.
+#[derive(Clone)]
 pub struct InstructorClient {
-    client: Client,
+    backend: Arc<dyn Backend>,
+    mode: Mode,
+    reask_template: String,
 }
 
 impl InstructorClient {
     pub fn new(client: Client) -> Self {
-        Self { client }
+        Self::from_backend(OpenAIBackend::new(client))
+    }
+
+    /// Creates a client that talks to the model through any [`Backend`] implementation.
+    pub fn from_backend<B: Backend + 'static>(backend: B) -> Self {
+        Self {
+            backend: Arc::new(backend),
+            mode: Mode::default(),
+            reask_template: DEFAULT_REASK_TEMPLATE.to_string(),
+        }
+    }
+
+    /// Sets how the response model is requested from the model. Defaults to
+    /// [`Mode::Tools`].
+    pub fn with_mode(mut self, mode: Mode) -> Self {
+        self.mode = mode;
+        self
     }
 
+    /// Replaces the message sent back to the model after a rejected answer. Every
+    /// `{error}` in `template` is replaced with the reason the answer was rejected.
+    pub fn with_reask_template(mut self, template: impl Into<String>) -> Self {
+        self.reask_template = template.into();
+        self
+    }
+
+    /// Asks the model for a `T`, allowing up to `max_retries` answers in total before
+    /// giving up with [`InstructorError::RetriesExhausted`]. An answer that fails to
+    /// parse or validate is sent back to the model along with the error.
+    ///
+    /// This uses [`RetryPolicy::with_max_retries`], so on top of the re-asks, transport
+    /// errors, rate limits and 5xx responses are retried up to 3 times with jittered
+    /// exponential backoff. The model always gets at least one call, even when
+    /// `max_retries` is 0. Use [`InstructorClient::chat_completion_with_policy`] to
+    /// change either.
     pub fn chat_completion<T>(
         &self,
         req: ChatCompletionRequest,
         max_retries: u8,
-    ) -> Result<T, APIError>
+    ) -> Result<T, InstructorError>
     where
         T: InstructMacro + for<'de> serde::Deserialize<'de>,
     {
-        let parsed_model: StructInfo = match T::get_info() {
-            InstructMacroResult::Struct(info) => info,
-            _ => {
-                panic!("Expected StructInfo but got a different InstructMacroResult variant");
-            }
-        };
-        let mut error_message: Option<String> = None;
+        self.chat_completion_with_policy(req, &RetryPolicy::with_max_retries(max_retries))
+    }
 
-        let mut req = req.clone();
-        req.tool_choice = Some(chat_completion::ToolChoiceType::Auto);
+    /// Like [`InstructorClient::chat_completion`], with separate budgets for transport
+    /// retries and validation re-asks.
+    pub fn chat_completion_with_policy<T>(
+        &self,
+        req: ChatCompletionRequest,
+        policy: &RetryPolicy,
+    ) -> Result<T, InstructorError>
+    where
+        T: InstructMacro + for<'de> serde::Deserialize<'de>,
+    {
+        let parsed_model = Self::parsed_model::<T>()?;
+        self.retry(
+            policy,
+            |reasks| self.backend.chat(&req, &parsed_model, self.mode, reasks),
+            |response| {
+                Self::parse_response::<T>(response, self.mode).and_then(Self::validate_result)
+            },
+        )
+    }
 
-        for _ in 0..max_retries {
-            let mut req = req.clone();
+    /// Lets the model call several tools in one answer, one per response model in `T`.
+    ///
+    /// `T` is usually an enum deriving `ParallelTools` whose variants each wrap a
+    /// response model. Every tool call is parsed into its variant and validated, and
+    /// the model is re-asked if any of them fails. Tools are used whatever the
+    /// client's mode, and only backends that implement [`Backend::chat_parallel`]
+    /// support this.
+    pub fn chat_completion_parallel<T: ParallelTools>(
+        &self,
+        req: ChatCompletionRequest,
+        max_retries: u8,
+    ) -> Result<Vec<T>, InstructorError> {
+        self.chat_completion_parallel_with_policy(req, &RetryPolicy::with_max_retries(max_retries))
+    }
 
-            if let Some(ref error) = error_message {
-                let new_message = chat_completion::ChatCompletionMessage {
-                    role: chat_completion::MessageRole::user,
-                    content: chat_completion::Content::Text(error.clone()),
-                    name: None,
-                };
-                req.messages.push(new_message);
-            }
+    /// Like [`InstructorClient::chat_completion_parallel`], with separate budgets for
+    /// transport retries and validation re-asks.
+    pub fn chat_completion_parallel_with_policy<T: ParallelTools>(
+        &self,
+        req: ChatCompletionRequest,
+        policy: &RetryPolicy,
+    ) -> Result<Vec<T>, InstructorError> {
+        let tools = T::get_tools()
+            .into_iter()
+            .map(|tool| match tool {
+                Parameter::Struct(info) => Ok(info),
+                _ => Err(InstructorError::ResponseModel(
+                    "Every parallel tool must be a struct".to_string(),
+                )),
+            })
+            .collect::<Result<Vec<StructInfo>, InstructorError>>()?;
 
-            let result = self._retry_sync::<T>(req.clone(), parsed_model.clone());
+        self.retry(
+            policy,
+            |reasks| self.backend.chat_parallel(&req, &tools, reasks),
+            Self::parse_tool_calls,
+        )
+    }
 
-            match result {
-                Ok(value) => {
-                    match T::validate(&value) {
-                        Ok(_) => {}
-                        Err(e) => {
-                            error_message =
-                                Some(format!("Validation Error: {:?}. Please fix the issue", e));
-                            continue;
-                        }
-                    }
+    /// Streams the response model while the model is still generating it.
+    ///
+    /// The returned [`PartialStream`] yields a [`Partial`] every time more of the
+    /// answer has arrived, and validates the complete answer once it has all been
+    /// received. Streams are not retried or re-asked, and only backends that implement
+    /// [`Backend::chat_stream`] support them.
+    pub fn chat_completion_stream<T>(
+        &self,
+        req: ChatCompletionRequest,
+    ) -> Result<PartialStream<T>, InstructorError>
+    where
+        T: InstructMacro + for<'de> serde::Deserialize<'de>,
+        T::Partial: serde::de::DeserializeOwned,
+    {
+        let parsed_model = Self::parsed_model::<T>()?;
+        let chunks = self.backend.chat_stream(&req, &parsed_model, self.mode)?;
+        Ok(PartialStream::new(chunks, self.mode))
+    }
 
-                    return Ok(value);
-                }
-                Err(e) => {
-                    error_message =
-                        Some(format!("Validation Error: {:?}. Please fix the issue", e));
-                    continue;
+    /// Extracts every instance of `T` the model finds, as an iterator.
+    ///
+    /// The model is asked for a `{"tasks": [T]}` object and each item is yielded as
+    /// soon as it has been generated and validated on its own. Backends that can't
+    /// stream answer in one go, and their items are yielded once the answer is
+    /// complete. Like streams, iterable extraction is not retried or re-asked.
+    pub fn chat_completion_iterable<T>(
+        &self,
+        req: ChatCompletionRequest,
+    ) -> Result<IterableStream<T>, InstructorError>
+    where
+        T: InstructMacro + for<'de> serde::Deserialize<'de>,
+    {
+        let parsed_model = iterable::iterable_model(Self::parsed_model::<T>()?);
+        let (chunks, mode): (ChunkStream, Mode) =
+            match self.backend.chat_stream(&req, &parsed_model, self.mode) {
+                Err(InstructorError::StreamingUnsupported) => {
+                    let response = self.backend.chat(&req, &parsed_model, self.mode, &[])?;
+                    let content = Self::response_json(&response, self.mode)?.to_string();
+                    // The JSON has already been taken out of any Markdown around it
+                    (Box::new(std::iter::once(Ok(content))), Mode::Json)
                 }
-            }
-        }
+                chunks => (chunks?, self.mode),
+            };
+        Ok(IterableStream::new(chunks, mode))
+    }
 
-        Err(APIError {
-            message: format!("Unable to derive model: {:?}", error_message),
-        })
+    /// Async counterpart of [`InstructorClient::chat_completion`].
+    ///
+    /// The retry and validation loop is identical to the sync path. Requests are sent
+    /// with [`Backend::chat_async`] and transport retries wait with
+    /// `tokio::time::sleep`, so the calling task never blocks and dropping the future
+    /// cancels the request in flight. Backends that don't implement `chat_async` fall
+    /// back to running `chat` on tokio's blocking thread pool.
+    #[cfg(feature = "async")]
+    pub async fn chat_completion_async<T>(
+        &self,
+        req: ChatCompletionRequest,
+        max_retries: u8,
+    ) -> Result<T, InstructorError>
+    where
+        T: InstructMacro + for<'de> serde::Deserialize<'de>,
+    {
+        self.chat_completion_with_policy_async(req, &RetryPolicy::with_max_retries(max_retries))
+            .await
     }
 
-    fn _retry_sync<T>(
+    /// Async counterpart of [`InstructorClient::chat_completion_with_policy`].
+    #[cfg(feature = "async")]
+    pub async fn chat_completion_with_policy_async<T>(
         &self,
         req: ChatCompletionRequest,
-        parsed_model: StructInfo,
-    ) -> Result<T, serde_json::Error>
+        policy: &RetryPolicy,
+    ) -> Result<T, InstructorError>
     where
         T: InstructMacro + for<'de> serde::Deserialize<'de>,
     {
-        let func_call = chat_completion::Tool {
-            r#type: chat_completion::ToolType::Function,
-            function: chat_completion::Function {
-                name: parsed_model.name.clone(),
-                description: Some(parsed_model.description.clone()),
-                parameters: helpers::get_response_model(parsed_model.clone()),
-            },
+        let parsed_model = Self::parsed_model::<T>()?;
+        let mut state = RetryState::new(policy);
+
+        loop {
+            let response = match self
+                .backend
+                .chat_async(&req, &parsed_model, self.mode, state.reasks())
+                .await
+            {
+                Err(InstructorError::AsyncUnsupported) => {
+                    self.chat_blocking(&req, &parsed_model, state.reasks())
+                        .await
+                }
+                response => response,
+            };
+
+            let parse = |response: &BackendResponse| {
+                Self::parse_response::<T>(response, self.mode).and_then(Self::validate_result)
+            };
+            match self.handle_attempt(response, parse, &mut state)? {
+                Ok(value) => return Ok(value),
+                Err(NextAttempt::Wait(delay)) => tokio::time::sleep(delay).await,
+                Err(NextAttempt::Reask) => {}
+            }
+        }
+    }
+
+    /// Runs the blocking [`Backend::chat`] on tokio's blocking thread pool, for
+    /// backends without an async transport. The call can't be cancelled, so it runs
+    /// to completion even if the future is dropped.
+    #[cfg(feature = "async")]
+    async fn chat_blocking(
+        &self,
+        req: &ChatCompletionRequest,
+        response_model: &StructInfo,
+        reasks: &[Reask],
+    ) -> Result<BackendResponse, InstructorError> {
+        let (backend, mode) = (Arc::clone(&self.backend), self.mode);
+        let (req, response_model, reasks) = (req.clone(), response_model.clone(), reasks.to_vec());
+        tokio::task::spawn_blocking(move || backend.chat(&req, &response_model, mode, &reasks))
+            .await
+            .unwrap_or_else(|e| {
+                Err(InstructorError::Transport(format!(
+                    "Chat completion task failed to complete: {}",
+                    e
+                )))
+            })
+    }
+
+    fn parsed_model<T: InstructMacro>() -> Result<StructInfo, InstructorError> {
+        match T::get_info() {
+            InstructMacroResult::Struct(info) => Ok(info),
+            _ => Err(InstructorError::ResponseModel(
+                "Expected StructInfo but got a different InstructMacroResult variant".to_string(),
+            )),
+        }
+    }
+
+    /// Sends the request until an answer passes `parse`, within the budgets of `policy`.
+    fn retry<R>(
+        &self,
+        policy: &RetryPolicy,
+        call: impl Fn(&[Reask]) -> Result<BackendResponse, InstructorError>,
+        parse: impl Fn(&BackendResponse) -> Result<R, InstructorError>,
+    ) -> Result<R, InstructorError> {
+        let mut state = RetryState::new(policy);
+
+        loop {
+            let response = call(state.reasks());
+
+            match self.handle_attempt(response, &parse, &mut state)? {
+                Ok(value) => return Ok(value),
+                Err(NextAttempt::Wait(delay)) => std::thread::sleep(delay),
+                Err(NextAttempt::Reask) => {}
+            }
+        }
+    }
+
+    /// Parses and validates one backend call.
+    ///
+    /// Returns the value once it passes validation, otherwise records the failure and
+    /// tells the caller how to go on. Errors that can't be retried and exhausted
+    /// budgets end the call.
+    fn handle_attempt<R>(
+        &self,
+        response: Result<BackendResponse, InstructorError>,
+        parse: impl Fn(&BackendResponse) -> Result<R, InstructorError>,
+        state: &mut RetryState,
+    ) -> Result<Result<R, NextAttempt>, InstructorError> {
+        let response = match response {
+            Ok(response) => response,
+            Err(error) => return state.transport_failed(error).map(Err),
         };
 
-        let req = req
-            .tools(vec![func_call])
-            .tool_choice(chat_completion::ToolChoiceType::Auto);
+        let error = match parse(&response) {
+            Ok(value) => return Ok(Ok(value)),
+            Err(error) => error,
+        };
 
-        let result = self.client.chat_completion(req).unwrap();
+        let message = self.reask_template.replace("{error}", &error.to_string());
+        state.rejected(response, error, message).map(Err)
+    }
 
-        match result.choices[0].finish_reason {
-            Some(chat_completion::FinishReason::tool_calls) => {
-                // TODO: Support more than one tool at some point?
-                let tool_calls = result.choices[0].message.tool_calls.as_ref().unwrap();
+    /// Parses and validates every call of a parallel tool answer. When several calls
+    /// fail, their errors are combined so the model can fix them all at once.
+    fn parse_tool_calls<T: ParallelTools>(
+        response: &BackendResponse,
+    ) -> Result<Vec<T>, InstructorError> {
+        let tool_calls = match response {
+            BackendResponse::ToolCalls(tool_calls) => tool_calls,
+            BackendResponse::Json(text) | BackendResponse::Text(text) => {
+                return Err(InstructorError::NoToolCall(text.clone()))
+            }
+        };
 
+        let mut values = Vec::new();
+        let mut errors = Vec::new();
+        for tool_call in tool_calls {
+            let value = T::from_tool_call(&tool_call.name, &tool_call.arguments)
+                .map_err(|message| InstructorError::Deserialization {
+                    message,
+                    content: tool_call.arguments.clone(),
+                })
+                .and_then(|value| match value.validate() {
+                    Ok(_) => Ok(value),
+                    Err(e) => Err(InstructorError::Validation(e)),
+                });
+            match value {
+                Ok(value) => values.push(value),
+                Err(error) => errors.push((&tool_call.name, error)),
+            }
+        }
+
+        match errors.len() {
+            0 => Ok(values),
+            1 => Err(errors.remove(0).1),
+            _ => Err(InstructorError::Validation(
+                errors
+                    .iter()
+                    .map(|(name, error)| format!("{}: {}", name, error))
+                    .collect::<Vec<String>>()
+                    .join("\n"),
+            )),
+        }
+    }
+
+    /// Runs the model's own validators on a parsed response.
+    fn validate_result<T: InstructMacro>(value: T) -> Result<T, InstructorError> {
+        match T::validate(&value) {
+            Ok(_) => Ok(value),
+            Err(e) => Err(InstructorError::Validation(e)),
+        }
+    }
+
+    fn parse_response<T>(result: &BackendResponse, mode: Mode) -> Result<T, InstructorError>
+    where
+        T: for<'de> serde::Deserialize<'de>,
+    {
+        Self::parse_json(Self::response_json(result, mode)?)
+    }
+
+    /// Finds the JSON document holding the response model in a backend response.
+    fn response_json(result: &BackendResponse, mode: Mode) -> Result<&str, InstructorError> {
+        match result {
+            BackendResponse::ToolCalls(tool_calls) => {
+                // TODO: Support more than one tool at some point?
                 match tool_calls.len() {
-                    1 => {
-                        let tool_call = &tool_calls[0];
-                        let arguments = tool_call.function.arguments.clone().unwrap();
-                        match serde_json::from_str(&arguments) {
-                            Ok(value) => return Ok(value),
-                            Err(e) => {
-                                let tool_call_json = arguments.clone();
-                                let error_message = format!(
-                                    "Invalid Response from tool call: {:?}. Tool call: {}",
-                                    e, tool_call_json
-                                );
-                                return Err(serde::de::Error::custom(error_message));
-                            }
-                        }
-                    }
-                    _ => {
-                        // TODO: Support multiple tool calls at some point
-                        let error_message =
-                            format!("Unexpected number of tool calls: {:?}. PLease only generate a single tool call.", tool_calls);
-                        return Err(serde::de::Error::custom(error_message));
-                    }
+                    1 => Ok(&tool_calls[0].arguments),
+                    _ => Err(InstructorError::Deserialization {
+                        message: format!(
+                            "Expected a single tool call but got {}. Please only generate a single tool call",
+                            tool_calls.len()
+                        ),
+                        content: format!("{:?}", tool_calls),
+                    }),
                 }
             }
-            _ => {
-                let error_message =
-                    "Please make sure to generate a response and call a tool".to_string();
-                return Err(serde::de::Error::custom(error_message));
-            }
+            BackendResponse::Json(content) => Ok(content),
+            BackendResponse::Text(text) => match mode {
+                Mode::Tools => Err(InstructorError::NoToolCall(text.clone())),
+                Mode::Json | Mode::JsonSchema => Ok(text),
+                Mode::MarkdownJson => {
+                    mode::extract_json_block(text).ok_or_else(|| InstructorError::Deserialization {
+                        message: "No ```json code block found in the response".to_string(),
+                        content: text.clone(),
+                    })
+                }
+            },
         }
     }
+
+    fn parse_json<T>(content: &str) -> Result<T, InstructorError>
+    where
+        T: for<'de> serde::Deserialize<'de>,
+    {
+        serde_json::from_str(content).map_err(|e| InstructorError::Deserialization {
+            message: e.to_string(),
+            content: content.to_string(),
+        })
+    }
 }
 
 pub fn from_openai(client: Client) -> InstructorClient {
     InstructorClient::new(client)
 }
+
+pub fn from_anthropic(client: AnthropicBackend) -> InstructorClient {
+    InstructorClient::from_backend(client)
+}
+
+pub fn from_gemini(client: GeminiBackend) -> InstructorClient {
+    InstructorClient::from_backend(client)
+}
+
+pub fn from_mistral(client: MistralBackend) -> InstructorClient {
+    InstructorClient::from_backend(client)
+}
+
+pub fn from_cohere(client: CohereBackend) -> InstructorClient {
+    InstructorClient::from_backend(client)
+}
+
+pub fn from_ollama(client: OllamaBackend) -> InstructorClient {
+    InstructorClient::from_backend(client)
+}
+
+pub fn from_llama_cpp(client: LlamaCppBackend) -> InstructorClient {
+    InstructorClient::from_backend(client)
+}
@@ -0,0 +1,74 @@
+/// How the response model is requested from the model and read back from its answer.
+///
+/// Providers support different subsets of these; see each backend for how a mode maps
+/// onto its API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Mode {
+    /// The response model is sent as a tool and the model is forced to call it.
+    #[default]
+    Tools,
+    /// The provider's JSON mode is turned on and the schema is described in the system
+    /// prompt.
+    Json,
+    /// The schema is passed as a structured output format that the provider enforces,
+    /// with `strict: true` where supported.
+    JsonSchema,
+    /// The schema is described in the system prompt and the answer is read from a
+    /// ```` ```json ```` fenced block in the plain text reply.
+    MarkdownJson,
+}
+
+/// Returns the contents of the first ```` ```json ```` block in `text`, falling back to
+/// the first unlabelled ```` ``` ```` block.
+pub(crate) fn extract_json_block(text: &str) -> Option<&str> {
+    let rest = &text[block_start(text)?..];
+    let end = rest.find("```")?;
+    Some(rest[..end].trim())
+}
+
+/// Like [`extract_json_block`], for a reply that is still streaming in: the block may
+/// not be closed yet.
+pub(crate) fn partial_json_block(text: &str) -> Option<&str> {
+    let rest = &text[block_start(text)?..];
+    Some(rest.find("```").map_or(rest, |end| &rest[..end]))
+}
+
+fn block_start(text: &str) -> Option<usize> {
+    match text.find("```json") {
+        Some(start) => Some(start + "```json".len()),
+        None => Some(text.find("```")? + "```".len()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_json_block() {
+        let text = "Here you go:\n```json\n{\"name\": \"John\"}\n```\nAnything else?";
+        assert_eq!(extract_json_block(text), Some("{\"name\": \"John\"}"));
+
+        let text = "```\n{\"name\": \"John\"}\n```";
+        assert_eq!(extract_json_block(text), Some("{\"name\": \"John\"}"));
+
+        let text = "```python\nprint(1)\n```\n```json\n{}\n```";
+        assert_eq!(extract_json_block(text), Some("{}"));
+    }
+
+    #[test]
+    fn test_extract_json_block_without_fence() {
+        assert_eq!(extract_json_block("{\"name\": \"John\"}"), None);
+        assert_eq!(extract_json_block("```json\n{\"name\": \"John\"}"), None);
+    }
+
+    #[test]
+    fn test_partial_json_block() {
+        assert_eq!(partial_json_block("Here you go:\n"), None);
+        assert_eq!(
+            partial_json_block("Here you go:\n```json\n{\"name\": \"Jo"),
+            Some("\n{\"name\": \"Jo")
+        );
+        assert_eq!(partial_json_block("```json\n{}\n```\nDone"), Some("\n{}\n"));
+    }
+}
@@ -0,0 +1,610 @@
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::Deref;
+
+use instruct_macros_types::InstructMacro;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Number, Value};
+
+use crate::backends::ChunkStream;
+use crate::{mode, InstructorClient, InstructorError, Mode};
+
+/// A response model that is still being generated.
+///
+/// Every field is optional: a field shows up once the model has started writing its
+/// value. Strings grow as they stream in, while numbers, booleans and `null` only
+/// appear once they are complete. Nested objects and arrays are filled in the same
+/// way.
+///
+/// The fields are read through the response model's generated partial, which a
+/// `Partial` dereferences to: a `UserInfo` streams in as its
+/// `<UserInfo as InstructMacro>::Partial`, with an `Option` for every field.
+/// [`Partial::value`] is the JSON received so far.
+pub struct Partial<T: InstructMacro> {
+    value: Value,
+    partial: T::Partial,
+}
+
+impl<T: InstructMacro> Partial<T>
+where
+    T::Partial: DeserializeOwned,
+{
+    /// Reads `value` into the partial of `T`, or `None` when it can't be read as one.
+    fn new(value: Value) -> Option<Self> {
+        let partial = serde_json::from_value(value.clone()).ok()?;
+        Some(Self { value, partial })
+    }
+
+    /// The JSON received so far.
+    pub fn value(&self) -> &Value {
+        &self.value
+    }
+
+    pub fn into_value(self) -> Value {
+        self.value
+    }
+
+    /// The generated partial of the response model.
+    pub fn into_inner(self) -> T::Partial {
+        self.partial
+    }
+
+    /// Reads a field of the response model, or `None` while it hasn't arrived yet or
+    /// can't be read as a `V` so far.
+    pub fn get<V: DeserializeOwned>(&self, field: &str) -> Option<V> {
+        self.value
+            .get(field)
+            .and_then(|value| serde_json::from_value(value.clone()).ok())
+    }
+}
+
+impl<T: InstructMacro> Deref for Partial<T> {
+    type Target = T::Partial;
+
+    fn deref(&self) -> &Self::Target {
+        &self.partial
+    }
+}
+
+impl<T: InstructMacro> Clone for Partial<T>
+where
+    T::Partial: DeserializeOwned,
+{
+    fn clone(&self) -> Self {
+        // The partial is read again rather than cloned, so it doesn't have to be `Clone`
+        Self::new(self.value.clone()).expect("The same JSON is read into the same partial")
+    }
+}
+
+impl<T: InstructMacro> PartialEq for Partial<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<T: InstructMacro> fmt::Debug for Partial<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Partial").field(&self.value).finish()
+    }
+}
+
+/// The answer to [`InstructorClient::chat_completion_stream`].
+///
+/// Iterating yields a new [`Partial`] every time more of the response model has
+/// arrived. [`PartialStream::finish`] reads the rest of the stream and parses and
+/// validates the complete answer.
+///
+/// ```no_run
+/// # use instruct_macros::InstructMacro;
+/// # use instruct_macros_types::{InstructMacro, Parameter, ParameterInfo, StructInfo};
+/// # use openai_api_rs::v1::{api::Client, chat_completion::ChatCompletionRequest};
+/// # use serde::{Deserialize, Serialize};
+/// # #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+/// # struct UserInfo {
+/// #     name: String,
+/// # }
+/// # fn main() -> Result<(), instructor_ai::InstructorError> {
+/// # let client = instructor_ai::from_openai(Client::new(String::new()));
+/// # let req = ChatCompletionRequest::new(String::new(), vec![]);
+/// let mut stream = client.chat_completion_stream::<UserInfo>(req)?;
+/// for partial in stream.by_ref() {
+///     println!("{:?}", partial?.name);
+/// }
+/// let user = stream.finish()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct PartialStream<T> {
+    chunks: ChunkStream,
+    mode: Mode,
+    buffer: String,
+    last: Option<Value>,
+    done: bool,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> PartialStream<T>
+where
+    T: InstructMacro + for<'de> serde::Deserialize<'de>,
+    T::Partial: DeserializeOwned,
+{
+    pub(crate) fn new(chunks: ChunkStream, mode: Mode) -> Self {
+        Self {
+            chunks,
+            mode,
+            buffer: String::new(),
+            last: None,
+            done: false,
+            marker: PhantomData,
+        }
+    }
+
+    /// Waits for the end of the stream, then parses the complete answer and runs the
+    /// response model's validators on it.
+    pub fn finish(mut self) -> Result<T, InstructorError> {
+        for partial in self.by_ref() {
+            partial?;
+        }
+
+        let content = match self.mode {
+            Mode::MarkdownJson => mode::extract_json_block(&self.buffer).ok_or_else(|| {
+                InstructorError::Deserialization {
+                    message: "No ```json code block found in the response".to_string(),
+                    content: self.buffer.clone(),
+                }
+            })?,
+            _ => &self.buffer,
+        };
+        InstructorClient::parse_json(content).and_then(InstructorClient::validate_result)
+    }
+
+    /// The part of the buffer that holds the JSON document.
+    fn json_text(&self) -> Option<&str> {
+        match self.mode {
+            Mode::MarkdownJson => mode::partial_json_block(&self.buffer),
+            _ => Some(&self.buffer),
+        }
+    }
+}
+
+impl<T> Iterator for PartialStream<T>
+where
+    T: InstructMacro + for<'de> serde::Deserialize<'de>,
+    T::Partial: DeserializeOwned,
+{
+    type Item = Result<Partial<T>, InstructorError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            match self.chunks.next() {
+                Some(Ok(chunk)) => self.buffer.push_str(&chunk),
+                Some(Err(error)) => {
+                    self.done = true;
+                    return Some(Err(error));
+                }
+                None => {
+                    self.done = true;
+                    return None;
+                }
+            }
+
+            let Some(value) = self.json_text().and_then(parse_partial_json) else {
+                continue;
+            };
+            if self.last.as_ref() != Some(&value) {
+                self.last = Some(value.clone());
+                // JSON that doesn't have the shape of the response model is skipped
+                if let Some(partial) = Partial::new(value) {
+                    return Some(Ok(partial));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Parses a JSON document that may have been cut off at any point.
+///
+/// Open objects, arrays and strings are closed where the input ends. Keys without a
+/// value and numbers or literals that may still be incomplete are left out. Returns
+/// `None` when nothing usable has arrived yet or the input isn't JSON.
+pub(crate) fn parse_partial_json(text: &str) -> Option<Value> {
+    let mut parser = PartialParser {
+        input: text.as_bytes(),
+        pos: 0,
+    };
+    parser.skip_whitespace();
+    let value = match parser.value().ok()? {
+        Parsed::Complete(value) => {
+            parser.skip_whitespace();
+            if parser.peek().is_some() {
+                return None;
+            }
+            value
+        }
+        Parsed::Truncated(value) => value?,
+    };
+    Some(value)
+}
+
+enum Parsed {
+    Complete(Value),
+    /// The input ended inside the value, along with what could be read of it.
+    Truncated(Option<Value>),
+}
+
+/// Marks input that can never become valid JSON.
+struct Invalid;
+
+struct PartialParser<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl PartialParser<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\n' | b'\r' | b'\t')) {
+            self.pos += 1;
+        }
+    }
+
+    fn value(&mut self) -> Result<Parsed, Invalid> {
+        match self.peek() {
+            None => Ok(Parsed::Truncated(None)),
+            Some(b'{') => self.object(),
+            Some(b'[') => self.array(),
+            Some(b'"') => Ok(match self.string()? {
+                (value, true) => Parsed::Complete(Value::String(value)),
+                (value, false) => Parsed::Truncated(Some(Value::String(value))),
+            }),
+            Some(b't') => self.literal("true", Value::Bool(true)),
+            Some(b'f') => self.literal("false", Value::Bool(false)),
+            Some(b'n') => self.literal("null", Value::Null),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(_) => Err(Invalid),
+        }
+    }
+
+    fn object(&mut self) -> Result<Parsed, Invalid> {
+        self.pos += 1;
+        let mut object = Map::new();
+
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                None => return Ok(Parsed::Truncated(Some(Value::Object(object)))),
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(Parsed::Complete(Value::Object(object)));
+                }
+                Some(b'"') => {}
+                Some(_) => return Err(Invalid),
+            }
+
+            let (key, complete) = self.string()?;
+            self.skip_whitespace();
+            if !complete || self.peek().is_none() {
+                return Ok(Parsed::Truncated(Some(Value::Object(object))));
+            }
+            if self.peek() != Some(b':') {
+                return Err(Invalid);
+            }
+            self.pos += 1;
+            self.skip_whitespace();
+
+            match self.value()? {
+                Parsed::Complete(value) => {
+                    object.insert(key, value);
+                }
+                Parsed::Truncated(value) => {
+                    if let Some(value) = value {
+                        object.insert(key, value);
+                    }
+                    return Ok(Parsed::Truncated(Some(Value::Object(object))));
+                }
+            }
+
+            self.skip_whitespace();
+            match self.peek() {
+                None => return Ok(Parsed::Truncated(Some(Value::Object(object)))),
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(Parsed::Complete(Value::Object(object)));
+                }
+                Some(_) => return Err(Invalid),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Parsed, Invalid> {
+        self.pos += 1;
+        let mut array = Vec::new();
+
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                None => return Ok(Parsed::Truncated(Some(Value::Array(array)))),
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(Parsed::Complete(Value::Array(array)));
+                }
+                Some(_) => {}
+            }
+
+            match self.value()? {
+                Parsed::Complete(value) => array.push(value),
+                Parsed::Truncated(value) => {
+                    array.extend(value);
+                    return Ok(Parsed::Truncated(Some(Value::Array(array))));
+                }
+            }
+
+            self.skip_whitespace();
+            match self.peek() {
+                None => return Ok(Parsed::Truncated(Some(Value::Array(array)))),
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(Parsed::Complete(Value::Array(array)));
+                }
+                Some(_) => return Err(Invalid),
+            }
+        }
+    }
+
+    /// Reads a string starting at its opening quote. The flag is false when the input
+    /// ended before the closing quote.
+    fn string(&mut self) -> Result<(String, bool), Invalid> {
+        self.pos += 1;
+        let mut bytes = Vec::new();
+
+        let complete = loop {
+            let Some(byte) = self.peek() else {
+                break false;
+            };
+            self.pos += 1;
+            match byte {
+                b'"' => break true,
+                b'\\' => {
+                    let Some(escape) = self.peek() else {
+                        break false;
+                    };
+                    self.pos += 1;
+                    let unescaped = match escape {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => match self.unicode_escape()? {
+                            Some(c) => c,
+                            None => break false,
+                        },
+                        _ => return Err(Invalid),
+                    };
+                    let mut buf = [0; 4];
+                    bytes.extend_from_slice(unescaped.encode_utf8(&mut buf).as_bytes());
+                }
+                _ => bytes.push(byte),
+            }
+        };
+
+        String::from_utf8(bytes)
+            .map(|value| (value, complete))
+            .map_err(|_| Invalid)
+    }
+
+    /// Reads the code point of a `\u` escape, including the low half of a surrogate
+    /// pair. Returns `None` when the input ends inside the escape.
+    fn unicode_escape(&mut self) -> Result<Option<char>, Invalid> {
+        let Some(high) = self.hex4()? else {
+            return Ok(None);
+        };
+        if !(0xD800..0xDC00).contains(&high) {
+            return char::from_u32(high).map(Some).ok_or(Invalid);
+        }
+
+        let rest = &self.input[self.pos..];
+        if rest.len() < 2 && b"\\u".starts_with(rest) {
+            self.pos = self.input.len();
+            return Ok(None);
+        }
+        if !rest.starts_with(b"\\u") {
+            return Err(Invalid);
+        }
+        self.pos += 2;
+        let Some(low) = self.hex4()? else {
+            return Ok(None);
+        };
+        if !(0xDC00..0xE000).contains(&low) {
+            return Err(Invalid);
+        }
+        let code = 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00);
+        char::from_u32(code).map(Some).ok_or(Invalid)
+    }
+
+    fn hex4(&mut self) -> Result<Option<u32>, Invalid> {
+        let digits = &self.input[self.pos..(self.pos + 4).min(self.input.len())];
+        if !digits.iter().all(u8::is_ascii_hexdigit) {
+            return Err(Invalid);
+        }
+        if digits.len() < 4 {
+            self.pos = self.input.len();
+            return Ok(None);
+        }
+        self.pos += 4;
+        let digits = std::str::from_utf8(digits).map_err(|_| Invalid)?;
+        u32::from_str_radix(digits, 16)
+            .map(Some)
+            .map_err(|_| Invalid)
+    }
+
+    fn literal(&mut self, literal: &str, value: Value) -> Result<Parsed, Invalid> {
+        let rest = &self.input[self.pos..];
+        if rest.starts_with(literal.as_bytes()) {
+            self.pos += literal.len();
+            Ok(Parsed::Complete(value))
+        } else if literal.as_bytes().starts_with(rest) {
+            self.pos = self.input.len();
+            Ok(Parsed::Truncated(None))
+        } else {
+            Err(Invalid)
+        }
+    }
+
+    /// Numbers at the very end of the input may still be missing digits, so they only
+    /// count once something follows them.
+    fn number(&mut self) -> Result<Parsed, Invalid> {
+        let start = self.pos;
+        while matches!(
+            self.peek(),
+            Some(b'0'..=b'9' | b'-' | b'+' | b'.' | b'e' | b'E')
+        ) {
+            self.pos += 1;
+        }
+        if self.peek().is_none() {
+            return Ok(Parsed::Truncated(None));
+        }
+
+        let digits = std::str::from_utf8(&self.input[start..self.pos]).map_err(|_| Invalid)?;
+        serde_json::from_str::<Number>(digits)
+            .map(|number| Parsed::Complete(Value::Number(number)))
+            .map_err(|_| Invalid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use instruct_macros::InstructMacro;
+    use instruct_macros_types::{Parameter, ParameterInfo, StructInfo};
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use super::*;
+
+    #[derive(InstructMacro, Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Address {
+        city: String,
+    }
+
+    #[derive(InstructMacro, Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct User {
+        name: String,
+        tags: Vec<String>,
+        age: u8,
+        address: Option<Address>,
+    }
+
+    #[test]
+    fn test_parse_complete_json() {
+        let text = r#"{"name": "John", "age": 30, "tags": ["a", "b"], "ok": true, "x": null}"#;
+        assert_eq!(
+            parse_partial_json(text),
+            Some(serde_json::from_str::<Value>(text).unwrap())
+        );
+        assert_eq!(parse_partial_json(r#"{"name": "John"} trailing"#), None);
+    }
+
+    #[test]
+    fn test_parse_truncated_json() {
+        assert_eq!(parse_partial_json(""), None);
+        assert_eq!(parse_partial_json("{"), Some(json!({})));
+        assert_eq!(parse_partial_json(r#"{"na"#), Some(json!({})));
+        assert_eq!(parse_partial_json(r#"{"name""#), Some(json!({})));
+        assert_eq!(parse_partial_json(r#"{"name": "#), Some(json!({})));
+        assert_eq!(
+            parse_partial_json(r#"{"name": "Jo"#),
+            Some(json!({"name": "Jo"}))
+        );
+        assert_eq!(
+            parse_partial_json(r#"{"name": "John", "age": 3"#),
+            Some(json!({"name": "John"}))
+        );
+        assert_eq!(
+            parse_partial_json(r#"{"name": "John", "age": 30,"#),
+            Some(json!({"name": "John", "age": 30}))
+        );
+        assert_eq!(parse_partial_json(r#"{"active": tr"#), Some(json!({})));
+        assert_eq!(
+            parse_partial_json(r#"{"tags": ["a", "b"#),
+            Some(json!({"tags": ["a", "b"]}))
+        );
+        assert_eq!(
+            parse_partial_json(r#"{"address": {"city": "Par"#),
+            Some(json!({"address": {"city": "Par"}}))
+        );
+    }
+
+    #[test]
+    fn test_parse_truncated_escapes() {
+        assert_eq!(
+            parse_partial_json(r#"{"quote": "say \"hi\""#),
+            Some(json!({"quote": "say \"hi\""}))
+        );
+        assert_eq!(
+            parse_partial_json(r#"{"line": "a\"#),
+            Some(json!({"line": "a"}))
+        );
+        assert_eq!(
+            parse_partial_json(r#"{"emoji": "😀"}"#),
+            Some(json!({"emoji": "😀"}))
+        );
+        assert_eq!(
+            parse_partial_json(r#"{"emoji": "x\ud83d\ud"#),
+            Some(json!({"emoji": "x"}))
+        );
+        assert_eq!(
+            parse_partial_json(r#"{"letter": "é"#),
+            Some(json!({"letter": "é"}))
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_json() {
+        assert_eq!(parse_partial_json("Sure! Here is"), None);
+        assert_eq!(parse_partial_json(r#"{"name" "John"}"#), None);
+        assert_eq!(parse_partial_json(r#"{"age": 3x"#), None);
+        assert_eq!(parse_partial_json(r#"[1 2]"#), None);
+    }
+
+    #[test]
+    fn test_partial_fields() {
+        let value = parse_partial_json(r#"{"name": "Jo", "tags": ["a"], "address": {"ci"#);
+        let partial = Partial::<User>::new(value.unwrap()).unwrap();
+
+        assert_eq!(partial.name, Some("Jo".to_string()));
+        assert_eq!(partial.tags, Some(vec!["a".to_string()]));
+        assert_eq!(partial.age, None);
+        assert_eq!(partial.address.as_ref().unwrap().city, None);
+
+        // A field that can't be read yet stays empty rather than failing the rest
+        let partial = Partial::<User>::new(json!({"name": "Jo", "age": "thirty"})).unwrap();
+        assert_eq!(partial.name, Some("Jo".to_string()));
+        assert_eq!(partial.age, None);
+
+        assert!(Partial::<User>::new(json!("Jo")).is_none());
+    }
+
+    #[test]
+    fn test_partial_get() {
+        let partial = Partial::<User>::new(json!({"name": "Jo", "tags": ["a"]})).unwrap();
+
+        assert_eq!(partial.get::<String>("name"), Some("Jo".to_string()));
+        assert_eq!(
+            partial.get::<Vec<String>>("tags"),
+            Some(vec!["a".to_string()])
+        );
+        assert_eq!(partial.get::<u8>("age"), None);
+        assert_eq!(partial.get::<u8>("name"), None);
+    }
+}
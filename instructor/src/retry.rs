@@ -0,0 +1,312 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::mem;
+use std::time::Duration;
+
+use crate::backends::{BackendResponse, Reask};
+use crate::{Attempt, InstructorError};
+
+/// Controls how often and how quickly a request is retried.
+///
+/// Two kinds of failure are budgeted separately:
+///
+/// - transport retries send the same conversation again after a network failure, a
+///   rate limit or a server error, waiting with exponential backoff in between
+/// - re-asks happen when the model answered but the answer failed to parse or validate.
+///   The error is appended to the conversation and the model is asked again straight away
+///
+/// ```
+/// use std::time::Duration;
+/// use instructor_ai::RetryPolicy;
+///
+/// let policy = RetryPolicy {
+///     max_reasks: 1,
+///     max_transport_retries: 5,
+///     initial_backoff: Duration::from_secs(1),
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// How many times the model is asked to fix an answer that failed validation.
+    pub max_reasks: u32,
+    /// How many times a request is sent again after a retryable transport error.
+    pub max_transport_retries: u32,
+    /// Delay before the first transport retry.
+    pub initial_backoff: Duration,
+    /// Upper bound for every delay, including the provider's `Retry-After`.
+    pub max_backoff: Duration,
+    /// Factor the delay grows by after every transport retry.
+    pub backoff_multiplier: f64,
+    /// Picks a random delay between half and all of the computed backoff, so that
+    /// clients failing together don't retry together.
+    pub jitter: bool,
+    /// Waits for the provider's `Retry-After` delay, up to `max_backoff`, instead of the
+    /// computed backoff.
+    pub respect_retry_after: bool,
+}
+
+impl RetryPolicy {
+    /// A policy that allows `max_retries` answers from the model in total, matching the
+    /// `max_retries` argument of [`InstructorClient::chat_completion`](crate::InstructorClient::chat_completion).
+    ///
+    /// A `max_retries` of 0 still allows one answer, and every other setting, including
+    /// the transport retries, comes from [`RetryPolicy::default`].
+    pub fn with_max_retries(max_retries: u8) -> Self {
+        Self {
+            max_reasks: u32::from(max_retries.saturating_sub(1)),
+            ..Default::default()
+        }
+    }
+
+    /// The delay before transport retry number `retry`, counting from zero.
+    pub fn backoff(&self, retry: u32, error: &InstructorError) -> Duration {
+        if self.respect_retry_after {
+            if let Some(retry_after) = error.retry_after() {
+                return retry_after.min(self.max_backoff);
+            }
+        }
+
+        if self.initial_backoff.is_zero() {
+            return Duration::ZERO;
+        }
+
+        let exponent = i32::try_from(retry).unwrap_or(i32::MAX);
+        let seconds =
+            self.initial_backoff.as_secs_f64() * self.backoff_multiplier.max(1.0).powi(exponent);
+        let backoff = Duration::try_from_secs_f64(seconds)
+            .unwrap_or(self.max_backoff)
+            .min(self.max_backoff);
+
+        if self.jitter {
+            let half = backoff / 2;
+            half + half.mul_f64(random_fraction())
+        } else {
+            backoff
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_reasks: 2,
+            max_transport_retries: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            backoff_multiplier: 2.0,
+            jitter: true,
+            respect_retry_after: true,
+        }
+    }
+}
+
+/// A number in `[0, 1)` that is good enough for jitter, without pulling in `rand`.
+fn random_fraction() -> f64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos(),
+    );
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// What the retry loop should do after a failed attempt.
+pub(crate) enum NextAttempt {
+    /// Send the same conversation again after waiting.
+    Wait(Duration),
+    /// Send the conversation again with the new re-ask appended.
+    Reask,
+}
+
+/// Tracks the attempts of a single call against the budgets of a [`RetryPolicy`].
+pub(crate) struct RetryState<'a> {
+    policy: &'a RetryPolicy,
+    transport_retries: u32,
+    attempts: Vec<Attempt>,
+    reasks: Vec<Reask>,
+}
+
+impl<'a> RetryState<'a> {
+    pub(crate) fn new(policy: &'a RetryPolicy) -> Self {
+        Self {
+            policy,
+            transport_retries: 0,
+            attempts: Vec::new(),
+            reasks: Vec::new(),
+        }
+    }
+
+    pub(crate) fn reasks(&self) -> &[Reask] {
+        &self.reasks
+    }
+
+    /// Records a request that failed before the model answered. Errors that are not
+    /// worth retrying are returned as they are.
+    pub(crate) fn transport_failed(
+        &mut self,
+        error: InstructorError,
+    ) -> Result<NextAttempt, InstructorError> {
+        if !error.is_retryable() {
+            return Err(error);
+        }
+
+        let delay = self.policy.backoff(self.transport_retries, &error);
+        self.attempts.push(Attempt {
+            response: None,
+            error,
+        });
+
+        if self.transport_retries >= self.policy.max_transport_retries {
+            return Err(self.exhausted());
+        }
+        self.transport_retries += 1;
+        Ok(NextAttempt::Wait(delay))
+    }
+
+    /// Records an answer that was rejected, along with the message sent back to the model.
+    pub(crate) fn rejected(
+        &mut self,
+        response: BackendResponse,
+        error: InstructorError,
+        message: String,
+    ) -> Result<NextAttempt, InstructorError> {
+        self.reasks.push(Reask {
+            response: response.clone(),
+            error: message,
+        });
+        self.attempts.push(Attempt {
+            response: Some(response),
+            error,
+        });
+
+        if self.reasks.len() as u32 > self.policy.max_reasks {
+            return Err(self.exhausted());
+        }
+        Ok(NextAttempt::Reask)
+    }
+
+    fn exhausted(&mut self) -> InstructorError {
+        InstructorError::RetriesExhausted(mem::take(&mut self.attempts))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy {
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(1),
+            jitter: false,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_exponential_backoff() {
+        let policy = policy();
+        let error = InstructorError::Transport("connection reset".to_string());
+
+        assert_eq!(policy.backoff(0, &error), Duration::from_millis(100));
+        assert_eq!(policy.backoff(1, &error), Duration::from_millis(200));
+        assert_eq!(policy.backoff(2, &error), Duration::from_millis(400));
+        assert_eq!(policy.backoff(4, &error), Duration::from_secs(1));
+        assert_eq!(policy.backoff(u32::MAX, &error), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_backoff_with_jitter() {
+        let policy = RetryPolicy {
+            jitter: true,
+            ..policy()
+        };
+        let error = InstructorError::Transport("connection reset".to_string());
+
+        for _ in 0..100 {
+            let backoff = policy.backoff(1, &error);
+            assert!(backoff >= Duration::from_millis(100));
+            assert!(backoff <= Duration::from_millis(200));
+        }
+    }
+
+    #[test]
+    fn test_backoff_respects_retry_after() {
+        let error = InstructorError::Http {
+            status: 429,
+            body: "".to_string(),
+            retry_after: Some(Duration::from_secs(7)),
+        };
+        let policy = RetryPolicy {
+            max_backoff: Duration::from_secs(10),
+            ..policy()
+        };
+
+        assert_eq!(policy.backoff(0, &error), Duration::from_secs(7));
+
+        // A `Retry-After` past `max_backoff` is capped
+        let error = InstructorError::Http {
+            status: 503,
+            body: "".to_string(),
+            retry_after: Some(Duration::MAX),
+        };
+        assert_eq!(policy.backoff(0, &error), Duration::from_secs(10));
+
+        let policy = RetryPolicy {
+            respect_retry_after: false,
+            ..policy
+        };
+        assert_eq!(policy.backoff(0, &error), Duration::from_millis(100));
+    }
+
+    #[test]
+    fn test_budgets_are_separate() {
+        let policy = RetryPolicy {
+            max_reasks: 1,
+            max_transport_retries: 1,
+            ..policy()
+        };
+        let mut state = RetryState::new(&policy);
+        let response = BackendResponse::Text("".to_string());
+        let rejected = InstructorError::NoToolCall("".to_string());
+        let overloaded = InstructorError::Http {
+            status: 503,
+            body: "overloaded".to_string(),
+            retry_after: None,
+        };
+
+        assert!(matches!(
+            state.rejected(response.clone(), rejected.clone(), "fix it".to_string()),
+            Ok(NextAttempt::Reask)
+        ));
+        assert!(matches!(
+            state.transport_failed(overloaded.clone()),
+            Ok(NextAttempt::Wait(_))
+        ));
+        assert_eq!(state.reasks().len(), 1);
+
+        match state.transport_failed(overloaded) {
+            Err(InstructorError::RetriesExhausted(attempts)) => assert_eq!(attempts.len(), 3),
+            _ => panic!("Expected the transport budget to run out"),
+        }
+    }
+
+    #[test]
+    fn test_non_retryable_errors_are_returned() {
+        let policy = policy();
+        let mut state = RetryState::new(&policy);
+        let unauthorized = InstructorError::Http {
+            status: 401,
+            body: "invalid api key".to_string(),
+            retry_after: None,
+        };
+
+        assert_eq!(
+            state.transport_failed(unauthorized.clone()).err(),
+            Some(unauthorized)
+        );
+    }
+}
@@ -0,0 +1,144 @@
+//! A tiny HTTP stand-in for provider APIs.
+//!
+//! The server answers each incoming request with the next canned response and records
+//! what it received, so backends can be tested end to end without network access.
+
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use serde_json::Value;
+
+/// Status, extra headers and JSON body of a canned response.
+pub type MockResponse = (u16, Vec<(&'static str, String)>, Value);
+
+/// Status, extra headers, content type and raw body of a canned response.
+type RawResponse = (u16, Vec<(&'static str, String)>, &'static str, String);
+
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub path: String,
+    pub headers: HashMap<String, String>,
+    pub body: Value,
+}
+
+pub struct MockServer {
+    pub url: String,
+    requests: Arc<Mutex<Vec<RecordedRequest>>>,
+}
+
+impl MockServer {
+    /// Starts a server that replies to the n-th request with `responses[n]`.
+    pub fn start(responses: Vec<(u16, Value)>) -> Self {
+        Self::start_with_headers(
+            responses
+                .into_iter()
+                .map(|(status, body)| (status, Vec::new(), body))
+                .collect(),
+        )
+    }
+
+    /// Like [`MockServer::start`], with extra headers on each response.
+    pub fn start_with_headers(responses: Vec<MockResponse>) -> Self {
+        Self::serve(
+            responses
+                .into_iter()
+                .map(|(status, headers, body)| {
+                    (status, headers, "application/json", body.to_string())
+                })
+                .collect(),
+        )
+    }
+
+    /// Starts a server that streams each response as server-sent events, one `data`
+    /// event per value followed by `[DONE]`.
+    pub fn start_sse(responses: Vec<Vec<Value>>) -> Self {
+        Self::serve(
+            responses
+                .into_iter()
+                .map(|events| {
+                    let mut body: String = events
+                        .iter()
+                        .map(|event| format!("data: {}\n\n", event))
+                        .collect();
+                    body.push_str("data: [DONE]\n\n");
+                    (200, Vec::new(), "text/event-stream", body)
+                })
+                .collect(),
+        )
+    }
+
+    fn serve(responses: Vec<RawResponse>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let recorded = Arc::clone(&requests);
+        thread::spawn(move || {
+            for (status, extra_headers, content_type, body) in responses {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let path = request_line
+                    .split_whitespace()
+                    .nth(1)
+                    .unwrap_or_default()
+                    .to_string();
+
+                let mut headers = HashMap::new();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    let line = line.trim_end();
+                    if line.is_empty() {
+                        break;
+                    }
+                    if let Some((key, value)) = line.split_once(':') {
+                        headers.insert(key.trim().to_lowercase(), value.trim().to_string());
+                    }
+                }
+
+                let length: usize = headers
+                    .get("content-length")
+                    .and_then(|length| length.parse().ok())
+                    .unwrap_or(0);
+                let mut request_body = vec![0; length];
+                reader.read_exact(&mut request_body).unwrap();
+
+                recorded.lock().unwrap().push(RecordedRequest {
+                    path,
+                    headers,
+                    body: serde_json::from_slice(&request_body).unwrap_or(Value::Null),
+                });
+
+                let extra_headers: String = extra_headers
+                    .iter()
+                    .map(|(key, value)| format!("{}: {}\r\n", key, value))
+                    .collect();
+                let response = format!(
+                    "HTTP/1.1 {} Mock\r\nContent-Type: {}\r\nContent-Length: {}\r\n{}Connection: close\r\n\r\n{}",
+                    status,
+                    content_type,
+                    body.len(),
+                    extra_headers,
+                    body
+                );
+                let mut stream = reader.into_inner();
+                stream.write_all(response.as_bytes()).unwrap();
+                stream.flush().unwrap();
+            }
+        });
+
+        Self { url, requests }
+    }
+
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
@@ -0,0 +1,272 @@
+extern crate instruct_macros;
+extern crate instruct_macros_types;
+
+mod common;
+
+use instruct_macros::{validate, InstructMacro, ParallelTools};
+use instruct_macros_types::{Parameter, ParameterInfo, StructInfo};
+use instructor_ai::{backends::AnthropicBackend, from_anthropic, InstructorError, Mode};
+
+#[cfg(test)]
+mod tests {
+    use common::MockServer;
+    use openai_api_rs::v1::chat_completion::{self, ChatCompletionRequest};
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+
+    use super::*;
+
+    #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+    #[description("A user mentioned in the text")]
+    struct UserInfo {
+        #[validate(custom = "validate_uppercase")]
+        name: String,
+        age: u8,
+    }
+
+    #[validate]
+    fn validate_uppercase(name: &String) -> Result<String, String> {
+        if name.chars().any(|c| c.is_lowercase()) {
+            return Err(format!("Name '{}' should be entirely in uppercase", name));
+        }
+        Ok(name.to_uppercase())
+    }
+
+    fn tool_use(id: &str, input: serde_json::Value) -> serde_json::Value {
+        json!({
+            "id": "msg_01",
+            "type": "message",
+            "role": "assistant",
+            "model": "claude-3-5-sonnet-20240620",
+            "content": [
+                {"type": "text", "text": "Extracting the user"},
+                {"type": "tool_use", "id": id, "name": "UserInfo", "input": input}
+            ],
+            "stop_reason": "tool_use",
+            "usage": {"input_tokens": 10, "output_tokens": 10}
+        })
+    }
+
+    fn request() -> ChatCompletionRequest {
+        ChatCompletionRequest::new(
+            "claude-3-5-sonnet-20240620".to_string(),
+            vec![
+                chat_completion::ChatCompletionMessage {
+                    role: chat_completion::MessageRole::system,
+                    content: chat_completion::Content::Text(String::from(
+                        "You extract users from text",
+                    )),
+                    name: None,
+                },
+                chat_completion::ChatCompletionMessage {
+                    role: chat_completion::MessageRole::user,
+                    content: chat_completion::Content::Text(String::from(
+                        "John Doe is a 30 year old software engineer",
+                    )),
+                    name: None,
+                },
+            ],
+        )
+    }
+
+    #[test]
+    fn test_from_anthropic() {
+        let server = MockServer::start(vec![(
+            200,
+            tool_use("toolu_01", json!({"name": "JOHN DOE", "age": 30})),
+        )]);
+        let client = AnthropicBackend::new_with_endpoint(
+            format!("{}/v1", server.url),
+            "test-key".to_string(),
+        );
+        let instructor_client = from_anthropic(client);
+
+        let result = instructor_client
+            .chat_completion::<UserInfo>(request(), 3)
+            .unwrap();
+
+        assert_eq!(result.name, "JOHN DOE");
+        assert_eq!(result.age, 30);
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].path, "/v1/messages");
+        assert_eq!(requests[0].headers["x-api-key"], "test-key");
+        assert_eq!(requests[0].headers["anthropic-version"], "2023-06-01");
+
+        let body = &requests[0].body;
+        assert_eq!(body["system"], "You extract users from text");
+        assert_eq!(body["max_tokens"], 4096);
+        assert_eq!(
+            body["messages"],
+            json!([{"role": "user", "content": "John Doe is a 30 year old software engineer"}])
+        );
+        assert_eq!(body["tools"][0]["name"], "UserInfo");
+        assert_eq!(
+            body["tools"][0]["description"],
+            "A user mentioned in the text"
+        );
+        assert_eq!(body["tools"][0]["input_schema"]["type"], "object");
+        assert_eq!(
+            body["tools"][0]["input_schema"]["required"],
+            json!(["name", "age"])
+        );
+        assert_eq!(
+            body["tool_choice"],
+            json!({"type": "tool", "name": "UserInfo"})
+        );
+    }
+
+    #[test]
+    fn test_anthropic_reask_with_tool_result() {
+        let server = MockServer::start(vec![
+            (
+                200,
+                tool_use("toolu_01", json!({"name": "John Doe", "age": 30})),
+            ),
+            (
+                200,
+                tool_use("toolu_02", json!({"name": "JOHN DOE", "age": 30})),
+            ),
+        ]);
+        let client = AnthropicBackend::new_with_endpoint(
+            format!("{}/v1", server.url),
+            "test-key".to_string(),
+        );
+        let instructor_client = from_anthropic(client);
+
+        let result = instructor_client
+            .chat_completion::<UserInfo>(request(), 3)
+            .unwrap();
+        assert_eq!(result.name, "JOHN DOE");
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+
+        let messages = requests[1].body["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(
+            messages[1],
+            json!({
+                "role": "assistant",
+                "content": [{
+                    "type": "tool_use",
+                    "id": "toolu_01",
+                    "name": "UserInfo",
+                    "input": {"name": "John Doe", "age": 30}
+                }]
+            })
+        );
+
+        let tool_result = &messages[2]["content"][0];
+        assert_eq!(messages[2]["role"], "user");
+        assert_eq!(tool_result["type"], "tool_result");
+        assert_eq!(tool_result["tool_use_id"], "toolu_01");
+        assert_eq!(tool_result["is_error"], true);
+        assert!(tool_result["content"]
+            .as_str()
+            .unwrap()
+            .contains("should be entirely in uppercase"));
+    }
+
+    #[test]
+    fn test_anthropic_json_mode() {
+        let server = MockServer::start(vec![(
+            200,
+            json!({
+                "id": "msg_01",
+                "type": "message",
+                "role": "assistant",
+                "model": "claude-3-5-sonnet-20240620",
+                "content": [{"type": "text", "text": r#"{"name": "JOHN DOE", "age": 30}"#}],
+                "stop_reason": "end_turn",
+                "usage": {"input_tokens": 10, "output_tokens": 10}
+            }),
+        )]);
+        let client = AnthropicBackend::new_with_endpoint(
+            format!("{}/v1", server.url),
+            "test-key".to_string(),
+        );
+        let instructor_client = from_anthropic(client).with_mode(Mode::Json);
+
+        let result = instructor_client
+            .chat_completion::<UserInfo>(request(), 3)
+            .unwrap();
+        assert_eq!(result.name, "JOHN DOE");
+
+        let body = &server.requests()[0].body;
+        assert!(body.get("tools").is_none());
+        assert!(body.get("tool_choice").is_none());
+        let system = body["system"].as_str().unwrap();
+        assert!(system.starts_with("You extract users from text\n"));
+        assert!(system.contains("json_schema"));
+    }
+
+    #[test]
+    fn test_anthropic_json_schema_mode_is_unsupported() {
+        let client = AnthropicBackend::new_with_endpoint(
+            "http://127.0.0.1:9".to_string(),
+            "key".to_string(),
+        );
+        let instructor_client = from_anthropic(client).with_mode(Mode::JsonSchema);
+
+        let result = instructor_client.chat_completion::<UserInfo>(request(), 3);
+
+        assert_eq!(
+            result.unwrap_err(),
+            InstructorError::UnsupportedMode {
+                backend: "Anthropic".to_string(),
+                mode: Mode::JsonSchema,
+            }
+        );
+    }
+
+    #[test]
+    fn test_anthropic_parallel_tool_calls() {
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        struct Company {
+            name: String,
+        }
+
+        #[derive(ParallelTools, Debug)]
+        enum Entity {
+            User(UserInfo),
+            Company(Company),
+        }
+
+        let server = MockServer::start(vec![(
+            200,
+            json!({
+                "id": "msg_01",
+                "type": "message",
+                "role": "assistant",
+                "model": "claude-3-5-sonnet-20240620",
+                "content": [
+                    {"type": "tool_use", "id": "toolu_1", "name": "User", "input": {"name": "JOHN DOE", "age": 30}},
+                    {"type": "tool_use", "id": "toolu_2", "name": "Company", "input": {"name": "Acme"}}
+                ],
+                "stop_reason": "tool_use",
+                "usage": {"input_tokens": 10, "output_tokens": 10}
+            }),
+        )]);
+        let client = AnthropicBackend::new_with_endpoint(
+            format!("{}/v1", server.url),
+            "test-key".to_string(),
+        );
+        let instructor_client = from_anthropic(client);
+
+        let entities = instructor_client
+            .chat_completion_parallel::<Entity>(request(), 3)
+            .unwrap();
+
+        assert_eq!(entities.len(), 2);
+        assert!(matches!(&entities[0], Entity::User(user) if user.age == 30));
+        assert!(matches!(&entities[1], Entity::Company(company) if company.name == "Acme"));
+
+        let body = &server.requests()[0].body;
+        assert_eq!(body["tool_choice"], json!({"type": "any"}));
+        assert_eq!(body["tools"][0]["name"], "User");
+        assert_eq!(body["tools"][1]["name"], "Company");
+        assert_eq!(body["system"], "You extract users from text");
+    }
+}
@@ -0,0 +1,237 @@
+#![cfg(feature = "async")]
+
+extern crate instruct_macros;
+extern crate instruct_macros_types;
+
+mod common;
+
+use instruct_macros::InstructMacro;
+use instruct_macros_types::{Parameter, ParameterInfo, StructInfo};
+use instructor_ai::backends::{Backend, BackendResponse, Reask};
+use instructor_ai::{from_openai, InstructorClient, InstructorError, Mode, RetryPolicy};
+use openai_api_rs::v1::api::Client;
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::io::{ErrorKind, Read};
+    use std::net::TcpListener;
+    use std::thread;
+    use std::time::Duration;
+
+    use common::MockServer;
+    use openai_api_rs::v1::{
+        chat_completion::{self, ChatCompletionRequest},
+        common::GPT4_O,
+    };
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_chat_completion_async() {
+        let client = Client::new(env::var("OPENAI_API_KEY").unwrap().to_string());
+        let instructor_client = from_openai(client);
+
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        struct UserInfo {
+            #[description("The name of the user")]
+            name: String,
+            #[description("The age of the user")]
+            age: u8,
+        }
+
+        let req = ChatCompletionRequest::new(
+            GPT4_O.to_string(),
+            vec![chat_completion::ChatCompletionMessage {
+                role: chat_completion::MessageRole::user,
+                content: chat_completion::Content::Text(String::from(
+                    "John Doe is a 30 year old software engineer",
+                )),
+                name: None,
+            }],
+        );
+
+        let result = instructor_client
+            .chat_completion_async::<UserInfo>(req, 3)
+            .await
+            .unwrap();
+
+        assert_eq!(result.age, 30);
+        assert_eq!(result.name, "John Doe");
+    }
+
+    fn tool_call_completion(arguments: &str) -> serde_json::Value {
+        json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion",
+            "created": 1721680408,
+            "model": "gpt-4o",
+            "choices": [{
+                "index": 0,
+                "message": {
+                    "role": "assistant",
+                    "tool_calls": [{
+                        "id": "call_1",
+                        "type": "function",
+                        "function": {"name": "UserInfo", "arguments": arguments}
+                    }]
+                },
+                "finish_reason": "tool_calls"
+            }],
+            "usage": {"prompt_tokens": 10, "completion_tokens": 10, "total_tokens": 20},
+            "system_fingerprint": null
+        })
+    }
+
+    #[tokio::test]
+    async fn test_chat_completion_async_retries_and_reasks() {
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        struct UserInfo {
+            name: String,
+            #[validate(range(min = 18))]
+            age: u8,
+        }
+
+        let server = MockServer::start(vec![
+            (429, json!({"error": {"message": "Rate limit reached"}})),
+            (
+                200,
+                tool_call_completion(r#"{"name": "John Doe", "age": 3}"#),
+            ),
+            (
+                200,
+                tool_call_completion(r#"{"name": "John Doe", "age": 30}"#),
+            ),
+        ]);
+        let client = Client::new_with_endpoint(server.url.clone(), "test-key".to_string());
+        let instructor_client = from_openai(client);
+        let policy = RetryPolicy {
+            initial_backoff: Duration::from_millis(50),
+            jitter: false,
+            ..RetryPolicy::with_max_retries(2)
+        };
+
+        let req = ChatCompletionRequest::new(
+            "gpt-4o".to_string(),
+            vec![chat_completion::ChatCompletionMessage {
+                role: chat_completion::MessageRole::user,
+                content: chat_completion::Content::Text(String::from(
+                    "John Doe is a 30 year old software engineer",
+                )),
+                name: None,
+            }],
+        );
+
+        let started = tokio::time::Instant::now();
+        let result = instructor_client
+            .chat_completion_with_policy_async::<UserInfo>(req, &policy)
+            .await
+            .unwrap();
+
+        assert_eq!(result.name, "John Doe");
+        assert_eq!(result.age, 30);
+        // The rate limit is waited out before the request is sent again
+        assert!(started.elapsed() >= Duration::from_millis(50));
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[0].body, requests[1].body);
+        // The validation error is sent back to the model
+        let messages = requests[2].body["messages"].as_array().unwrap();
+        assert!(messages
+            .last()
+            .unwrap()
+            .to_string()
+            .contains("Validation failed for field 'age'"));
+    }
+
+    fn request() -> ChatCompletionRequest {
+        ChatCompletionRequest::new(
+            "gpt-4o".to_string(),
+            vec![chat_completion::ChatCompletionMessage {
+                role: chat_completion::MessageRole::user,
+                content: chat_completion::Content::Text(String::from(
+                    "John Doe is a 30 year old software engineer",
+                )),
+                name: None,
+            }],
+        )
+    }
+
+    /// A backend with only the blocking `chat`.
+    struct BlockingBackend;
+
+    impl Backend for BlockingBackend {
+        fn chat(
+            &self,
+            _req: &ChatCompletionRequest,
+            _response_model: &StructInfo,
+            _mode: Mode,
+            _reasks: &[Reask],
+        ) -> Result<BackendResponse, InstructorError> {
+            Ok(BackendResponse::Json(
+                r#"{"name": "John Doe", "age": 30}"#.to_string(),
+            ))
+        }
+    }
+
+    #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+    struct Person {
+        name: String,
+        age: u8,
+    }
+
+    #[tokio::test]
+    async fn test_backends_without_chat_async_fall_back_to_chat() {
+        let instructor_client = InstructorClient::from_backend(BlockingBackend);
+
+        let result = tokio::spawn(async move {
+            instructor_client
+                .chat_completion_async::<Person>(request(), 1)
+                .await
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(result.name, "John Doe");
+        assert_eq!(result.age, 30);
+    }
+
+    #[tokio::test]
+    async fn test_dropping_the_future_cancels_the_request() {
+        // A provider that never answers, and reports whether the connection was closed
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let provider = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            stream
+                .set_read_timeout(Some(Duration::from_secs(5)))
+                .unwrap();
+            let mut buffer = [0; 1024];
+            loop {
+                match stream.read(&mut buffer) {
+                    Ok(0) => return true,
+                    Ok(_) => continue,
+                    Err(e) if e.kind() == ErrorKind::ConnectionReset => return true,
+                    Err(_) => return false,
+                }
+            }
+        });
+
+        let client = Client::new_with_endpoint(url, "test-key".to_string());
+        let instructor_client = from_openai(client);
+        let result = tokio::time::timeout(
+            Duration::from_millis(200),
+            instructor_client.chat_completion_async::<Person>(request(), 1),
+        )
+        .await;
+
+        assert!(result.is_err());
+        // The connection is closed by the runtime, so wait without blocking it
+        let closed = tokio::task::spawn_blocking(move || provider.join().unwrap());
+        assert!(closed.await.unwrap());
+    }
+}
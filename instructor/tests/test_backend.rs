@@ -0,0 +1,442 @@
+extern crate instruct_macros;
+extern crate instruct_macros_types;
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use instruct_macros::{validate, InstructMacro, ParallelTools};
+use instruct_macros_types::{Parameter, ParameterInfo, StructInfo};
+use instructor_ai::backends::{Backend, BackendResponse, Reask, ToolCall};
+use instructor_ai::{Attempt, InstructorClient, InstructorError, Mode, RetryPolicy};
+use openai_api_rs::v1::chat_completion::ChatCompletionRequest;
+
+/// Replays canned responses and records the re-asks it receives on every call.
+struct MockBackend {
+    responses: Mutex<Vec<Result<BackendResponse, InstructorError>>>,
+    requests: Arc<Mutex<Vec<Vec<Reask>>>>,
+    modes: Arc<Mutex<Vec<Mode>>>,
+}
+
+impl MockBackend {
+    fn new(responses: Vec<BackendResponse>) -> Self {
+        Self::with_results(responses.into_iter().map(Ok).collect())
+    }
+
+    fn with_results(mut responses: Vec<Result<BackendResponse, InstructorError>>) -> Self {
+        responses.reverse();
+        Self {
+            responses: Mutex::new(responses),
+            requests: Arc::new(Mutex::new(Vec::new())),
+            modes: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+}
+
+impl Backend for MockBackend {
+    fn chat(
+        &self,
+        req: &ChatCompletionRequest,
+        response_model: &StructInfo,
+        mode: Mode,
+        reasks: &[Reask],
+    ) -> Result<BackendResponse, InstructorError> {
+        assert_eq!(req.messages.len(), 1);
+        assert!(matches!(
+            response_model.name.as_str(),
+            "UserInfo" | "IterableUserInfo"
+        ));
+        self.modes.lock().unwrap().push(mode);
+        self.requests.lock().unwrap().push(reasks.to_vec());
+        self.responses.lock().unwrap().pop().unwrap()
+    }
+}
+
+fn tool_call(arguments: &str) -> BackendResponse {
+    BackendResponse::ToolCalls(vec![ToolCall {
+        id: "call_1".to_string(),
+        name: "UserInfo".to_string(),
+        arguments: arguments.to_string(),
+    }])
+}
+
+#[cfg(test)]
+mod tests {
+    use openai_api_rs::v1::chat_completion::{self, Content};
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+
+    #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+    struct UserInfo {
+        #[validate(custom = "validate_uppercase")]
+        name: String,
+        age: u8,
+    }
+
+    #[validate]
+    fn validate_uppercase(name: &String) -> Result<String, String> {
+        if name.chars().any(|c| c.is_lowercase()) {
+            return Err(format!("Name '{}' should be entirely in uppercase", name));
+        }
+        Ok(name.to_uppercase())
+    }
+
+    fn request() -> ChatCompletionRequest {
+        ChatCompletionRequest::new(
+            "mock-model".to_string(),
+            vec![chat_completion::ChatCompletionMessage {
+                role: chat_completion::MessageRole::user,
+                content: Content::Text(String::from("John Doe is a 30 year old software engineer")),
+                name: None,
+            }],
+        )
+    }
+
+    #[test]
+    fn test_custom_backend() {
+        let backend = MockBackend::new(vec![tool_call(r#"{"name": "JOHN DOE", "age": 30}"#)]);
+        let requests = Arc::clone(&backend.requests);
+        let instructor_client = InstructorClient::from_backend(backend);
+
+        let result = instructor_client
+            .chat_completion::<UserInfo>(request(), 3)
+            .unwrap();
+
+        assert_eq!(result.name, "JOHN DOE");
+        assert_eq!(result.age, 30);
+        assert_eq!(requests.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_custom_backend_retries_on_validation_error() {
+        let backend = MockBackend::new(vec![
+            tool_call(r#"{"name": "John Doe", "age": 30}"#),
+            BackendResponse::Text("I don't want to call a tool".to_string()),
+            tool_call(r#"{"name": "JOHN DOE", "age": 30}"#),
+        ]);
+        let requests = Arc::clone(&backend.requests);
+        let instructor_client = InstructorClient::from_backend(backend);
+
+        let result = instructor_client
+            .chat_completion::<UserInfo>(request(), 3)
+            .unwrap();
+        assert_eq!(result.name, "JOHN DOE");
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 3);
+        assert!(requests[0].is_empty());
+
+        assert_eq!(requests[1].len(), 1);
+        assert_eq!(
+            requests[1][0].response,
+            tool_call(r#"{"name": "John Doe", "age": 30}"#)
+        );
+        assert!(requests[1][0]
+            .error
+            .contains("should be entirely in uppercase"));
+
+        assert_eq!(requests[2].len(), 2);
+        assert_eq!(
+            requests[2][1].response,
+            BackendResponse::Text("I don't want to call a tool".to_string())
+        );
+        assert!(requests[2][1].error.contains("call a tool"));
+    }
+
+    #[test]
+    fn test_custom_backend_gives_up_after_max_retries() {
+        let backend = MockBackend::new(vec![
+            tool_call(r#"{"name": "John Doe", "age": 30}"#),
+            BackendResponse::Text("I don't want to call a tool".to_string()),
+            tool_call(r#"{"name": "JOHN DOE"}"#),
+        ]);
+        let requests = Arc::clone(&backend.requests);
+        let instructor_client = InstructorClient::from_backend(backend);
+
+        let result = instructor_client.chat_completion::<UserInfo>(request(), 3);
+        assert_eq!(requests.lock().unwrap().len(), 3);
+
+        let attempts = match result {
+            Err(InstructorError::RetriesExhausted(attempts)) => attempts,
+            other => panic!("Expected RetriesExhausted, got {:?}", other),
+        };
+        assert_eq!(attempts.len(), 3);
+
+        assert_eq!(
+            attempts[0].response,
+            Some(tool_call(r#"{"name": "John Doe", "age": 30}"#))
+        );
+        assert!(matches!(
+            &attempts[0].error,
+            InstructorError::Validation(message) if message.contains("should be entirely in uppercase")
+        ));
+        assert_eq!(
+            attempts[1].error,
+            InstructorError::NoToolCall("I don't want to call a tool".to_string())
+        );
+        assert!(matches!(
+            &attempts[2].error,
+            InstructorError::Deserialization { message, content }
+                if message.contains("missing field `age`") && content == r#"{"name": "JOHN DOE"}"#
+        ));
+    }
+
+    fn no_backoff() -> RetryPolicy {
+        RetryPolicy {
+            initial_backoff: Duration::ZERO,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_custom_backend_retries_transport_errors() {
+        let backend = MockBackend::with_results(vec![
+            Err(InstructorError::Transport("connection reset".to_string())),
+            Ok(tool_call(r#"{"name": "JOHN DOE", "age": 30}"#)),
+        ]);
+        let requests = Arc::clone(&backend.requests);
+        let instructor_client = InstructorClient::from_backend(backend);
+
+        let result = instructor_client
+            .chat_completion_with_policy::<UserInfo>(request(), &no_backoff())
+            .unwrap();
+        assert_eq!(result.name, "JOHN DOE");
+
+        // The failed request is sent again as is, without a re-ask
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].is_empty());
+    }
+
+    #[test]
+    fn test_custom_backend_reports_transport_errors_in_attempts() {
+        let overloaded = InstructorError::Http {
+            status: 503,
+            body: "overloaded".to_string(),
+            retry_after: None,
+        };
+        let backend = MockBackend::with_results(vec![
+            Err(InstructorError::Transport("connection reset".to_string())),
+            Err(overloaded.clone()),
+        ]);
+        let instructor_client = InstructorClient::from_backend(backend);
+        let policy = RetryPolicy {
+            max_transport_retries: 1,
+            ..no_backoff()
+        };
+
+        let result = instructor_client.chat_completion_with_policy::<UserInfo>(request(), &policy);
+
+        assert_eq!(
+            result.unwrap_err(),
+            InstructorError::RetriesExhausted(vec![
+                Attempt {
+                    response: None,
+                    error: InstructorError::Transport("connection reset".to_string()),
+                },
+                Attempt {
+                    response: None,
+                    error: overloaded,
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_custom_backend_does_not_retry_client_errors() {
+        let unauthorized = InstructorError::Http {
+            status: 401,
+            body: "invalid api key".to_string(),
+            retry_after: None,
+        };
+        let backend = MockBackend::with_results(vec![Err(unauthorized.clone())]);
+        let requests = Arc::clone(&backend.requests);
+        let instructor_client = InstructorClient::from_backend(backend);
+
+        let result =
+            instructor_client.chat_completion_with_policy::<UserInfo>(request(), &no_backoff());
+
+        assert_eq!(result.unwrap_err(), unauthorized);
+        assert_eq!(requests.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_custom_backend_budgets_are_independent() {
+        let backend = MockBackend::with_results(vec![
+            Ok(tool_call(r#"{"name": "John Doe", "age": 30}"#)),
+            Err(InstructorError::Transport("connection reset".to_string())),
+            Err(InstructorError::Transport("connection reset".to_string())),
+            Ok(tool_call(r#"{"name": "JOHN DOE", "age": 30}"#)),
+        ]);
+        let requests = Arc::clone(&backend.requests);
+        let instructor_client = InstructorClient::from_backend(backend);
+        let policy = RetryPolicy {
+            max_reasks: 1,
+            max_transport_retries: 2,
+            ..no_backoff()
+        };
+
+        let result = instructor_client
+            .chat_completion_with_policy::<UserInfo>(request(), &policy)
+            .unwrap();
+        assert_eq!(result.name, "JOHN DOE");
+
+        // Transport retries keep the re-ask from the rejected answer
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 4);
+        assert!(requests[1..].iter().all(|reasks| reasks.len() == 1));
+    }
+
+    #[test]
+    fn test_enum_response_model_is_an_error() {
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        #[allow(dead_code)]
+        enum Status {
+            Active,
+            Inactive,
+        }
+
+        let backend = MockBackend::new(vec![]);
+        let requests = Arc::clone(&backend.requests);
+        let instructor_client = InstructorClient::from_backend(backend);
+
+        let result = instructor_client.chat_completion::<Status>(request(), 3);
+
+        assert!(matches!(result, Err(InstructorError::ResponseModel(_))));
+        assert!(requests.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_custom_reask_template() {
+        let backend = MockBackend::new(vec![
+            tool_call(r#"{"name": "John Doe", "age": 30}"#),
+            tool_call(r#"{"name": "JOHN DOE", "age": 30}"#),
+        ]);
+        let requests = Arc::clone(&backend.requests);
+        let instructor_client = InstructorClient::from_backend(backend)
+            .with_reask_template("Your answer was rejected ({error}), try again");
+
+        instructor_client
+            .chat_completion::<UserInfo>(request(), 3)
+            .unwrap();
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(
+            requests[1][0].error,
+            "Your answer was rejected (Validation failed for field 'name': Name 'John Doe' should be entirely in uppercase), try again"
+        );
+    }
+
+    #[test]
+    fn test_json_mode_parses_text() {
+        let backend = MockBackend::new(vec![BackendResponse::Text(
+            r#"{"name": "JOHN DOE", "age": 30}"#.to_string(),
+        )]);
+        let modes = Arc::clone(&backend.modes);
+        let instructor_client = InstructorClient::from_backend(backend).with_mode(Mode::Json);
+
+        let result = instructor_client
+            .chat_completion::<UserInfo>(request(), 3)
+            .unwrap();
+
+        assert_eq!(result.name, "JOHN DOE");
+        assert_eq!(*modes.lock().unwrap(), vec![Mode::Json]);
+    }
+
+    #[test]
+    fn test_markdown_json_mode() {
+        let backend = MockBackend::new(vec![
+            BackendResponse::Text(r#"{"name": "JOHN DOE", "age": 30}"#.to_string()),
+            BackendResponse::Text(
+                "Sure!\n```json\n{\"name\": \"JOHN DOE\", \"age\": 30}\n```".to_string(),
+            ),
+        ]);
+        let requests = Arc::clone(&backend.requests);
+        let modes = Arc::clone(&backend.modes);
+        let instructor_client =
+            InstructorClient::from_backend(backend).with_mode(Mode::MarkdownJson);
+
+        let result = instructor_client
+            .chat_completion::<UserInfo>(request(), 3)
+            .unwrap();
+        assert_eq!(result.age, 30);
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1][0].error.contains("No ```json code block found"));
+        assert_eq!(*modes.lock().unwrap(), vec![Mode::MarkdownJson; 2]);
+    }
+
+    #[test]
+    fn test_streaming_is_unsupported_by_default() {
+        let backend = MockBackend::new(vec![]);
+        let instructor_client = InstructorClient::from_backend(backend);
+
+        let result = instructor_client.chat_completion_stream::<UserInfo>(request());
+
+        assert_eq!(result.err(), Some(InstructorError::StreamingUnsupported));
+    }
+
+    #[test]
+    fn test_iterable_validates_each_item() {
+        let backend = MockBackend::new(vec![tool_call(
+            r#"{"tasks": [
+                {"name": "JOHN DOE", "age": 30},
+                {"name": "Jane Doe", "age": 25},
+                {"name": "BOB"},
+                {"name": "ALICE", "age": 40}
+            ]}"#,
+        )]);
+        let instructor_client = InstructorClient::from_backend(backend);
+
+        let results: Vec<Result<UserInfo, InstructorError>> = instructor_client
+            .chat_completion_iterable::<UserInfo>(request())
+            .unwrap()
+            .collect();
+
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[0].as_ref().unwrap().name, "JOHN DOE");
+        assert!(matches!(results[1], Err(InstructorError::Validation(_))));
+        assert!(matches!(
+            results[2],
+            Err(InstructorError::Deserialization { .. })
+        ));
+        assert_eq!(results[3].as_ref().unwrap().age, 40);
+    }
+
+    #[test]
+    fn test_iterable_markdown_json_mode() {
+        let backend = MockBackend::new(vec![BackendResponse::Text(
+            "Found two:\n```json\n{\"tasks\": [{\"name\": \"JOHN\", \"age\": 30}, {\"name\": \"JANE\", \"age\": 25}]}\n```"
+                .to_string(),
+        )]);
+        let instructor_client =
+            InstructorClient::from_backend(backend).with_mode(Mode::MarkdownJson);
+
+        let names: Vec<String> = instructor_client
+            .chat_completion_iterable::<UserInfo>(request())
+            .unwrap()
+            .map(|user| user.unwrap().name)
+            .collect();
+
+        assert_eq!(names, vec!["JOHN", "JANE"]);
+    }
+
+    #[test]
+    fn test_parallel_tools_are_unsupported_by_default() {
+        #[derive(ParallelTools, Debug)]
+        enum Extraction {
+            User(UserInfo),
+        }
+
+        let backend = MockBackend::new(vec![]);
+        let instructor_client = InstructorClient::from_backend(backend);
+
+        let result = instructor_client.chat_completion_parallel::<Extraction>(request(), 3);
+
+        assert_eq!(
+            result.err(),
+            Some(InstructorError::ParallelToolsUnsupported)
+        );
+    }
+}
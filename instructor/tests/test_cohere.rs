@@ -0,0 +1,144 @@
+extern crate instruct_macros;
+extern crate instruct_macros_types;
+
+mod common;
+
+use instruct_macros::InstructMacro;
+use instruct_macros_types::{Parameter, ParameterInfo, StructInfo};
+use instructor_ai::{backends::CohereBackend, from_cohere};
+
+#[cfg(test)]
+mod tests {
+    use common::MockServer;
+    use openai_api_rs::v1::chat_completion::{self, ChatCompletionRequest};
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+
+    use super::*;
+
+    #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+    struct UserInfo {
+        name: String,
+        age: u8,
+    }
+
+    fn tool_call_response(id: &str, arguments: impl Serialize) -> serde_json::Value {
+        json!({
+            "id": "c14c80c3-18eb-4519-9460-6c92edd8cfb4",
+            "finish_reason": "TOOL_CALL",
+            "message": {
+                "role": "assistant",
+                "tool_plan": "I will extract the user from the text.",
+                "tool_calls": [{
+                    "id": id,
+                    "type": "function",
+                    "function": {"name": "UserInfo", "arguments": arguments}
+                }]
+            },
+            "usage": {
+                "billed_units": {"input_tokens": 37, "output_tokens": 21},
+                "tokens": {"input_tokens": 913, "output_tokens": 77}
+            }
+        })
+    }
+
+    fn request() -> ChatCompletionRequest {
+        ChatCompletionRequest::new(
+            "command-r-plus".to_string(),
+            vec![chat_completion::ChatCompletionMessage {
+                role: chat_completion::MessageRole::user,
+                content: chat_completion::Content::Text(String::from(
+                    "John Doe is a 30 year old software engineer",
+                )),
+                name: None,
+            }],
+        )
+        .top_p(0.9)
+    }
+
+    #[test]
+    fn test_from_cohere() {
+        let server = MockServer::start(vec![(
+            200,
+            tool_call_response("UserInfo_0", r#"{"name": "John Doe", "age": 30}"#),
+        )]);
+        let client = CohereBackend::new_with_endpoint(server.url.clone(), "test-key".to_string());
+        let instructor_client = from_cohere(client);
+
+        let result = instructor_client
+            .chat_completion::<UserInfo>(request(), 3)
+            .unwrap();
+
+        assert_eq!(result.name, "John Doe");
+        assert_eq!(result.age, 30);
+
+        let requests = server.requests();
+        assert_eq!(requests[0].path, "/chat");
+        assert_eq!(requests[0].headers["authorization"], "Bearer test-key");
+
+        let body = &requests[0].body;
+        assert_eq!(body["model"], "command-r-plus");
+        assert_eq!(body["p"], 0.9);
+        assert_eq!(body["tool_choice"], "REQUIRED");
+        assert_eq!(body["tools"][0]["function"]["name"], "UserInfo");
+    }
+
+    #[test]
+    fn test_cohere_tool_call_with_object_arguments() {
+        let server = MockServer::start(vec![(
+            200,
+            tool_call_response("UserInfo_0", json!({"name": "John Doe", "age": 30})),
+        )]);
+        let client = CohereBackend::new_with_endpoint(server.url.clone(), "test-key".to_string());
+        let instructor_client = from_cohere(client);
+
+        let result = instructor_client
+            .chat_completion::<UserInfo>(request(), 3)
+            .unwrap();
+
+        assert_eq!(result.name, "John Doe");
+        assert_eq!(result.age, 30);
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[test]
+    fn test_cohere_reask_with_tool_message() {
+        let server = MockServer::start(vec![
+            (
+                200,
+                tool_call_response("UserInfo_0", r#"{"name": "John Doe"}"#),
+            ),
+            (
+                200,
+                tool_call_response("UserInfo_1", r#"{"name": "John Doe", "age": 30}"#),
+            ),
+        ]);
+        let client = CohereBackend::new_with_endpoint(server.url.clone(), "test-key".to_string());
+        let instructor_client = from_cohere(client);
+
+        let result = instructor_client
+            .chat_completion::<UserInfo>(request(), 3)
+            .unwrap();
+        assert_eq!(result.age, 30);
+
+        let messages = server.requests()[1].body["messages"].clone();
+        assert_eq!(messages.as_array().unwrap().len(), 3);
+        assert_eq!(
+            messages[1],
+            json!({
+                "role": "assistant",
+                "tool_calls": [{
+                    "id": "UserInfo_0",
+                    "type": "function",
+                    "function": {"name": "UserInfo", "arguments": r#"{"name": "John Doe"}"#}
+                }]
+            })
+        );
+        assert_eq!(messages[2]["role"], "tool");
+        assert_eq!(messages[2]["tool_call_id"], "UserInfo_0");
+        assert!(messages[2]["content"]
+            .as_str()
+            .unwrap()
+            .contains("missing field `age`"));
+    }
+}
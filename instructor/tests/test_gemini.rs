@@ -0,0 +1,164 @@
+extern crate instruct_macros;
+extern crate instruct_macros_types;
+
+mod common;
+
+use instruct_macros::InstructMacro;
+use instruct_macros_types::{Parameter, ParameterInfo, StructInfo};
+use instructor_ai::{backends::GeminiBackend, from_gemini};
+
+#[cfg(test)]
+mod tests {
+    use common::MockServer;
+    use openai_api_rs::v1::chat_completion::{self, ChatCompletionRequest};
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+
+    use super::*;
+
+    #[derive(InstructMacro, Debug, Serialize, Deserialize, PartialEq)]
+    struct Address {
+        street: String,
+        city: String,
+    }
+
+    #[derive(InstructMacro, Debug, Serialize, Deserialize, PartialEq)]
+    enum SecurityClearance {
+        Low,
+        Medium,
+        High,
+    }
+
+    #[derive(InstructMacro, Debug, Serialize, Deserialize, PartialEq)]
+    #[description("Details about a user")]
+    struct UserDetail {
+        name: String,
+        age: u8,
+        address: Address,
+        clearance: SecurityClearance,
+        nickname: Option<String>,
+    }
+
+    fn fixture(name: &str) -> serde_json::Value {
+        let path = format!(
+            "{}/tests/fixtures/gemini/{}",
+            env!("CARGO_MANIFEST_DIR"),
+            name
+        );
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    fn request() -> ChatCompletionRequest {
+        ChatCompletionRequest::new(
+            "gemini-1.5-flash".to_string(),
+            vec![
+                chat_completion::ChatCompletionMessage {
+                    role: chat_completion::MessageRole::system,
+                    content: chat_completion::Content::Text(String::from(
+                        "Extract the user details",
+                    )),
+                    name: None,
+                },
+                chat_completion::ChatCompletionMessage {
+                    role: chat_completion::MessageRole::user,
+                    content: chat_completion::Content::Text(String::from(
+                        "John Doe is 30 years old, lives at 123 Main St, Anytown, and has a security clearance of High.",
+                    )),
+                    name: None,
+                },
+            ],
+        )
+    }
+
+    #[test]
+    fn test_from_gemini() {
+        let server = MockServer::start(vec![(200, fixture("function_call.json"))]);
+        let client = GeminiBackend::new_with_endpoint(server.url.clone(), "test-key".to_string());
+        let instructor_client = from_gemini(client);
+
+        let result = instructor_client
+            .chat_completion::<UserDetail>(request(), 3)
+            .unwrap();
+
+        assert_eq!(
+            result,
+            UserDetail {
+                name: "John Doe".to_string(),
+                age: 30,
+                address: Address {
+                    street: "123 Main St".to_string(),
+                    city: "Anytown".to_string(),
+                },
+                clearance: SecurityClearance::High,
+                nickname: None,
+            }
+        );
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].path, "/models/gemini-1.5-flash:generateContent");
+        assert_eq!(requests[0].headers["x-goog-api-key"], "test-key");
+
+        let body = &requests[0].body;
+        assert_eq!(
+            body["systemInstruction"],
+            json!({"parts": [{"text": "Extract the user details"}]})
+        );
+        assert_eq!(body["contents"].as_array().unwrap().len(), 1);
+        assert_eq!(
+            body["toolConfig"],
+            json!({"functionCallingConfig": {"mode": "ANY", "allowedFunctionNames": ["UserDetail"]}})
+        );
+
+        let declaration = &body["tools"][0]["functionDeclarations"][0];
+        assert_eq!(declaration["name"], "UserDetail");
+        assert_eq!(declaration["description"], "Details about a user");
+        assert_eq!(declaration["parameters"]["type"], "OBJECT");
+        assert_eq!(
+            declaration["parameters"]["properties"]["nickname"],
+            json!({"type": "STRING", "nullable": true})
+        );
+        assert_eq!(
+            declaration["parameters"]["properties"]["clearance"],
+            json!({"type": "STRING", "format": "enum", "enum": ["Low", "Medium", "High"]})
+        );
+    }
+
+    #[test]
+    fn test_gemini_reask_with_function_response() {
+        let server = MockServer::start(vec![
+            (200, fixture("function_call_missing_field.json")),
+            (200, fixture("function_call.json")),
+        ]);
+        let client = GeminiBackend::new_with_endpoint(server.url.clone(), "test-key".to_string());
+        let instructor_client = from_gemini(client);
+
+        let result = instructor_client
+            .chat_completion::<UserDetail>(request(), 3)
+            .unwrap();
+        assert_eq!(result.age, 30);
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+
+        let contents = requests[1].body["contents"].as_array().unwrap();
+        assert_eq!(contents.len(), 3);
+        assert_eq!(contents[1]["role"], "model");
+        assert_eq!(
+            contents[1]["parts"][0]["functionCall"]["name"],
+            "UserDetail"
+        );
+        assert_eq!(
+            contents[1]["parts"][0]["functionCall"]["args"]["name"],
+            "John Doe"
+        );
+
+        let function_response = &contents[2]["parts"][0]["functionResponse"];
+        assert_eq!(contents[2]["role"], "user");
+        assert_eq!(function_response["name"], "UserDetail");
+        assert!(function_response["response"]["error"]
+            .as_str()
+            .unwrap()
+            .contains("missing field `age`"));
+    }
+}
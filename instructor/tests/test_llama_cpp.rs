@@ -0,0 +1,75 @@
+extern crate instruct_macros;
+extern crate instruct_macros_types;
+
+mod common;
+
+use instruct_macros::InstructMacro;
+use instruct_macros_types::{Parameter, ParameterInfo, StructInfo};
+use instructor_ai::{backends::LlamaCppBackend, from_llama_cpp};
+
+#[cfg(test)]
+mod tests {
+    use common::MockServer;
+    use openai_api_rs::v1::chat_completion::{self, ChatCompletionRequest};
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+
+    use super::*;
+
+    #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+    struct UserInfo {
+        #[description("The name of the user")]
+        name: String,
+        #[description("The age of the user")]
+        age: u8,
+    }
+
+    #[test]
+    fn test_from_llama_cpp() {
+        let server = MockServer::start(vec![(
+            200,
+            json!({
+                "id": "chatcmpl-1",
+                "object": "chat.completion",
+                "created": 1721680000,
+                "model": "llama-3.1-8b-instruct",
+                "choices": [{
+                    "index": 0,
+                    "finish_reason": "stop",
+                    "message": {"role": "assistant", "content": r#"{"name": "John Doe", "age": 30}"#}
+                }]
+            }),
+        )]);
+        let client = LlamaCppBackend::new_with_endpoint(server.url.clone())
+            .with_api_key("local-key".to_string());
+        let instructor_client = from_llama_cpp(client);
+
+        let req = ChatCompletionRequest::new(
+            "llama-3.1-8b-instruct".to_string(),
+            vec![chat_completion::ChatCompletionMessage {
+                role: chat_completion::MessageRole::user,
+                content: chat_completion::Content::Text(String::from(
+                    "John Doe is a 30 year old software engineer",
+                )),
+                name: None,
+            }],
+        );
+
+        let result = instructor_client
+            .chat_completion::<UserInfo>(req, 3)
+            .unwrap();
+
+        assert_eq!(result.name, "John Doe");
+        assert_eq!(result.age, 30);
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].path, "/v1/chat/completions");
+        assert_eq!(requests[0].headers["authorization"], "Bearer local-key");
+
+        let body = &requests[0].body;
+        assert_eq!(body["json_schema"]["type"], "object");
+        assert_eq!(body["json_schema"]["required"], json!(["name", "age"]));
+        assert!(body.get("tools").is_none());
+    }
+}
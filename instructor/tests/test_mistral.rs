@@ -0,0 +1,133 @@
+extern crate instruct_macros;
+extern crate instruct_macros_types;
+
+mod common;
+
+use instruct_macros::InstructMacro;
+use instruct_macros_types::{Parameter, ParameterInfo, StructInfo};
+use instructor_ai::{backends::MistralBackend, from_mistral};
+
+#[cfg(test)]
+mod tests {
+    use common::MockServer;
+    use openai_api_rs::v1::chat_completion::{self, ChatCompletionRequest};
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+
+    use super::*;
+
+    #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+    struct UserInfo {
+        name: String,
+        age: u8,
+    }
+
+    fn tool_call_response(id: &str, arguments: &str) -> serde_json::Value {
+        json!({
+            "id": "cmpl-e5cc70bb28c444948073e77776eb30ef",
+            "object": "chat.completion",
+            "model": "mistral-large-latest",
+            "choices": [{
+                "index": 0,
+                "message": {
+                    "role": "assistant",
+                    "content": "",
+                    "tool_calls": [{
+                        "id": id,
+                        "function": {"name": "UserInfo", "arguments": arguments}
+                    }]
+                },
+                "finish_reason": "tool_calls"
+            }],
+            "usage": {"prompt_tokens": 10, "completion_tokens": 10, "total_tokens": 20}
+        })
+    }
+
+    fn request() -> ChatCompletionRequest {
+        ChatCompletionRequest::new(
+            "mistral-large-latest".to_string(),
+            vec![chat_completion::ChatCompletionMessage {
+                role: chat_completion::MessageRole::user,
+                content: chat_completion::Content::Text(String::from(
+                    "John Doe is a 30 year old software engineer",
+                )),
+                name: None,
+            }],
+        )
+    }
+
+    #[test]
+    fn test_from_mistral() {
+        let server = MockServer::start(vec![(
+            200,
+            tool_call_response("D681PevKs", r#"{"name": "John Doe", "age": 30}"#),
+        )]);
+        let client = MistralBackend::new_with_endpoint(server.url.clone(), "test-key".to_string());
+        let instructor_client = from_mistral(client);
+
+        let result = instructor_client
+            .chat_completion::<UserInfo>(request(), 3)
+            .unwrap();
+
+        assert_eq!(result.name, "John Doe");
+        assert_eq!(result.age, 30);
+
+        let requests = server.requests();
+        assert_eq!(requests[0].path, "/chat/completions");
+        assert_eq!(requests[0].headers["authorization"], "Bearer test-key");
+
+        let body = &requests[0].body;
+        assert_eq!(body["tool_choice"], "any");
+        assert_eq!(body["tools"][0]["type"], "function");
+        assert_eq!(body["tools"][0]["function"]["name"], "UserInfo");
+        assert_eq!(
+            body["tools"][0]["function"]["parameters"]["required"],
+            json!(["name", "age"])
+        );
+    }
+
+    #[test]
+    fn test_mistral_reask_with_tool_message() {
+        let server = MockServer::start(vec![
+            (
+                200,
+                tool_call_response("call_0123456789", r#"{"name": "John Doe"}"#),
+            ),
+            (
+                200,
+                tool_call_response("D681PevKs", r#"{"name": "John Doe", "age": 30}"#),
+            ),
+        ]);
+        let client = MistralBackend::new_with_endpoint(server.url.clone(), "test-key".to_string());
+        let instructor_client = from_mistral(client);
+
+        let result = instructor_client
+            .chat_completion::<UserInfo>(request(), 3)
+            .unwrap();
+        assert_eq!(result.age, 30);
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+
+        let messages = requests[1].body["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 3);
+
+        let tool_call = &messages[1]["tool_calls"][0];
+        let tool_call_id = tool_call["id"].as_str().unwrap();
+        assert_eq!(messages[1]["role"], "assistant");
+        assert_eq!(tool_call_id.len(), 9);
+        assert!(tool_call_id.chars().all(|c| c.is_ascii_alphanumeric()));
+        assert_eq!(
+            tool_call["function"]["arguments"],
+            r#"{"name": "John Doe"}"#
+        );
+
+        assert_eq!(messages[2]["role"], "tool");
+        assert_eq!(messages[2]["name"], "UserInfo");
+        assert_eq!(messages[2]["tool_call_id"], tool_call_id);
+        assert!(messages[2]["content"]
+            .as_str()
+            .unwrap()
+            .contains("missing field `age`"));
+    }
+}
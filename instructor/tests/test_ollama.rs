@@ -0,0 +1,185 @@
+extern crate instruct_macros;
+extern crate instruct_macros_types;
+
+mod common;
+
+use instruct_macros::InstructMacro;
+use instruct_macros_types::{Parameter, ParameterInfo, StructInfo};
+use instructor_ai::{backends::OllamaBackend, from_ollama, InstructorError, RetryPolicy};
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use common::MockServer;
+    use openai_api_rs::v1::chat_completion::{self, ChatCompletionRequest};
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+
+    use super::*;
+
+    #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+    struct UserInfo {
+        #[description("The name of the user")]
+        name: String,
+        #[description("The age of the user")]
+        age: u8,
+    }
+
+    fn chat_response(content: &str) -> serde_json::Value {
+        json!({
+            "model": "llama3.1",
+            "created_at": "2024-07-22T20:33:28.123648Z",
+            "message": {"role": "assistant", "content": content},
+            "done_reason": "stop",
+            "done": true
+        })
+    }
+
+    fn request() -> ChatCompletionRequest {
+        ChatCompletionRequest::new(
+            "llama3.1".to_string(),
+            vec![chat_completion::ChatCompletionMessage {
+                role: chat_completion::MessageRole::user,
+                content: chat_completion::Content::Text(String::from(
+                    "John Doe is a 30 year old software engineer",
+                )),
+                name: None,
+            }],
+        )
+        .temperature(0.0)
+    }
+
+    #[test]
+    fn test_from_ollama() {
+        let server = MockServer::start(vec![(
+            200,
+            chat_response(r#"{"name": "John Doe", "age": 30}"#),
+        )]);
+        let instructor_client = from_ollama(OllamaBackend::new_with_endpoint(server.url.clone()));
+
+        let result = instructor_client
+            .chat_completion::<UserInfo>(request(), 3)
+            .unwrap();
+
+        assert_eq!(result.name, "John Doe");
+        assert_eq!(result.age, 30);
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].path, "/api/chat");
+
+        let body = &requests[0].body;
+        assert_eq!(body["model"], "llama3.1");
+        assert_eq!(body["stream"], false);
+        assert_eq!(body["options"], json!({"temperature": 0.0}));
+        assert_eq!(
+            body["messages"],
+            json!([{"role": "user", "content": "John Doe is a 30 year old software engineer"}])
+        );
+        assert_eq!(body["format"]["type"], "object");
+        assert_eq!(body["format"]["required"], json!(["name", "age"]));
+        assert_eq!(
+            body["format"]["properties"]["name"]["description"],
+            "The name of the user"
+        );
+    }
+
+    #[test]
+    fn test_ollama_retries_invalid_json() {
+        let server = MockServer::start(vec![
+            (200, chat_response(r#"{"name": "John Doe"}"#)),
+            (200, chat_response(r#"{"name": "John Doe", "age": 30}"#)),
+        ]);
+        let instructor_client = from_ollama(OllamaBackend::new_with_endpoint(server.url.clone()));
+
+        let result = instructor_client
+            .chat_completion::<UserInfo>(request(), 3)
+            .unwrap();
+        assert_eq!(result.age, 30);
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+
+        let messages = requests[1].body["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(
+            messages[1],
+            json!({"role": "assistant", "content": r#"{"name": "John Doe"}"#})
+        );
+        assert_eq!(messages[2]["role"], "user");
+        assert!(messages[2]["content"]
+            .as_str()
+            .unwrap()
+            .contains("missing field `age`"));
+    }
+
+    #[test]
+    fn test_ollama_retries_transport_error() {
+        let server = MockServer::start(vec![
+            (500, json!({"error": "model is loading"})),
+            (200, chat_response(r#"{"name": "John Doe", "age": 30}"#)),
+        ]);
+        let instructor_client = from_ollama(OllamaBackend::new_with_endpoint(server.url.clone()));
+        let policy = RetryPolicy {
+            initial_backoff: Duration::ZERO,
+            ..Default::default()
+        };
+
+        let result = instructor_client
+            .chat_completion_with_policy::<UserInfo>(request(), &policy)
+            .unwrap();
+        assert_eq!(result.name, "John Doe");
+
+        // The same conversation is sent again, without a re-ask
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].body, requests[1].body);
+    }
+
+    #[test]
+    fn test_ollama_retry_after() {
+        let server = MockServer::start_with_headers(vec![(
+            429,
+            vec![("Retry-After", "2".to_string())],
+            json!({"error": "too many requests"}),
+        )]);
+        let instructor_client = from_ollama(OllamaBackend::new_with_endpoint(server.url.clone()));
+        let policy = RetryPolicy {
+            max_transport_retries: 0,
+            ..Default::default()
+        };
+
+        let result = instructor_client.chat_completion_with_policy::<UserInfo>(request(), &policy);
+
+        match result {
+            Err(InstructorError::RetriesExhausted(attempts)) => {
+                assert_eq!(attempts.len(), 1);
+                assert_eq!(attempts[0].response, None);
+                assert_eq!(
+                    attempts[0].error.retry_after(),
+                    Some(Duration::from_secs(2))
+                );
+            }
+            other => panic!("Expected RetriesExhausted, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_ollama_client_error() {
+        let server = MockServer::start(vec![(404, json!({"error": "model not found"}))]);
+        let instructor_client = from_ollama(OllamaBackend::new_with_endpoint(server.url.clone()));
+
+        let result = instructor_client.chat_completion::<UserInfo>(request(), 3);
+
+        assert_eq!(
+            result.unwrap_err(),
+            InstructorError::Http {
+                status: 404,
+                body: r#"{"error":"model not found"}"#.to_string(),
+                retry_after: None,
+            }
+        );
+        assert_eq!(server.requests().len(), 1);
+    }
+}
@@ -0,0 +1,665 @@
+extern crate instruct_macros;
+extern crate instruct_macros_types;
+
+mod common;
+
+use instruct_macros::{validate, InstructMacro, ParallelTools};
+use instruct_macros_types::{Parameter, ParameterInfo, StructInfo};
+use instructor_ai::{from_openai, InstructorError, Mode, RetryPolicy};
+use openai_api_rs::v1::api::Client;
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use common::MockServer;
+    use openai_api_rs::v1::chat_completion::{self, ChatCompletionRequest};
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+
+    use super::*;
+
+    #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+    struct UserInfo {
+        #[description("The name of the user")]
+        name: String,
+        #[description("The age of the user")]
+        age: u8,
+    }
+
+    fn completion(choices: serde_json::Value) -> serde_json::Value {
+        json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion",
+            "created": 1721680408,
+            "model": "gpt-4o",
+            "choices": choices,
+            "usage": {"prompt_tokens": 10, "completion_tokens": 10, "total_tokens": 20},
+            "system_fingerprint": null
+        })
+    }
+
+    fn tool_call_choice(arguments: &str) -> serde_json::Value {
+        json!([{
+            "index": 0,
+            "message": {
+                "role": "assistant",
+                "tool_calls": [{
+                    "id": "call_1",
+                    "type": "function",
+                    "function": {"name": "UserInfo", "arguments": arguments}
+                }]
+            },
+            "finish_reason": "tool_calls"
+        }])
+    }
+
+    fn request() -> ChatCompletionRequest {
+        ChatCompletionRequest::new(
+            "gpt-4o".to_string(),
+            vec![chat_completion::ChatCompletionMessage {
+                role: chat_completion::MessageRole::user,
+                content: chat_completion::Content::Text(String::from(
+                    "John Doe is a 30 year old software engineer",
+                )),
+                name: None,
+            }],
+        )
+    }
+
+    #[test]
+    fn test_openai_recovers_from_transport_errors() {
+        let server = MockServer::start(vec![
+            (
+                503,
+                json!({"error": {"message": "The server is overloaded"}}),
+            ),
+            (200, completion(json!([]))),
+            (
+                200,
+                completion(tool_call_choice(r#"{"name": "John Doe", "age": 30}"#)),
+            ),
+        ]);
+        let client = Client::new_with_endpoint(server.url.clone(), "test-key".to_string());
+        let instructor_client = from_openai(client);
+        let policy = RetryPolicy {
+            initial_backoff: Duration::ZERO,
+            ..Default::default()
+        };
+
+        let result = instructor_client
+            .chat_completion_with_policy::<UserInfo>(request(), &policy)
+            .unwrap();
+
+        assert_eq!(result.name, "John Doe");
+        assert_eq!(result.age, 30);
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[test]
+    fn test_openai_malformed_responses_are_errors() {
+        let server = MockServer::start(vec![
+            (200, completion(json!([]))),
+            (
+                200,
+                completion(json!([{
+                    "index": 0,
+                    "message": {"role": "assistant"},
+                    "finish_reason": "tool_calls"
+                }])),
+            ),
+        ]);
+        let client = Client::new_with_endpoint(server.url.clone(), "test-key".to_string());
+        let instructor_client = from_openai(client);
+        let policy = RetryPolicy {
+            max_reasks: 0,
+            max_transport_retries: 1,
+            initial_backoff: Duration::ZERO,
+            ..Default::default()
+        };
+
+        let result = instructor_client.chat_completion_with_policy::<UserInfo>(request(), &policy);
+
+        match result {
+            Err(InstructorError::RetriesExhausted(attempts)) => {
+                assert_eq!(attempts.len(), 2);
+                assert!(matches!(attempts[0].error, InstructorError::Transport(_)));
+                assert_eq!(
+                    attempts[1].error,
+                    InstructorError::NoToolCall(String::new())
+                );
+            }
+            other => panic!("Expected RetriesExhausted, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_openai_status_errors_are_classified() {
+        let server = MockServer::start(vec![(
+            401,
+            json!({"error": {"message": "Incorrect API key provided"}}),
+        )]);
+        let client = Client::new_with_endpoint(server.url.clone(), "test-key".to_string());
+        let instructor_client = from_openai(client);
+
+        let result = instructor_client.chat_completion::<UserInfo>(request(), 3);
+
+        match result {
+            Err(error @ InstructorError::Http { status: 401, .. }) => {
+                assert!(!error.is_retryable());
+            }
+            other => panic!("Expected an HTTP error, got {:?}", other),
+        }
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[test]
+    fn test_openai_reask_with_tool_message() {
+        let server = MockServer::start(vec![
+            (200, completion(tool_call_choice(r#"{"name": "John Doe"}"#))),
+            (
+                200,
+                completion(tool_call_choice(r#"{"name": "John Doe", "age": 30}"#)),
+            ),
+        ]);
+        let client = Client::new_with_endpoint(server.url.clone(), "test-key".to_string());
+        let instructor_client = from_openai(client);
+
+        let result = instructor_client
+            .chat_completion::<UserInfo>(request(), 3)
+            .unwrap();
+        assert_eq!(result.age, 30);
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].path, "/chat/completions");
+        assert_eq!(requests[0].headers["authorization"], "Bearer test-key");
+
+        let body = &requests[0].body;
+        assert_eq!(body["model"], "gpt-4o");
+        assert_eq!(
+            body["tool_choice"],
+            json!({"type": "function", "function": {"name": "UserInfo"}})
+        );
+        assert!(body.get("response_format").is_none());
+        assert_eq!(body["tools"][0]["function"]["name"], "UserInfo");
+        assert_eq!(body["messages"].as_array().unwrap().len(), 1);
+
+        let messages = requests[1].body["messages"].as_array().unwrap().clone();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(
+            messages[1],
+            json!({
+                "role": "assistant",
+                "content": null,
+                "tool_calls": [{
+                    "id": "call_1",
+                    "type": "function",
+                    "function": {"name": "UserInfo", "arguments": r#"{"name": "John Doe"}"#}
+                }]
+            })
+        );
+        assert_eq!(messages[2]["role"], "tool");
+        assert_eq!(messages[2]["tool_call_id"], "call_1");
+        let content = messages[2]["content"].as_str().unwrap();
+        assert!(content.starts_with("Validation Error: "));
+        assert!(content.contains("missing field `age`"));
+    }
+
+    fn content_choice(content: &str) -> serde_json::Value {
+        json!([{
+            "index": 0,
+            "message": {"role": "assistant", "content": content},
+            "finish_reason": "stop"
+        }])
+    }
+
+    #[test]
+    fn test_openai_json_mode() {
+        let server = MockServer::start(vec![(
+            200,
+            completion(content_choice(r#"{"name": "John Doe", "age": 30}"#)),
+        )]);
+        let client = Client::new_with_endpoint(server.url.clone(), "test-key".to_string());
+        let instructor_client = from_openai(client).with_mode(Mode::Json);
+
+        let result = instructor_client
+            .chat_completion::<UserInfo>(request(), 3)
+            .unwrap();
+        assert_eq!(result.name, "John Doe");
+
+        let body = &server.requests()[0].body;
+        assert_eq!(body["response_format"], json!({"type": "json_object"}));
+        assert!(body.get("tools").is_none());
+
+        let messages = body["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0]["role"], "system");
+        let prompt = messages[0]["content"].as_str().unwrap();
+        assert!(prompt.contains("json_schema"));
+        assert!(prompt.contains(r#""age""#));
+    }
+
+    #[test]
+    fn test_openai_json_schema_mode() {
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        struct Contact {
+            #[description("The name of the contact")]
+            name: String,
+            #[description("The email of the contact")]
+            email: Option<String>,
+        }
+
+        let server = MockServer::start(vec![(
+            200,
+            completion(content_choice(r#"{"name": "John Doe", "email": null}"#)),
+        )]);
+        let client = Client::new_with_endpoint(server.url.clone(), "test-key".to_string());
+        let instructor_client = from_openai(client).with_mode(Mode::JsonSchema);
+
+        let result = instructor_client
+            .chat_completion::<Contact>(request(), 3)
+            .unwrap();
+        assert_eq!(result.email, None);
+
+        let body = &server.requests()[0].body;
+        assert_eq!(body["response_format"]["type"], "json_schema");
+        assert_eq!(body["response_format"]["json_schema"]["name"], "Contact");
+        assert_eq!(body["response_format"]["json_schema"]["strict"], true);
+
+        let schema = &body["response_format"]["json_schema"]["schema"];
+        assert_eq!(schema["additionalProperties"], false);
+        assert_eq!(schema["properties"]["name"]["type"], "string");
+        assert_eq!(
+            schema["properties"]["email"]["type"],
+            json!(["string", "null"])
+        );
+        let mut required: Vec<&str> = schema["required"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|name| name.as_str().unwrap())
+            .collect();
+        required.sort();
+        assert_eq!(required, vec!["email", "name"]);
+
+        let messages = body["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 1);
+    }
+
+    #[test]
+    fn test_openai_union_field() {
+        #[derive(InstructMacro, Debug, Serialize, Deserialize, PartialEq)]
+        struct Message {
+            text: String,
+        }
+
+        #[derive(InstructMacro, Debug, Serialize, Deserialize, PartialEq)]
+        #[serde(tag = "kind")]
+        enum Action {
+            Search { query: String },
+            Reply(Message),
+        }
+
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        struct Step {
+            #[description("The next action")]
+            action: Action,
+        }
+
+        let server = MockServer::start(vec![(
+            200,
+            completion(tool_call_choice(
+                r#"{"action": {"kind": "Reply", "text": "Hello John"}}"#,
+            )),
+        )]);
+        let client = Client::new_with_endpoint(server.url.clone(), "test-key".to_string());
+        let instructor_client = from_openai(client);
+
+        let result = instructor_client
+            .chat_completion::<Step>(request(), 3)
+            .unwrap();
+        assert_eq!(
+            result.action,
+            Action::Reply(Message {
+                text: "Hello John".to_string()
+            })
+        );
+
+        let body = &server.requests()[0].body;
+        let action = &body["tools"][0]["function"]["parameters"]["properties"]["action"];
+        assert_eq!(action["description"], "The next action");
+        assert_eq!(
+            action["anyOf"][1]["properties"]["kind"],
+            json!({"type": "string", "enum": ["Reply"]})
+        );
+        assert_eq!(action["anyOf"][1]["required"], json!(["kind", "text"]));
+    }
+
+    #[test]
+    fn test_openai_markdown_json_mode() {
+        let server = MockServer::start(vec![(
+            200,
+            completion(content_choice(
+                "Here is the user:\n```json\n{\"name\": \"John Doe\", \"age\": 30}\n```",
+            )),
+        )]);
+        let client = Client::new_with_endpoint(server.url.clone(), "test-key".to_string());
+        let instructor_client = from_openai(client).with_mode(Mode::MarkdownJson);
+
+        let result = instructor_client
+            .chat_completion::<UserInfo>(request(), 3)
+            .unwrap();
+        assert_eq!(result.age, 30);
+
+        let body = &server.requests()[0].body;
+        assert!(body.get("response_format").is_none());
+        assert!(body.get("tools").is_none());
+        assert!(body["messages"][0]["content"]
+            .as_str()
+            .unwrap()
+            .contains("```json"));
+    }
+
+    fn tool_call_delta(arguments: &str) -> serde_json::Value {
+        json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion.chunk",
+            "created": 1721680408,
+            "model": "gpt-4o",
+            "choices": [{
+                "index": 0,
+                "delta": {"tool_calls": [{"index": 0, "function": {"arguments": arguments}}]},
+                "finish_reason": null
+            }]
+        })
+    }
+
+    #[test]
+    fn test_openai_stream_partials() {
+        let server = MockServer::start_sse(vec![vec![
+            tool_call_delta(""),
+            tool_call_delta(r#"{"na"#),
+            tool_call_delta(r#"me": "Jo"#),
+            tool_call_delta(r#"hn Doe", "#),
+            tool_call_delta(r#""age": 3"#),
+            tool_call_delta(r#"0}"#),
+        ]]);
+        let client = Client::new_with_endpoint(server.url.clone(), "test-key".to_string());
+        let instructor_client = from_openai(client);
+
+        let mut stream = instructor_client
+            .chat_completion_stream::<UserInfo>(request())
+            .unwrap();
+        let partials: Vec<_> = stream.by_ref().map(Result::unwrap).collect();
+        let names: Vec<Option<String>> = partials.iter().map(|p| p.name.clone()).collect();
+        let partials: Vec<serde_json::Value> =
+            partials.into_iter().map(|p| p.into_value()).collect();
+        let user = stream.finish().unwrap();
+
+        assert_eq!(
+            partials,
+            vec![
+                json!({}),
+                json!({"name": "Jo"}),
+                json!({"name": "John Doe"}),
+                json!({"name": "John Doe", "age": 30}),
+            ]
+        );
+        assert_eq!(
+            names,
+            vec![
+                None,
+                Some("Jo".to_string()),
+                Some("John Doe".to_string()),
+                Some("John Doe".to_string()),
+            ]
+        );
+        assert_eq!(user.name, "John Doe");
+        assert_eq!(user.age, 30);
+
+        let body = &server.requests()[0].body;
+        assert_eq!(body["stream"], true);
+        assert_eq!(body["tools"][0]["function"]["name"], "UserInfo");
+    }
+
+    #[test]
+    fn test_openai_stream_validates_the_final_answer() {
+        #[derive(InstructMacro, Debug, Serialize, Deserialize)]
+        struct User {
+            #[validate(custom = "validate_uppercase")]
+            name: String,
+        }
+
+        #[validate]
+        fn validate_uppercase(name: &String) -> Result<String, String> {
+            if name.chars().any(|c| c.is_lowercase()) {
+                return Err(format!("Name '{}' should be entirely in uppercase", name));
+            }
+            Ok(name.to_uppercase())
+        }
+
+        let server = MockServer::start_sse(vec![vec![
+            tool_call_delta(r#"{"name": "#),
+            tool_call_delta(r#""John Doe"}"#),
+        ]]);
+        let client = Client::new_with_endpoint(server.url.clone(), "test-key".to_string());
+        let instructor_client = from_openai(client);
+
+        let mut stream = instructor_client
+            .chat_completion_stream::<User>(request())
+            .unwrap();
+        let partial = stream.next().unwrap().unwrap();
+        assert_eq!(partial.get::<String>("name"), None);
+        assert_eq!(partial.name, None);
+
+        assert_eq!(
+            stream.finish().unwrap_err(),
+            InstructorError::Validation(
+                "Validation failed for field 'name': Name 'John Doe' should be entirely in uppercase"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_openai_stream_json_mode() {
+        let content_delta = |content: &str| json!({"choices": [{"index": 0, "delta": {"content": content}, "finish_reason": null}]});
+        let server = MockServer::start_sse(vec![vec![
+            json!({"choices": [{"index": 0, "delta": {"role": "assistant"}, "finish_reason": null}]}),
+            content_delta(r#"{"name": "John"#),
+            content_delta(r#" Doe", "age": 30}"#),
+        ]]);
+        let client = Client::new_with_endpoint(server.url.clone(), "test-key".to_string());
+        let instructor_client = from_openai(client).with_mode(Mode::Json);
+
+        let stream = instructor_client
+            .chat_completion_stream::<UserInfo>(request())
+            .unwrap();
+        let user = stream.finish().unwrap();
+
+        assert_eq!(user.name, "John Doe");
+        assert_eq!(
+            server.requests()[0].body["response_format"],
+            json!({"type": "json_object"})
+        );
+    }
+
+    #[test]
+    fn test_openai_stream_status_error() {
+        let server = MockServer::start(vec![(
+            401,
+            json!({"error": {"message": "Incorrect API key provided"}}),
+        )]);
+        let client = Client::new_with_endpoint(server.url.clone(), "test-key".to_string());
+        let instructor_client = from_openai(client);
+
+        let result = instructor_client.chat_completion_stream::<UserInfo>(request());
+
+        match result {
+            Err(InstructorError::Http { status, body, .. }) => {
+                assert_eq!(status, 401);
+                assert!(body.contains("Incorrect API key provided"));
+            }
+            other => panic!("Expected an HTTP error, got {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn test_openai_iterable() {
+        let server = MockServer::start_sse(vec![vec![
+            tool_call_delta(r#"{"tasks": [{"name": "John Doe", "#),
+            tool_call_delta(r#""age": 30}, {"name": "Jane"#),
+            tool_call_delta(r#" Doe", "age": 25}]}"#),
+        ]]);
+        let client = Client::new_with_endpoint(server.url.clone(), "test-key".to_string());
+        let instructor_client = from_openai(client);
+
+        let users: Vec<UserInfo> = instructor_client
+            .chat_completion_iterable::<UserInfo>(request())
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(users.len(), 2);
+        assert_eq!(users[1].name, "Jane Doe");
+        assert_eq!(users[1].age, 25);
+
+        let body = &server.requests()[0].body;
+        let function = &body["tools"][0]["function"];
+        assert_eq!(function["name"], "IterableUserInfo");
+        assert_eq!(
+            function["parameters"]["properties"]["tasks"]["type"],
+            "array"
+        );
+        assert_eq!(
+            function["parameters"]["properties"]["tasks"]["items"]["properties"]["age"]["type"],
+            "integer"
+        );
+    }
+
+    #[derive(InstructMacro, Debug, Serialize, Deserialize, PartialEq)]
+    #[description("Looks up the weather in a city")]
+    struct Weather {
+        #[description("The city to look up")]
+        city: String,
+    }
+
+    #[derive(InstructMacro, Debug, Serialize, Deserialize, PartialEq)]
+    #[description("Searches the web")]
+    struct Search {
+        #[description("What to search for")]
+        query: String,
+    }
+
+    #[derive(ParallelTools, Debug, PartialEq)]
+    enum Action {
+        Weather(Weather),
+        Search(Search),
+    }
+
+    fn parallel_choice(calls: &[(&str, &str, &str)]) -> serde_json::Value {
+        let tool_calls: Vec<serde_json::Value> = calls
+            .iter()
+            .map(|(id, name, arguments)| {
+                json!({
+                    "id": id,
+                    "type": "function",
+                    "function": {"name": name, "arguments": arguments}
+                })
+            })
+            .collect();
+        json!([{
+            "index": 0,
+            "message": {"role": "assistant", "tool_calls": tool_calls},
+            "finish_reason": "tool_calls"
+        }])
+    }
+
+    #[test]
+    fn test_openai_parallel_tool_calls() {
+        let server = MockServer::start(vec![(
+            200,
+            completion(parallel_choice(&[
+                ("call_1", "Weather", r#"{"city": "Paris"}"#),
+                ("call_2", "Search", r#"{"query": "Paris museums"}"#),
+                ("call_3", "Weather", r#"{"city": "Rome"}"#),
+            ])),
+        )]);
+        let client = Client::new_with_endpoint(server.url.clone(), "test-key".to_string());
+        let instructor_client = from_openai(client);
+
+        let actions = instructor_client
+            .chat_completion_parallel::<Action>(request(), 3)
+            .unwrap();
+
+        assert_eq!(
+            actions,
+            vec![
+                Action::Weather(Weather {
+                    city: "Paris".to_string()
+                }),
+                Action::Search(Search {
+                    query: "Paris museums".to_string()
+                }),
+                Action::Weather(Weather {
+                    city: "Rome".to_string()
+                }),
+            ]
+        );
+
+        let body = &server.requests()[0].body;
+        assert_eq!(body["tool_choice"], "required");
+        assert_eq!(body["parallel_tool_calls"], true);
+        let tools: Vec<&str> = body["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|tool| tool["function"]["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(tools, vec!["Weather", "Search"]);
+        assert_eq!(
+            body["tools"][1]["function"]["description"],
+            "Searches the web"
+        );
+    }
+
+    #[test]
+    fn test_openai_parallel_tool_calls_reask() {
+        let server = MockServer::start(vec![
+            (
+                200,
+                completion(parallel_choice(&[
+                    ("call_1", "Weather", r#"{"city": "Paris"}"#),
+                    ("call_2", "Lookup", r#"{"query": "Paris museums"}"#),
+                ])),
+            ),
+            (
+                200,
+                completion(parallel_choice(&[
+                    ("call_3", "Weather", r#"{"city": "Paris"}"#),
+                    ("call_4", "Search", r#"{"query": "Paris museums"}"#),
+                ])),
+            ),
+        ]);
+        let client = Client::new_with_endpoint(server.url.clone(), "test-key".to_string());
+        let instructor_client = from_openai(client);
+
+        let actions = instructor_client
+            .chat_completion_parallel::<Action>(request(), 3)
+            .unwrap();
+        assert_eq!(actions.len(), 2);
+
+        let messages = server.requests()[1].body["messages"]
+            .as_array()
+            .unwrap()
+            .clone();
+        assert_eq!(messages.len(), 4);
+        assert_eq!(messages[1]["tool_calls"].as_array().unwrap().len(), 2);
+        assert_eq!(messages[3]["tool_call_id"], "call_2");
+        assert!(messages[3]["content"]
+            .as_str()
+            .unwrap()
+            .contains("Unknown tool Lookup"));
+    }
+}
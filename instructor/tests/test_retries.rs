@@ -24,12 +24,12 @@ mod tests {
         let instructor_client = from_openai(client);
 
         #[derive(InstructMacro, Debug, Serialize, Deserialize)]
-        // This represents a single user
+        /// This represents a single user
         struct UserInfo {
-            // This represents the name of the user
+            /// This represents the name of the user
             #[validate(custom = "validate_uppercase")]
             name: String,
-            // This represents the age of the user
+            /// This represents the age of the user
             age: u8,
         }
 